@@ -25,10 +25,18 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Guilds,
+    #[sea_orm(has_many = "super::channel_daily::Entity")]
+    ChannelDaily,
     #[sea_orm(has_many = "super::messages::Entity")]
     Messages,
 }
 
+impl Related<super::channel_daily::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ChannelDaily.def()
+    }
+}
+
 impl Related<super::guilds::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Guilds.def()
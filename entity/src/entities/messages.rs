@@ -7,14 +7,26 @@ use sea_orm::entity::prelude::*;
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub snowflake: i64,
-    #[sea_orm(column_type = "Text")]
-    pub content: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub content: Option<String>,
     #[sea_orm(column_type = "Float")]
     pub score: f32,
     pub replys_to: Option<i64>,
     pub channel: i64,
     pub user: i64,
     pub timestamp: DateTime,
+    pub length: i32,
+    pub word_count: i32,
+    #[sea_orm(column_type = "Float")]
+    pub engagement: f32,
+    pub reaction_count: i32,
+    pub attachment_count: i32,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub attachment_types: Option<String>,
+    pub link_count: i32,
+    pub code_lines: i32,
+    pub emoji_only: bool,
+    pub mention_count: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -27,12 +39,18 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Channels,
+    #[sea_orm(has_many = "super::message_bigrams::Entity")]
+    MessageBigrams,
+    #[sea_orm(has_many = "super::message_tokens::Entity")]
+    MessageTokens,
+    #[sea_orm(has_many = "super::reactions::Entity")]
+    Reactions,
     #[sea_orm(
         belongs_to = "Entity",
         from = "Column::ReplysTo",
         to = "Column::Snowflake",
         on_update = "Cascade",
-        on_delete = "Cascade"
+        on_delete = "SetNull"
     )]
     SelfRef,
     #[sea_orm(
@@ -51,6 +69,24 @@ impl Related<super::channels::Entity> for Entity {
     }
 }
 
+impl Related<super::message_bigrams::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageBigrams.def()
+    }
+}
+
+impl Related<super::message_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageTokens.def()
+    }
+}
+
+impl Related<super::reactions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reactions.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
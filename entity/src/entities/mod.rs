@@ -2,7 +2,17 @@
 
 pub mod prelude;
 
+pub mod bot_manager_roles;
+pub mod channel_daily;
 pub mod channels;
+pub mod guild_settings;
+pub mod guild_stopwords;
 pub mod guilds;
+pub mod message_bigrams;
+pub mod message_tokens;
 pub mod messages;
+pub mod reactions;
+pub mod score_adjustments;
+pub mod user_daily;
 pub mod users;
+pub mod voice_sessions;
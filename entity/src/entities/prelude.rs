@@ -1,6 +1,16 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.0.0-rc.5
 
+pub use super::bot_manager_roles::Entity as BotManagerRoles;
+pub use super::channel_daily::Entity as ChannelDaily;
 pub use super::channels::Entity as Channels;
+pub use super::guild_settings::Entity as GuildSettings;
+pub use super::guild_stopwords::Entity as GuildStopwords;
 pub use super::guilds::Entity as Guilds;
+pub use super::message_bigrams::Entity as MessageBigrams;
+pub use super::message_tokens::Entity as MessageTokens;
 pub use super::messages::Entity as Messages;
+pub use super::reactions::Entity as Reactions;
+pub use super::score_adjustments::Entity as ScoreAdjustments;
+pub use super::user_daily::Entity as UserDaily;
 pub use super::users::Entity as Users;
+pub use super::voice_sessions::Entity as VoiceSessions;
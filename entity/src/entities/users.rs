@@ -27,6 +27,12 @@ pub enum Relation {
     Guilds,
     #[sea_orm(has_many = "super::messages::Entity")]
     Messages,
+    #[sea_orm(has_many = "super::score_adjustments::Entity")]
+    ScoreAdjustments,
+    #[sea_orm(has_many = "super::user_daily::Entity")]
+    UserDaily,
+    #[sea_orm(has_many = "super::voice_sessions::Entity")]
+    VoiceSessions,
 }
 
 impl Related<super::guilds::Entity> for Entity {
@@ -41,4 +47,22 @@ impl Related<super::messages::Entity> for Entity {
     }
 }
 
+impl Related<super::score_adjustments::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ScoreAdjustments.def()
+    }
+}
+
+impl Related<super::user_daily::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserDaily.def()
+    }
+}
+
+impl Related<super::voice_sessions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::VoiceSessions.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
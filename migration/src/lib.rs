@@ -4,6 +4,19 @@ mod m20220101_000001_create_table;
 mod m20231013_004433_snowflake_primary;
 mod m20231015_012152_float_score;
 mod m20231016_192446_time;
+mod m20261019_120000_content_retention;
+mod m20261019_130000_score_adjustments;
+mod m20261019_140000_bot_manager_roles;
+mod m20261019_150000_engagement;
+mod m20261019_160000_reactions;
+mod m20261019_170000_voice_sessions;
+mod m20261019_180000_message_content_features;
+mod m20261019_190000_stopwords;
+mod m20261019_200000_message_bigrams;
+mod m20261019_210000_user_daily;
+mod m20261019_220000_channel_daily;
+mod m20261019_230000_query_indexes;
+mod m20261019_231000_consolidate_schema;
 
 pub struct Migrator;
 
@@ -15,6 +28,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20231013_004433_snowflake_primary::Migration),
             Box::new(m20231015_012152_float_score::Migration),
             Box::new(m20231016_192446_time::Migration),
+            Box::new(m20261019_120000_content_retention::Migration),
+            Box::new(m20261019_130000_score_adjustments::Migration),
+            Box::new(m20261019_140000_bot_manager_roles::Migration),
+            Box::new(m20261019_150000_engagement::Migration),
+            Box::new(m20261019_160000_reactions::Migration),
+            Box::new(m20261019_170000_voice_sessions::Migration),
+            Box::new(m20261019_180000_message_content_features::Migration),
+            Box::new(m20261019_190000_stopwords::Migration),
+            Box::new(m20261019_200000_message_bigrams::Migration),
+            Box::new(m20261019_210000_user_daily::Migration),
+            Box::new(m20261019_220000_channel_daily::Migration),
+            Box::new(m20261019_230000_query_indexes::Migration),
+            Box::new(m20261019_231000_consolidate_schema::Migration),
         ]
     }
 }
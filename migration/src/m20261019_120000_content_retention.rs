@@ -0,0 +1,170 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .modify_column(ColumnDef::new(Messages::Content).text().null())
+                    .add_column(
+                        ColumnDef::new(Messages::Length)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Messages::WordCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildSettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildSettings::Guild)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildSettings::RetentionDays)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(GuildSettings::RetentionTruncate)
+                            .integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageTokens::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageTokens::Message)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MessageTokens::Token).text().not_null())
+                    .col(ColumnDef::new(MessageTokens::Count).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(MessageTokens::Message)
+                            .col(MessageTokens::Token),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_message_tokens_message")
+                            .from(MessageTokens::Table, MessageTokens::Message)
+                            .to(Messages::Table, Messages::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // backfill the features for messages that were stored before this migration
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"UPDATE messages SET
+                length = char_length(content),
+                word_count = coalesce(array_length(regexp_split_to_array(btrim(content), '\s+'), 1), 0)
+            WHERE content IS NOT NULL AND btrim(content) <> ''"#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"INSERT INTO message_tokens (message, token, count)
+            SELECT snowflake, lower(word), count(*)
+            FROM messages, regexp_split_to_table(btrim(content), '\s+') AS word
+            WHERE content IS NOT NULL AND word <> ''
+            GROUP BY snowflake, lower(word)
+            ON CONFLICT DO NOTHING"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(MessageTokens::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(GuildSettings::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE messages SET content = '' WHERE content IS NULL")
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .modify_column(ColumnDef::new(Messages::Content).text().not_null())
+                    .drop_column(Messages::Length)
+                    .drop_column(Messages::WordCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    Snowflake,
+    Content,
+    Length,
+    WordCount,
+}
+
+#[derive(Iden)]
+enum GuildSettings {
+    Table,
+    Guild,
+    RetentionDays,
+    RetentionTruncate,
+}
+
+#[derive(Iden)]
+enum MessageTokens {
+    Table,
+    Message,
+    Token,
+    Count,
+}
@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ScoreAdjustments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::Guild)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::User)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::Moderator)
+                            .big_integer()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(ScoreAdjustments::Delta).float().not_null())
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::MessageDelta)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(ScoreAdjustments::Source).text().not_null())
+                    .col(ColumnDef::new(ScoreAdjustments::Batch).text().null())
+                    .col(ColumnDef::new(ScoreAdjustments::Reason).text().null())
+                    .col(
+                        ColumnDef::new(ScoreAdjustments::Timestamp)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_score_adjustments_user")
+                            .from(ScoreAdjustments::Table, ScoreAdjustments::User)
+                            .to(Users::Table, Users::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ScoreAdjustments::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Snowflake,
+}
+
+#[derive(Iden)]
+enum ScoreAdjustments {
+    Table,
+    Id,
+    Guild,
+    User,
+    Moderator,
+    Delta,
+    MessageDelta,
+    Source,
+    Batch,
+    Reason,
+    Timestamp,
+}
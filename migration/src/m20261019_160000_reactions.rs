@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reactions::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Reactions::Message).big_integer().not_null())
+                    .col(ColumnDef::new(Reactions::User).big_integer().not_null())
+                    .col(ColumnDef::new(Reactions::Emoji).text().not_null())
+                    .col(ColumnDef::new(Reactions::Timestamp).timestamp().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(Reactions::Message)
+                            .col(Reactions::User)
+                            .col(Reactions::Emoji),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_reactions_message")
+                            .from(Reactions::Table, Reactions::Message)
+                            .to(Messages::Table, Messages::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_reactions_user")
+                    .table(Reactions::Table)
+                    .col(Reactions::User)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Reactions::Table).if_exists().to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Reactions {
+    Table,
+    Message,
+    User,
+    Emoji,
+    Timestamp,
+}
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    Snowflake,
+}
@@ -0,0 +1,150 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(VoiceSessions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(VoiceSessions::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key()
+                            .auto_increment(),
+                    )
+                    .col(
+                        ColumnDef::new(VoiceSessions::Guild)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(VoiceSessions::User).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(VoiceSessions::Channel)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VoiceSessions::StartedAt)
+                            .timestamp()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(VoiceSessions::EndedAt).timestamp().null())
+                    .col(
+                        ColumnDef::new(VoiceSessions::Muted)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(VoiceSessions::Deafened)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(VoiceSessions::ActiveSeconds)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(VoiceSessions::Score)
+                            .float()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_voice_sessions_user")
+                            .from(VoiceSessions::Table, VoiceSessions::User)
+                            .to(Users::Table, Users::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_voice_sessions_guild_user")
+                    .table(VoiceSessions::Table)
+                    .col(VoiceSessions::Guild)
+                    .col(VoiceSessions::User)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .add_column(
+                        ColumnDef::new(GuildSettings::VoicePointsPerMinute)
+                            .float()
+                            .not_null()
+                            .default(5.0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .drop_column(GuildSettings::VoicePointsPerMinute)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(VoiceSessions::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum VoiceSessions {
+    Table,
+    Id,
+    Guild,
+    User,
+    Channel,
+    StartedAt,
+    EndedAt,
+    Muted,
+    Deafened,
+    ActiveSeconds,
+    Score,
+}
+
+#[derive(Iden)]
+enum GuildSettings {
+    Table,
+    VoicePointsPerMinute,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Snowflake,
+}
@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(
+                        ColumnDef::new(Messages::AttachmentCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(Messages::AttachmentTypes).text().null())
+                    .add_column(
+                        ColumnDef::new(Messages::LinkCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Messages::CodeLines)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(
+                        ColumnDef::new(Messages::EmojiOnly)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(Messages::MentionCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .add_column(ColumnDef::new(GuildSettings::Scoring).json_binary().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        // links can be recovered from the content that is still stored, attachments and
+        // mentions were never stored so old messages keep 0
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"UPDATE messages SET link_count = (
+                SELECT count(*) FROM regexp_split_to_table(content, '\s+') AS word
+                WHERE word ~ '^https?://'
+            ) WHERE content IS NOT NULL"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .drop_column(GuildSettings::Scoring)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::AttachmentCount)
+                    .drop_column(Messages::AttachmentTypes)
+                    .drop_column(Messages::LinkCount)
+                    .drop_column(Messages::CodeLines)
+                    .drop_column(Messages::EmojiOnly)
+                    .drop_column(Messages::MentionCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    AttachmentCount,
+    AttachmentTypes,
+    LinkCount,
+    CodeLines,
+    EmojiOnly,
+    MentionCount,
+}
+
+#[derive(Iden)]
+enum GuildSettings {
+    Table,
+    Scoring,
+}
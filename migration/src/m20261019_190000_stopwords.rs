@@ -0,0 +1,86 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(GuildStopwords::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(GuildStopwords::Guild)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(GuildStopwords::Word).text().not_null())
+                    .col(
+                        ColumnDef::new(GuildStopwords::Removed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(GuildStopwords::Guild)
+                            .col(GuildStopwords::Word),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .add_column(
+                        ColumnDef::new(GuildSettings::StopwordLanguages)
+                            .text()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(GuildSettings::Table)
+                    .drop_column(GuildSettings::StopwordLanguages)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(GuildStopwords::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum GuildStopwords {
+    Table,
+    Guild,
+    Word,
+    Removed,
+}
+
+#[derive(Iden)]
+enum GuildSettings {
+    Table,
+    StopwordLanguages,
+}
@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageBigrams::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(MessageBigrams::Message)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(MessageBigrams::Bigram).text().not_null())
+                    .col(ColumnDef::new(MessageBigrams::Count).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(MessageBigrams::Message)
+                            .col(MessageBigrams::Bigram),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_message_bigrams_message")
+                            .from(MessageBigrams::Table, MessageBigrams::Message)
+                            .to(Messages::Table, Messages::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        // tokens stored before the tokenizer still have markup and punctuation, normalize them
+        // the same way so they can be grouped in SQL
+        db.execute_unprepared(
+            r#"CREATE TEMPORARY TABLE normalized_tokens AS
+            SELECT message, token, sum(count)::int AS count FROM (
+                SELECT message,
+                    lower(regexp_replace(replace(token, '||', ''), '^[^[:alnum:]]+|[^[:alnum:]]+$', '', 'g')) AS token,
+                    count
+                FROM message_tokens
+                WHERE token !~ '^<.*>$' AND token NOT LIKE '%://%'
+            ) tokens
+            WHERE token <> ''
+            GROUP BY message, token"#,
+        )
+        .await?;
+        db.execute_unprepared("DELETE FROM message_tokens").await?;
+        db.execute_unprepared(
+            "INSERT INTO message_tokens (message, token, count) SELECT message, token, count FROM normalized_tokens",
+        )
+        .await?;
+        db.execute_unprepared("DROP TABLE normalized_tokens")
+            .await?;
+
+        // bigrams of the content that is still stored, code blocks aren't skipped here
+        db.execute_unprepared(
+            r#"INSERT INTO message_bigrams (message, bigram, count)
+            SELECT snowflake, bigram, count(*) FROM (
+                SELECT snowflake, word || ' ' || lead(word) OVER (PARTITION BY snowflake ORDER BY n) AS bigram
+                FROM (
+                    SELECT m.snowflake, w.n,
+                        lower(regexp_replace(replace(w.word, '||', ''), '^[^[:alnum:]]+|[^[:alnum:]]+$', '', 'g')) AS word
+                    FROM messages m, regexp_split_to_table(m.content, '\s+') WITH ORDINALITY AS w(word, n)
+                    WHERE m.content IS NOT NULL AND w.word !~ '^<.*>$' AND w.word NOT LIKE '%://%'
+                ) words
+                WHERE word <> ''
+            ) pairs
+            WHERE bigram IS NOT NULL
+            GROUP BY snowflake, bigram"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(MessageBigrams::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum MessageBigrams {
+    Table,
+    Message,
+    Bigram,
+    Count,
+}
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    Snowflake,
+}
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserDaily::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(UserDaily::User).big_integer().not_null())
+                    .col(ColumnDef::new(UserDaily::Date).date().not_null())
+                    .col(ColumnDef::new(UserDaily::Guild).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(UserDaily::Score)
+                            .float()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .col(
+                        ColumnDef::new(UserDaily::MessageCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .primary_key(Index::create().col(UserDaily::User).col(UserDaily::Date))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_user_daily_user")
+                            .from(UserDaily::Table, UserDaily::User)
+                            .to(Users::Table, Users::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_daily_guild_date")
+                    .table(UserDaily::Table)
+                    .col(UserDaily::Guild)
+                    .col(UserDaily::Date)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r#"INSERT INTO user_daily ("user", date, guild, score, message_count)
+                SELECT m."user", m.timestamp::date, c.guild, sum(m.score), count(*)
+                FROM messages m JOIN channels c ON c.snowflake = m.channel
+                GROUP BY m."user", m.timestamp::date, c.guild
+                ON CONFLICT ("user", date) DO UPDATE
+                SET score = user_daily.score + excluded.score,
+                    message_count = user_daily.message_count + excluded.message_count"#,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserDaily::Table).if_exists().to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum UserDaily {
+    Table,
+    User,
+    Date,
+    Guild,
+    Score,
+    MessageCount,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Snowflake,
+}
@@ -0,0 +1,148 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserDaily::Table)
+                    .add_column(
+                        ColumnDef::new(UserDaily::Chars)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChannelDaily::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChannelDaily::Channel)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ChannelDaily::Date).date().not_null())
+                    .col(ColumnDef::new(ChannelDaily::Guild).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(ChannelDaily::Score)
+                            .float()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .col(
+                        ColumnDef::new(ChannelDaily::MessageCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ChannelDaily::Chars)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(ChannelDaily::Channel)
+                            .col(ChannelDaily::Date),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("FK_channel_daily_channel")
+                            .from(ChannelDaily::Table, ChannelDaily::Channel)
+                            .to(Channels::Table, Channels::Snowflake)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_channel_daily_guild_date")
+                    .table(ChannelDaily::Table)
+                    .col(ChannelDaily::Guild)
+                    .col(ChannelDaily::Date)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            r#"UPDATE user_daily SET chars = totals.chars
+            FROM (
+                SELECT "user", timestamp::date AS date, sum(length)::bigint AS chars
+                FROM messages GROUP BY "user", timestamp::date
+            ) totals
+            WHERE user_daily."user" = totals."user" AND user_daily.date = totals.date"#,
+        )
+        .await?;
+
+        db.execute_unprepared(
+            r#"INSERT INTO channel_daily (channel, date, guild, score, message_count, chars)
+            SELECT m.channel, m.timestamp::date, c.guild, sum(m.score), count(*), sum(m.length)
+            FROM messages m JOIN channels c ON c.snowflake = m.channel
+            GROUP BY m.channel, m.timestamp::date, c.guild"#,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ChannelDaily::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserDaily::Table)
+                    .drop_column(UserDaily::Chars)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum UserDaily {
+    Table,
+    Chars,
+}
+
+#[derive(Iden)]
+enum ChannelDaily {
+    Table,
+    Channel,
+    Date,
+    Guild,
+    Score,
+    MessageCount,
+    Chars,
+}
+
+#[derive(Iden)]
+enum Channels {
+    Table,
+    Snowflake,
+}
@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Indexes for the queries run on every message and stats command, see
+/// `rank_core/src/repository/postgres/query_plans.rs` for the queries they are checked against
+fn indexes() -> Vec<(&'static str, IndexCreateStatement)> {
+    vec![
+        // a user's messages in a time range: /stats, /compare, scoped stats with a user
+        named(
+            "idx_messages_user_timestamp",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::User)
+                .col(Messages::Timestamp),
+        ),
+        // a user's most recent messages: handle_message's last five
+        named(
+            "idx_messages_user_snowflake",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::User)
+                .col(Messages::Snowflake),
+        ),
+        // a user's messages in one channel in a time range: /stats per channel
+        named(
+            "idx_messages_user_channel_timestamp",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::User)
+                .col(Messages::Channel)
+                .col(Messages::Timestamp),
+        ),
+        // a channel's messages in a time range: /channelstats, /serverstats
+        named(
+            "idx_messages_channel_timestamp",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::Channel)
+                .col(Messages::Timestamp),
+        ),
+        // every message in a time range: retention, exports
+        named(
+            "idx_messages_timestamp",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::Timestamp),
+        ),
+        // replies to a message: engagement
+        named(
+            "idx_messages_replys_to",
+            Index::create()
+                .table(Messages::Table)
+                .col(Messages::ReplysTo),
+        ),
+        // users of a guild by score: /leaderboard, ranks
+        named(
+            "idx_users_guild_score",
+            Index::create()
+                .table(Users::Table)
+                .col(Users::Guild)
+                .col(Users::Score),
+        ),
+        // channels of a guild by score: top channels
+        named(
+            "idx_channels_guild_score",
+            Index::create()
+                .table(Channels::Table)
+                .col(Channels::Guild)
+                .col(Channels::Score),
+        ),
+    ]
+}
+
+/// Name an index, `down` drops them by name
+fn named(
+    name: &'static str,
+    index: &mut IndexCreateStatement,
+) -> (&'static str, IndexCreateStatement) {
+    (name, index.name(name).if_not_exists().to_owned())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (_, index) in indexes() {
+            manager.create_index(index).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (name, _) in indexes() {
+            manager
+                .drop_index(Index::drop().name(name).if_exists().to_owned())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Messages {
+    Table,
+    Snowflake,
+    ReplysTo,
+    Channel,
+    User,
+    Timestamp,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Guild,
+    Score,
+}
+
+#[derive(Iden)]
+enum Channels {
+    Table,
+    Guild,
+    Score,
+}
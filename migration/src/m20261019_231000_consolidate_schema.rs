@@ -0,0 +1,243 @@
+use crate::sea_orm::Statement;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+// Databases created before the snowflake primary keys can be missing foreign keys or still have
+// `integer` snowflake columns, depending on which version of the older migrations they ran.
+// This brings them in line with the entities and does nothing on a database that already is.
+
+/// Columns holding snowflakes, these need to be `bigint`
+const SNOWFLAKE_COLUMNS: [(&str, &str); 9] = [
+    ("guilds", "snowflake"),
+    ("channels", "snowflake"),
+    ("channels", "guild"),
+    ("users", "snowflake"),
+    ("users", "guild"),
+    ("messages", "snowflake"),
+    ("messages", "channel"),
+    ("messages", "user"),
+    ("messages", "replys_to"),
+];
+
+struct ForeignKeyDef {
+    name: &'static str,
+    table: &'static str,
+    column: &'static str,
+    to_table: &'static str,
+    to_column: &'static str,
+    /// A deleted parent sets the column to null instead of deleting the row
+    nullable: bool,
+}
+
+impl ForeignKeyDef {
+    fn on_delete(&self) -> ForeignKeyAction {
+        if self.nullable {
+            // a reply outlives the message it replied to
+            ForeignKeyAction::SetNull
+        } else {
+            ForeignKeyAction::Cascade
+        }
+    }
+}
+
+const FOREIGN_KEYS: [ForeignKeyDef; 5] = [
+    ForeignKeyDef {
+        name: "FK_channels_guild",
+        table: "channels",
+        column: "guild",
+        to_table: "guilds",
+        to_column: "snowflake",
+        nullable: false,
+    },
+    ForeignKeyDef {
+        name: "FK_users_guild",
+        table: "users",
+        column: "guild",
+        to_table: "guilds",
+        to_column: "snowflake",
+        nullable: false,
+    },
+    ForeignKeyDef {
+        name: "FK_messages_channels",
+        table: "messages",
+        column: "channel",
+        to_table: "channels",
+        to_column: "snowflake",
+        nullable: false,
+    },
+    ForeignKeyDef {
+        name: "FK_messages_users",
+        table: "messages",
+        column: "user",
+        to_table: "users",
+        to_column: "snowflake",
+        nullable: false,
+    },
+    ForeignKeyDef {
+        name: "FK_messages_replys_to",
+        table: "messages",
+        column: "replys_to",
+        to_table: "messages",
+        to_column: "snowflake",
+        nullable: true,
+    },
+];
+
+/// Foreign keys on a column, with whether they point at the right table, do $4 on delete and
+/// cascade on update
+const FOREIGN_KEYS_ON_SQL: &str = r#"
+SELECT c.conname::text AS "name",
+    c.confrelid::regclass::text = $3 AND c.confdeltype = $4 AND c.confupdtype = 'c' AS "matches"
+FROM pg_constraint c
+JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+WHERE c.contype = 'f' AND c.conrelid::regclass::text = $1 AND a.attname = $2
+"#;
+
+async fn column_type(
+    manager: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+) -> Result<Option<String>, DbErr> {
+    let row = manager
+        .get_connection()
+        .query_one(Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            r#"SELECT data_type::text AS "data_type" FROM information_schema.columns
+            WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2"#,
+            [table.into(), column.into()],
+        ))
+        .await?;
+    row.map(|row| row.try_get("", "data_type")).transpose()
+}
+
+async fn fix_foreign_key(manager: &SchemaManager<'_>, fk: &ForeignKeyDef) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+    let existing = db
+        .query_all(Statement::from_sql_and_values(
+            manager.get_database_backend(),
+            FOREIGN_KEYS_ON_SQL,
+            [
+                fk.table.into(),
+                fk.column.into(),
+                fk.to_table.into(),
+                if fk.nullable { "n" } else { "c" }.into(),
+            ],
+        ))
+        .await?;
+
+    let mut found = false;
+    for row in existing {
+        let name: String = row.try_get("", "name")?;
+        if row.try_get("", "matches")? {
+            found = true;
+        } else {
+            manager
+                .drop_foreign_key(
+                    ForeignKey::drop()
+                        .name(name)
+                        .table(Alias::new(fk.table))
+                        .to_owned(),
+                )
+                .await?;
+        }
+    }
+    if found {
+        return Ok(());
+    }
+
+    // rows whose parent is already gone can't get the foreign key
+    let orphaned = format!(
+        r#"FROM "{table}" WHERE "{column}" IS NOT NULL AND NOT EXISTS (SELECT 1 FROM "{to_table}" p WHERE p."{to_column}" = "{table}"."{column}")"#,
+        table = fk.table,
+        column = fk.column,
+        to_table = fk.to_table,
+        to_column = fk.to_column
+    );
+    let orphans: i64 = db
+        .query_one(Statement::from_string(
+            manager.get_database_backend(),
+            format!(r#"SELECT count(*) AS "orphans" {}"#, orphaned),
+        ))
+        .await?
+        .map(|row| row.try_get("", "orphans"))
+        .transpose()?
+        .unwrap_or(0);
+
+    if orphans > 0 {
+        if fk.nullable {
+            // the same the foreign key would have done when the parent was deleted
+            println!(
+                "Setting {}.{} to null on {} rows pointing at a missing {}",
+                fk.table, fk.column, orphans, fk.to_table
+            );
+            db.execute_unprepared(&format!(
+                r#"UPDATE "{table}" SET "{column}" = NULL WHERE ctid IN (SELECT ctid {orphaned})"#,
+                table = fk.table,
+                column = fk.column,
+                orphaned = orphaned
+            ))
+            .await?;
+        } else {
+            // never delete data on boot, the operator has to look at these first
+            return Err(DbErr::Migration(format!(
+                "{} rows of {} point at a missing {} so the foreign key on {}.{} can't be \
+                created. Back up the database, remove them with `DELETE {}` and migrate again",
+                orphans, fk.table, fk.to_table, fk.table, fk.column, orphaned
+            )));
+        }
+    }
+
+    manager
+        .create_foreign_key(
+            ForeignKey::create()
+                .name(fk.name)
+                .from(Alias::new(fk.table), Alias::new(fk.column))
+                .to(Alias::new(fk.to_table), Alias::new(fk.to_column))
+                .on_delete(fk.on_delete())
+                .on_update(ForeignKeyAction::Cascade)
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (table, column) in SNOWFLAKE_COLUMNS {
+            match column_type(manager, table, column).await?.as_deref() {
+                Some("bigint") => {}
+                Some(_) => {
+                    manager
+                        .get_connection()
+                        .execute_unprepared(&format!(
+                            r#"ALTER TABLE "{}" ALTER COLUMN "{}" TYPE bigint"#,
+                            table, column
+                        ))
+                        .await?;
+                }
+                None => {
+                    return Err(DbErr::Migration(format!(
+                        "column {}.{} is missing",
+                        table, column
+                    )))
+                }
+            }
+        }
+
+        for fk in FOREIGN_KEYS.iter() {
+            fix_foreign_key(manager, fk).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // the drift this fixes can't be restored, and the consolidated schema is what the older
+        // migrations' `down` expects
+        Ok(())
+    }
+}
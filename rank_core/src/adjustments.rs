@@ -0,0 +1,253 @@
+use crate::aggregates::{add_to_guild, add_to_user};
+use crate::Error;
+use chrono::NaiveDateTime;
+use entity::prelude::{ScoreAdjustments, Users};
+use entity::{score_adjustments, users};
+use sea_orm::ActiveValue::Set;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// Source recorded on adjustments made by moderators
+pub const MANUAL_SOURCE: &str = "manual";
+
+/// Source recorded on the score adjustments created by an import
+pub const IMPORT_SOURCE: &str = "import";
+
+/// A change to a user's score that does not come from a message
+pub struct NewAdjustment {
+    pub guild: u64,
+    pub user: u64,
+    pub moderator: Option<u64>,
+    pub delta: f32,
+    pub message_delta: i32,
+    pub source: &'static str,
+    pub batch: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// A user's score and message count carried over from another bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedUser {
+    pub user: u64,
+    /// Name for users that aren't stored yet
+    pub name: Option<String>,
+    pub delta: f32,
+    pub message_delta: i32,
+}
+
+/// Users imported together, so they can be reverted together
+pub struct NewImport {
+    pub guild: u64,
+    pub moderator: Option<u64>,
+    /// Name of the batch, made unique with a sequence number if it is taken
+    pub batch: String,
+    pub reason: Option<String>,
+    pub users: Vec<ImportedUser>,
+}
+
+/// The batch an import was stored as and how many of its users weren't stored before
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedBatch {
+    pub batch: String,
+    pub created_users: usize,
+}
+
+/// Record an adjustment in the audit log and fold it into the user and guild totals
+pub async fn apply_adjustment(
+    db: &impl ConnectionTrait,
+    adjustment: NewAdjustment,
+) -> Result<score_adjustments::Model, Error> {
+    let model = score_adjustments::ActiveModel {
+        guild: Set(adjustment.guild as i64),
+        user: Set(adjustment.user as i64),
+        moderator: Set(adjustment.moderator.map(|m| m as i64)),
+        delta: Set(adjustment.delta),
+        message_delta: Set(adjustment.message_delta),
+        source: Set(adjustment.source.to_string()),
+        batch: Set(adjustment.batch),
+        reason: Set(adjustment.reason),
+        timestamp: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    add_to_user(db, model.user, model.delta, model.message_delta).await?;
+    add_to_guild(db, model.guild, model.delta, model.message_delta).await?;
+
+    Ok(model)
+}
+
+/// Net adjustment per user in a guild since `since`, for folding into windowed totals.
+/// Imports are left out, they carry score earned before the import and not inside the window.
+pub async fn adjustments_since(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    since: NaiveDateTime,
+) -> Result<HashMap<i64, f32>, Error> {
+    let mut totals = HashMap::new();
+
+    for adjustment in ScoreAdjustments::find()
+        .filter(score_adjustments::Column::Guild.eq(guild as i64))
+        .filter(score_adjustments::Column::Timestamp.gt(since))
+        .filter(score_adjustments::Column::Source.ne(IMPORT_SOURCE))
+        .all(db)
+        .await?
+    {
+        *totals.entry(adjustment.user).or_insert(0.0) += adjustment.delta;
+    }
+
+    Ok(totals)
+}
+
+/// A page of a guild's adjustments, optionally only those of one user, newest first
+pub async fn adjustment_history(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    user: Option<i64>,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<score_adjustments::Model>, Error> {
+    let mut query = ScoreAdjustments::find()
+        .filter(score_adjustments::Column::Guild.eq(guild as i64))
+        .order_by_desc(score_adjustments::Column::Timestamp)
+        .offset(offset)
+        .limit(limit);
+
+    if let Some(user) = user {
+        query = query.filter(score_adjustments::Column::User.eq(user));
+    }
+
+    Ok(query.all(db).await?)
+}
+
+/// Store an import in one transaction: an adjustment tagged with the batch for every user,
+/// creating the users that aren't stored yet
+pub async fn import_adjustments(
+    db: &DatabaseConnection,
+    import: NewImport,
+) -> Result<ImportedBatch, Error> {
+    let txn = db.begin().await?;
+
+    // imports started in the same second get a sequence number so they can be reverted apart
+    let mut batch = import.batch.clone();
+    let mut sequence = 1;
+    while ScoreAdjustments::find()
+        .filter(score_adjustments::Column::Batch.eq(&batch))
+        .one(&txn)
+        .await?
+        .is_some()
+    {
+        sequence += 1;
+        batch = format!("{}-{}", import.batch, sequence);
+    }
+
+    let mut created_users = 0;
+    for imported in import.users.iter() {
+        let user = imported.user as i64;
+        if Users::find_by_id(user).one(&txn).await?.is_none() {
+            users::ActiveModel {
+                snowflake: Set(user),
+                name: Set(imported.name.clone().unwrap_or_else(|| user.to_string())),
+                score: Set(0.),
+                message_count: Set(0),
+                guild: Set(import.guild as i64),
+            }
+            .insert(&txn)
+            .await?;
+            created_users += 1;
+        }
+
+        apply_adjustment(
+            &txn,
+            NewAdjustment {
+                guild: import.guild,
+                user: imported.user,
+                moderator: import.moderator,
+                delta: imported.delta,
+                message_delta: imported.message_delta,
+                source: IMPORT_SOURCE,
+                batch: Some(batch.clone()),
+                reason: import.reason.clone(),
+            },
+        )
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(ImportedBatch {
+        batch,
+        created_users,
+    })
+}
+
+/// Undo an import by removing its adjustments and taking them back off the totals.
+/// Returns how many users were affected.
+pub async fn revert_import(
+    db: &DatabaseConnection,
+    guild: u64,
+    batch: &str,
+) -> Result<usize, Error> {
+    let txn = db.begin().await?;
+
+    let adjustments = ScoreAdjustments::find()
+        .filter(score_adjustments::Column::Guild.eq(guild as i64))
+        .filter(score_adjustments::Column::Source.eq(IMPORT_SOURCE))
+        .filter(score_adjustments::Column::Batch.eq(batch))
+        .all(&txn)
+        .await?;
+
+    for adjustment in adjustments.iter() {
+        add_to_user(
+            &txn,
+            adjustment.user,
+            -adjustment.delta,
+            -adjustment.message_delta,
+        )
+        .await?;
+        add_to_guild(
+            &txn,
+            adjustment.guild,
+            -adjustment.delta,
+            -adjustment.message_delta,
+        )
+        .await?;
+    }
+
+    ScoreAdjustments::delete_many()
+        .filter(score_adjustments::Column::Guild.eq(guild as i64))
+        .filter(score_adjustments::Column::Source.eq(IMPORT_SOURCE))
+        .filter(score_adjustments::Column::Batch.eq(batch))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    Ok(adjustments.len())
+}
+
+/// The imports of a guild that can still be reverted, with how many users each touched
+pub async fn import_batches(
+    db: &impl ConnectionTrait,
+    guild: u64,
+) -> Result<BTreeMap<String, usize>, Error> {
+    let mut batches = BTreeMap::new();
+
+    for adjustment in ScoreAdjustments::find()
+        .filter(score_adjustments::Column::Guild.eq(guild as i64))
+        .filter(score_adjustments::Column::Source.eq(IMPORT_SOURCE))
+        .order_by_asc(score_adjustments::Column::Timestamp)
+        .all(db)
+        .await?
+    {
+        if let Some(batch) = adjustment.batch {
+            *batches.entry(batch).or_insert(0) += 1;
+        }
+    }
+
+    Ok(batches)
+}
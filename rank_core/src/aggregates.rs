@@ -0,0 +1,72 @@
+use crate::Error;
+use entity::prelude::{Channels, Guilds, Users};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter};
+
+// These apply deltas in a single UPDATE so concurrent writers can't lose each other's changes
+
+/// Add to a user's running score and message count
+pub async fn add_to_user(
+    db: &impl ConnectionTrait,
+    user: i64,
+    score: f32,
+    messages: i32,
+) -> Result<(), Error> {
+    Users::update_many()
+        .col_expr(
+            entity::users::Column::Score,
+            Expr::col(entity::users::Column::Score).add(score),
+        )
+        .col_expr(
+            entity::users::Column::MessageCount,
+            Expr::col(entity::users::Column::MessageCount).add(messages),
+        )
+        .filter(entity::users::Column::Snowflake.eq(user))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Add to a guild's running score and message count
+pub async fn add_to_guild(
+    db: &impl ConnectionTrait,
+    guild: i64,
+    score: f32,
+    messages: i32,
+) -> Result<(), Error> {
+    Guilds::update_many()
+        .col_expr(
+            entity::guilds::Column::Score,
+            Expr::col(entity::guilds::Column::Score).add(score),
+        )
+        .col_expr(
+            entity::guilds::Column::MessageCount,
+            Expr::col(entity::guilds::Column::MessageCount).add(messages),
+        )
+        .filter(entity::guilds::Column::Snowflake.eq(guild))
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+/// Add to a channel's running score and message count
+pub async fn add_to_channel(
+    db: &impl ConnectionTrait,
+    channel: i64,
+    score: f32,
+    messages: i32,
+) -> Result<(), Error> {
+    Channels::update_many()
+        .col_expr(
+            entity::channels::Column::Score,
+            Expr::col(entity::channels::Column::Score).add(score),
+        )
+        .col_expr(
+            entity::channels::Column::MessageCount,
+            Expr::col(entity::channels::Column::MessageCount).add(messages),
+        )
+        .filter(entity::channels::Column::Snowflake.eq(channel))
+        .exec(db)
+        .await?;
+    Ok(())
+}
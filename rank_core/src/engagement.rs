@@ -0,0 +1,102 @@
+use crate::aggregates::{add_to_channel, add_to_guild, add_to_user};
+use crate::rollups::{add_to_channel_daily, add_to_user_daily, DailyDelta};
+use crate::Error;
+use entity::prelude::{Channels, Messages, Reactions};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, Select,
+    TransactionTrait,
+};
+
+/// Bonus for every other user replying
+pub const REPLY_BONUS: f32 = 10.0;
+/// Bonus for every other user reacting
+pub const REACTION_BONUS: f32 = 2.0;
+/// Most a single message can earn from engagement
+pub const MAX_ENGAGEMENT_BONUS: f32 = 100.0;
+
+/// The engagement bonus for a message that this many other users replied to and reacted to.
+/// Users are counted once however often they reply or react, so nobody can farm a bonus for a
+/// friend by replying repeatedly or adding every emoji.
+pub fn engagement_bonus(repliers: u64, reactors: u64) -> f32 {
+    (repliers as f32 * REPLY_BONUS + reactors as f32 * REACTION_BONUS).min(MAX_ENGAGEMENT_BONUS)
+}
+
+/// The distinct users other than its author that replied to a message
+pub(crate) fn repliers_query(message: i64, author: i64) -> Select<Messages> {
+    Messages::find()
+        .select_only()
+        .column(entity::messages::Column::User)
+        .distinct()
+        .filter(entity::messages::Column::ReplysTo.eq(message))
+        .filter(entity::messages::Column::User.ne(author))
+}
+
+/// Recompute the engagement bonus of a message from its stored replies and reactions and apply the
+/// change to the message's score and the author's, channel's and guild's running totals.
+/// Returns the change in score, messages that are not stored are ignored.
+pub async fn refresh_engagement(db: &DatabaseConnection, message: i64) -> Result<f32, Error> {
+    let txn = db.begin().await?;
+
+    // lock the row so concurrent replies and reactions can't apply the same bonus twice
+    let message = match Messages::find_by_id(message)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+    {
+        Some(message) => message,
+        None => return Ok(0.0),
+    };
+
+    let repliers = repliers_query(message.snowflake, message.user)
+        .count(&txn)
+        .await?;
+
+    let reactions = Reactions::find()
+        .filter(entity::reactions::Column::Message.eq(message.snowflake))
+        .filter(entity::reactions::Column::User.ne(message.user));
+    let reaction_count = reactions.clone().count(&txn).await? as i32;
+    let reactors = reactions
+        .select_only()
+        .column(entity::reactions::Column::User)
+        .distinct()
+        .count(&txn)
+        .await?;
+
+    let bonus = engagement_bonus(repliers, reactors);
+    let delta = bonus - message.engagement;
+
+    Messages::update_many()
+        .col_expr(
+            entity::messages::Column::ReactionCount,
+            Expr::value(reaction_count),
+        )
+        .col_expr(entity::messages::Column::Engagement, Expr::value(bonus))
+        .col_expr(
+            entity::messages::Column::Score,
+            Expr::col(entity::messages::Column::Score).add(delta),
+        )
+        .filter(entity::messages::Column::Snowflake.eq(message.snowflake))
+        .exec(&txn)
+        .await?;
+
+    if delta != 0.0 {
+        add_to_user(&txn, message.user, delta, 0).await?;
+        add_to_channel(&txn, message.channel, delta, 0).await?;
+        if let Some(channel) = Channels::find_by_id(message.channel).one(&txn).await? {
+            add_to_guild(&txn, channel.guild, delta, 0).await?;
+            let daily = DailyDelta {
+                date: message.timestamp.date(),
+                score: delta,
+                messages: 0,
+                chars: 0,
+            };
+            add_to_user_daily(&txn, message.user, channel.guild, daily).await?;
+            add_to_channel_daily(&txn, message.channel, channel.guild, daily).await?;
+        }
+    }
+
+    txn.commit().await?;
+
+    Ok(delta)
+}
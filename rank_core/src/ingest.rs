@@ -0,0 +1,83 @@
+use crate::message::Message;
+use crate::rollups::{add_to_channel_daily, add_to_user_daily, DailyDelta};
+use crate::scoring::extract_features;
+use crate::Error;
+use entity::messages::ActiveModel as MessageActiveModel;
+use entity::prelude::{MessageBigrams, MessageTokens};
+use entity::{message_bigrams, message_tokens};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set, TransactionTrait};
+
+/// Store a message along with its precomputed features and add it to the daily rollups.
+/// `content` is what the guild's retention policy keeps of the message's content.
+/// Everything is written in one transaction so a failure leaves no half stored message.
+pub async fn store_message(
+    db: &DatabaseConnection,
+    msg: &Message,
+    guild_id: u64,
+    content: Option<String>,
+    score: f32,
+    replys_to: Option<i64>,
+) -> Result<(), Error> {
+    let features = extract_features(msg);
+
+    let message = MessageActiveModel {
+        snowflake: Set(msg.id as i64),
+        content: Set(content),
+        score: Set(score),
+        user: Set(msg.author as i64),
+        channel: Set(msg.channel as i64),
+        replys_to: Set(replys_to),
+        timestamp: Set(msg.timestamp),
+        length: Set(features.length),
+        word_count: Set(features.word_count),
+        engagement: Set(0.0),
+        reaction_count: Set(0),
+        attachment_count: Set(features.attachment_count),
+        attachment_types: Set(features.attachment_types),
+        link_count: Set(features.link_count),
+        code_lines: Set(features.code_lines),
+        emoji_only: Set(features.emoji_only),
+        mention_count: Set(features.mention_count),
+    };
+
+    let txn = db.begin().await?;
+
+    message.insert(&txn).await?;
+
+    let delta = DailyDelta {
+        date: msg.timestamp.date(),
+        score,
+        messages: 1,
+        chars: features.length as i64,
+    };
+    add_to_user_daily(&txn, msg.author as i64, guild_id as i64, delta).await?;
+    add_to_channel_daily(&txn, msg.channel as i64, guild_id as i64, delta).await?;
+
+    if !features.bigrams.is_empty() {
+        MessageBigrams::insert_many(features.bigrams.into_iter().map(|(bigram, count)| {
+            message_bigrams::ActiveModel {
+                message: Set(msg.id as i64),
+                bigram: Set(bigram),
+                count: Set(count),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+
+    if !features.tokens.is_empty() {
+        MessageTokens::insert_many(features.tokens.into_iter().map(|(token, count)| {
+            message_tokens::ActiveModel {
+                message: Set(msg.id as i64),
+                token: Set(token),
+                count: Set(count),
+            }
+        }))
+        .exec(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
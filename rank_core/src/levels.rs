@@ -0,0 +1,156 @@
+/// Xp needed to go from level `level` to the next on the curve most levelling bots use
+fn xp_for_next_level(level: f64) -> f64 {
+    5.0 * level * level + 50.0 * level + 100.0
+}
+
+/// Total xp needed to reach `level`, the sum of `xp_for_next_level` over every level below it
+fn xp_for_level(level: f64) -> f64 {
+    (10.0 * level * level * level + 135.0 * level * level + 455.0 * level) / 6.0
+}
+
+/// Level reached with `xp` on the curve most levelling bots use,
+/// where going from level `l` to `l + 1` costs `5l² + 50l + 100` xp.
+pub fn level_from_xp(xp: f64) -> f32 {
+    if xp <= 0.0 {
+        return 0.0;
+    }
+    // the cubic term alone overestimates the level by a few, step down to the exact one
+    let mut level = (0.6 * xp).cbrt().floor();
+    while level > 0.0 && xp_for_level(level) > xp {
+        level -= 1.0;
+    }
+    (level + (xp - xp_for_level(level)) / xp_for_next_level(level)) as f32
+}
+
+/// Struct representing a user's score
+pub struct UserScore {
+    score: f32,
+}
+
+fn get_formatted_num_and_suffix(num: f32) -> (f32, String) {
+    let (suffix, diviser) = match num as i64 {
+        0..=999 => ("", 1.0),
+        1000..=999_999 => ("K", 1_000.0),
+        1_000_000..=999_999_999 => ("M", 1_000_000.0),
+        1_000_000_000..=999_999_999_999 => ("B", 1_000_000_000.0),
+        1_000_000_000_000..=999_999_999_999_999 => ("T", 1_000_000_000_000.0),
+        1_000_000_000_000_000..=999_999_999_999_999_999 => ("Q", 1_000_000_000_000_000.0),
+        _ => ("", 1.0),
+    };
+    let formatted_num = num / diviser;
+    (formatted_num, suffix.to_string())
+}
+
+impl UserScore {
+    pub fn new(score: f32) -> Self {
+        Self { score }
+    }
+
+    /// Function that determines the score needed to reach a level,
+    /// the inverse of the levelling curve.
+    pub fn score_for_level(level: f32) -> f32 {
+        level.max(0.0).powf(1.5) * 1000.0
+    }
+
+    /// Function that outputs a formatted score level and progress bar.
+    pub fn display_score(&self) -> String {
+        let progress = self.get_progress();
+        let level = self.get_level();
+        let (formatted_score, suffix) = get_formatted_num_and_suffix(self.score);
+        let next_level_score = (level + 1.0).powf(1.5) * 1000.0;
+        let (next_level_score, next_suffix) = get_formatted_num_and_suffix(next_level_score);
+
+        format!(
+            "Level: {:.1} - {:.1}{} / {:.1}{} [{:.2}%]",
+            level, formatted_score, suffix, next_level_score, next_suffix, progress
+        )
+    }
+
+    /// Function that determines the user's level based on their score.
+    /// The higher the score, the higher the level.
+    fn get_level(&self) -> f32 {
+        (self.score / 1000.0).powf(1. / 1.5)
+    }
+
+    /// Function that determines the user's progress towards the next level
+    /// based on their score. The progress is represented as a percentage.
+    fn get_progress(&self) -> f32 {
+        let level = self.get_level();
+        (level - level.floor()) * 100.0
+    }
+
+    /// Function that displays the user's progress towards the next level
+    /// as a progress bar made of equal signs (`=`) and spaces. Each
+    /// equal sign represents 10% progress.
+    #[allow(dead_code)]
+    fn get_progress_bar(&self) -> String {
+        let progress = self.get_progress();
+        let completed = progress / 10.0;
+        let remaining = 10.0 - completed;
+        format!(
+            "[{}>{}]",
+            "=".repeat(completed as usize),
+            " ".repeat(remaining as usize)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_for_level_inverts_the_level() {
+        for level in [0.0, 1.0, 2.5, 10.0, 42.0] {
+            let score = UserScore::new(UserScore::score_for_level(level));
+            assert!((score.get_level() - level).abs() < 1e-3, "level {}", level);
+        }
+        assert_eq!(UserScore::score_for_level(-3.0), 0.0);
+    }
+
+    #[test]
+    fn progress_is_the_fraction_of_the_level() {
+        let score = UserScore::new(UserScore::score_for_level(3.25));
+        assert!((score.get_progress() - 25.0).abs() < 0.1);
+        assert_eq!(score.get_progress_bar(), "[==>       ]");
+    }
+
+    #[test]
+    fn formats_large_scores_with_suffixes() {
+        assert_eq!(get_formatted_num_and_suffix(512.0), (512.0, String::new()));
+        assert_eq!(
+            get_formatted_num_and_suffix(1_500.0),
+            (1.5, "K".to_string())
+        );
+        assert_eq!(
+            get_formatted_num_and_suffix(2_500_000.0),
+            (2.5, "M".to_string())
+        );
+        assert!(UserScore::new(1_500.0)
+            .display_score()
+            .contains(" - 1.5K / "));
+    }
+
+    #[test]
+    fn xp_curve_of_other_bots() {
+        // 100 xp for the first level, 155 for the second, 220 for the third
+        assert_eq!(xp_for_level(1.0), 100.0);
+        assert_eq!(xp_for_level(3.0), 475.0);
+        for level in 0..200 {
+            let level = level as f64;
+            assert_eq!(level_from_xp(xp_for_level(level)), level as f32);
+        }
+    }
+
+    #[test]
+    fn xp_between_levels_is_a_fraction() {
+        assert_eq!(level_from_xp(0.0), 0.0);
+        assert_eq!(level_from_xp(-10.0), 0.0);
+        assert_eq!(level_from_xp(50.0), 0.5);
+        assert_eq!(level_from_xp(100.0 + 77.5), 1.5);
+        // the cube root estimate is above the real level for large xp
+        let level = level_from_xp(1e12);
+        assert!(xp_for_level(level.floor() as f64) <= 1e12);
+        assert!(xp_for_level(level.floor() as f64 + 1.0) > 1e12);
+    }
+}
@@ -0,0 +1,20 @@
+//! Scoring, levels and the stored totals of the rank bot, over plain ids and content so they
+//! can be used without a Discord connection
+
+pub mod adjustments;
+pub mod aggregates;
+pub mod engagement;
+pub mod ingest;
+pub mod levels;
+pub mod maintenance;
+pub mod message;
+pub mod model;
+pub mod replies;
+pub mod repository;
+pub mod rollups;
+pub mod scoring;
+pub mod stats;
+pub mod tokenizer;
+pub mod words;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -0,0 +1,259 @@
+use crate::Error;
+use chrono::NaiveDateTime;
+use entity::prelude::{Channels, Messages, Reactions, Users};
+use sea_orm::sea_query::{Expr, Query};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbBackend, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Statement, TransactionTrait,
+};
+use std::collections::HashSet;
+
+// Offline repairs, they recompute stored totals from the messages instead of applying deltas
+// like the handlers do
+
+/// Running totals closer than this to the recomputed value are left alone, they only differ by
+/// float rounding
+pub const TOLERANCE: f32 = 0.01;
+
+// $1 is the guild or null for every guild, $2 the tolerance
+
+const RECONCILE_USERS_SQL: &str = r#"
+UPDATE "users" u SET "score" = t."score", "message_count" = t."messages"
+FROM (
+    SELECT u."snowflake",
+        (coalesce((SELECT sum(m."score") FROM "messages" m WHERE m."user" = u."snowflake"), 0)
+        + coalesce((SELECT sum(a."delta") FROM "score_adjustments" a WHERE a."user" = u."snowflake"), 0)
+        + coalesce((SELECT sum(v."score") FROM "voice_sessions" v WHERE v."user" = u."snowflake"), 0)
+        )::real AS "score",
+        ((SELECT count(*) FROM "messages" m WHERE m."user" = u."snowflake")
+        + coalesce((SELECT sum(a."message_delta") FROM "score_adjustments" a WHERE a."user" = u."snowflake"), 0)
+        )::int AS "messages"
+    FROM "users" u WHERE $1::bigint IS NULL OR u."guild" = $1
+) t
+WHERE u."snowflake" = t."snowflake"
+    AND (abs(u."score" - t."score") > $2 OR u."message_count" <> t."messages")
+"#;
+
+const RECONCILE_CHANNELS_SQL: &str = r#"
+UPDATE "channels" c SET "score" = t."score", "message_count" = t."messages"
+FROM (
+    SELECT c."snowflake",
+        coalesce((SELECT sum(m."score") FROM "messages" m WHERE m."channel" = c."snowflake"), 0)::real AS "score",
+        (SELECT count(*) FROM "messages" m WHERE m."channel" = c."snowflake")::int AS "messages"
+    FROM "channels" c WHERE $1::bigint IS NULL OR c."guild" = $1
+) t
+WHERE c."snowflake" = t."snowflake"
+    AND (abs(c."score" - t."score") > $2 OR c."message_count" <> t."messages")
+"#;
+
+const RECONCILE_GUILDS_SQL: &str = r#"
+UPDATE "guilds" g SET "score" = t."score", "message_count" = t."messages", "user_count" = t."users"
+FROM (
+    SELECT g."snowflake",
+        (coalesce((SELECT sum(c."score") FROM "channels" c WHERE c."guild" = g."snowflake"), 0)
+        + coalesce((SELECT sum(a."delta") FROM "score_adjustments" a WHERE a."guild" = g."snowflake"), 0)
+        + coalesce((SELECT sum(v."score") FROM "voice_sessions" v WHERE v."guild" = g."snowflake"), 0)
+        )::real AS "score",
+        (coalesce((SELECT sum(c."message_count") FROM "channels" c WHERE c."guild" = g."snowflake"), 0)
+        + coalesce((SELECT sum(a."message_delta") FROM "score_adjustments" a WHERE a."guild" = g."snowflake"), 0)
+        )::int AS "messages",
+        (SELECT count(*) FROM "users" u WHERE u."guild" = g."snowflake")::int AS "users"
+    FROM "guilds" g WHERE $1::bigint IS NULL OR g."snowflake" = $1
+) t
+WHERE g."snowflake" = t."snowflake"
+    AND (abs(g."score" - t."score") > $2 OR g."message_count" <> t."messages" OR g."user_count" <> t."users")
+"#;
+
+/// How many rows had totals that didn't match their messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReconcileSummary {
+    pub users: u64,
+    pub channels: u64,
+    pub guilds: u64,
+}
+
+/// What deleting a user removed
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedUser {
+    pub messages: u64,
+    pub reactions: u64,
+    /// Messages the user replied or reacted to, their engagement bonus is stale.
+    /// Some of them may have been the user's own and are gone.
+    pub engaged: HashSet<i64>,
+}
+
+/// Recompute the score and message count of users, channels and guilds from their messages,
+/// score adjustments and voice sessions, for one guild or every guild
+pub async fn reconcile_aggregates(
+    db: &DatabaseConnection,
+    guild: Option<u64>,
+) -> Result<ReconcileSummary, Error> {
+    let txn = db.begin().await?;
+    let statement = |sql: &str| {
+        Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            sql,
+            [guild.map(|g| g as i64).into(), TOLERANCE.into()],
+        )
+    };
+
+    let users = txn.execute(statement(RECONCILE_USERS_SQL)).await?;
+    // guilds are summed from their channels so those go first
+    let channels = txn.execute(statement(RECONCILE_CHANNELS_SQL)).await?;
+    let guilds = txn.execute(statement(RECONCILE_GUILDS_SQL)).await?;
+
+    txn.commit().await?;
+
+    Ok(ReconcileSummary {
+        users: users.rows_affected(),
+        channels: channels.rows_affected(),
+        guilds: guilds.rows_affected(),
+    })
+}
+
+/// The messages of a guild in snowflake order, from `from` (inclusive) to `until` (exclusive),
+/// after the message `after` and at most `limit` of them
+pub async fn guild_messages(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    from: Option<NaiveDateTime>,
+    until: Option<NaiveDateTime>,
+    after: Option<i64>,
+    limit: u64,
+) -> Result<Vec<entity::messages::Model>, Error> {
+    let guild_channels = Query::select()
+        .column(entity::channels::Column::Snowflake)
+        .from(Channels)
+        .and_where(entity::channels::Column::Guild.eq(guild as i64))
+        .to_owned();
+
+    let mut query = Messages::find()
+        .filter(entity::messages::Column::Channel.in_subquery(guild_channels))
+        .order_by_asc(entity::messages::Column::Snowflake)
+        .limit(limit);
+
+    if let Some(from) = from {
+        query = query.filter(entity::messages::Column::Timestamp.gte(from));
+    }
+    if let Some(until) = until {
+        query = query.filter(entity::messages::Column::Timestamp.lt(until));
+    }
+    if let Some(after) = after {
+        query = query.filter(entity::messages::Column::Snowflake.gt(after));
+    }
+
+    Ok(query.all(db).await?)
+}
+
+/// Overwrite the score of messages, in one transaction. Totals and rollups are left alone.
+pub async fn set_message_scores(
+    db: &DatabaseConnection,
+    scores: &[(i64, f32)],
+) -> Result<(), Error> {
+    let txn = db.begin().await?;
+    for (message, score) in scores.iter() {
+        Messages::update_many()
+            .col_expr(entity::messages::Column::Score, Expr::value(*score))
+            .filter(entity::messages::Column::Snowflake.eq(*message))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+    Ok(())
+}
+
+/// Delete a user with their messages, reactions, score adjustments, voice sessions and rollups.
+/// Totals and the engagement of other messages are left alone.
+pub async fn delete_user(db: &DatabaseConnection, user: i64) -> Result<DeletedUser, Error> {
+    let txn = db.begin().await?;
+
+    let mut engaged = Messages::find()
+        .select_only()
+        .column(entity::messages::Column::ReplysTo)
+        .filter(entity::messages::Column::User.eq(user))
+        .filter(entity::messages::Column::ReplysTo.is_not_null())
+        .into_tuple::<i64>()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .collect::<HashSet<i64>>();
+    engaged.extend(
+        Reactions::find()
+            .select_only()
+            .column(entity::reactions::Column::Message)
+            .filter(entity::reactions::Column::User.eq(user))
+            .into_tuple::<i64>()
+            .all(&txn)
+            .await?,
+    );
+
+    let messages = Messages::find()
+        .filter(entity::messages::Column::User.eq(user))
+        .count(&txn)
+        .await?;
+
+    let reactions = Reactions::delete_many()
+        .filter(entity::reactions::Column::User.eq(user))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    // messages, adjustments, voice sessions and rollups cascade with the user, other users'
+    // replies to their messages are detached by the foreign key
+    Users::delete_by_id(user).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(DeletedUser {
+        messages,
+        reactions,
+        engaged,
+    })
+}
+
+/// Null the content of a guild's messages sent before `before`, or truncate it to `truncate`
+/// characters. Returns how many messages changed.
+pub async fn prune_content(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    before: NaiveDateTime,
+    truncate: Option<i32>,
+) -> Result<u64, Error> {
+    let guild_channels = Query::select()
+        .column(entity::channels::Column::Snowflake)
+        .from(Channels)
+        .and_where(entity::channels::Column::Guild.eq(guild as i64))
+        .to_owned();
+
+    let update = Messages::update_many()
+        .filter(entity::messages::Column::Timestamp.lt(before))
+        .filter(entity::messages::Column::Content.is_not_null())
+        .filter(entity::messages::Column::Channel.in_subquery(guild_channels));
+
+    let result = match truncate {
+        Some(chars) => {
+            update
+                .col_expr(
+                    entity::messages::Column::Content,
+                    Expr::cust_with_values("left(\"content\", ?)", [chars]),
+                )
+                .filter(Expr::cust_with_values(
+                    "char_length(\"content\") > ?",
+                    [chars],
+                ))
+                .exec(db)
+                .await?
+        }
+        None => {
+            update
+                .col_expr(
+                    entity::messages::Column::Content,
+                    Expr::value(Option::<String>::None),
+                )
+                .exec(db)
+                .await?
+        }
+    };
+
+    Ok(result.rows_affected)
+}
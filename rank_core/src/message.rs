@@ -0,0 +1,24 @@
+use chrono::NaiveDateTime;
+
+/// A file attached to a message
+#[derive(Debug, Clone, Default)]
+pub struct Attachment {
+    /// MIME type, if the sender's client set one
+    pub content_type: Option<String>,
+}
+
+/// A message as the engine sees it, whatever frontend it was sent from.
+/// Embeds are left out: Discord adds link previews in an update after the message was sent, so
+/// live messages would be scored without them and loaded ones with them. The links are counted
+/// from the content instead, and rich embeds are only sent by bots, whose messages aren't scored.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub id: u64,
+    pub author: u64,
+    pub channel: u64,
+    pub content: String,
+    pub timestamp: NaiveDateTime,
+    pub attachments: Vec<Attachment>,
+    /// Users and roles mentioned
+    pub mention_count: usize,
+}
@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+
+// What the repository stores and returns, independent of the database it is kept in.
+// Ids are Discord snowflakes, stored as `i64`.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guild {
+    pub snowflake: i64,
+    pub name: String,
+    pub score: f32,
+    pub message_count: i32,
+    pub user_count: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+    pub snowflake: i64,
+    pub name: String,
+    pub score: f32,
+    pub message_count: i32,
+    pub guild: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct User {
+    pub snowflake: i64,
+    pub name: String,
+    pub message_count: i32,
+    pub score: f32,
+    pub guild: i64,
+}
+
+/// A message with the features computed when it was stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredMessage {
+    pub snowflake: i64,
+    /// What the guild's retention policy kept, `None` once pruned
+    pub content: Option<String>,
+    pub score: f32,
+    pub replys_to: Option<i64>,
+    pub channel: i64,
+    pub user: i64,
+    pub timestamp: NaiveDateTime,
+    /// Length of the original content in characters
+    pub length: i32,
+    pub word_count: i32,
+    pub engagement: f32,
+    pub reaction_count: i32,
+    pub attachment_count: i32,
+    pub attachment_types: Option<String>,
+    pub link_count: i32,
+    pub code_lines: i32,
+    pub emoji_only: bool,
+    pub mention_count: i32,
+}
+
+/// What a guild configured
+#[derive(Debug, Clone, PartialEq)]
+pub struct GuildSettings {
+    pub guild: i64,
+    /// Days message content is kept, 0 to never store it
+    pub retention_days: Option<i32>,
+    /// Characters kept of old content instead of deleting it
+    pub retention_truncate: Option<i32>,
+    pub voice_points_per_minute: f32,
+    /// A `ScoringConfig`, missing fields take their defaults
+    pub scoring: Option<serde_json::Value>,
+    /// Comma separated language codes of the guild's common words
+    pub stopword_languages: Option<String>,
+}
+
+/// A word a guild added to its common words, or removed from them
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stopword {
+    pub guild: i64,
+    pub word: String,
+    pub removed: bool,
+}
+
+/// An entry of the score adjustment audit log
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub id: i32,
+    pub guild: i64,
+    pub user: i64,
+    pub moderator: Option<i64>,
+    pub delta: f32,
+    pub message_delta: i32,
+    pub source: String,
+    pub batch: Option<String>,
+    pub reason: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
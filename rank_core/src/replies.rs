@@ -0,0 +1,118 @@
+use crate::Error;
+use sea_orm::{ConnectionTrait, DbBackend, FromQueryResult, Statement};
+use std::collections::HashMap;
+
+/// Deepest reply chain followed, replies always point at older messages so this only guards
+/// against bad data
+pub const MAX_THREAD_DEPTH: i32 = 1000;
+
+/// Replies between two users, counting every message of `from` that replies to a message of `to`
+const REPLY_COUNTS_SQL: &str = r#"
+SELECT m."user" AS from_user, p."user" AS to_user, count(*) AS replies
+FROM messages m
+JOIN messages p ON p.snowflake = m.replys_to
+JOIN channels c ON c.snowflake = m.channel
+WHERE c.guild = $1 AND m."user" <> p."user"
+GROUP BY m."user", p."user"
+"#;
+
+/// Replies from $1 to $2 and from $2 to $1
+const REPLIES_BETWEEN_SQL: &str = r#"
+SELECT
+    count(*) FILTER (WHERE m."user" = $1) AS forward,
+    count(*) FILTER (WHERE m."user" = $2) AS backward
+FROM messages m
+JOIN messages p ON p.snowflake = m.replys_to
+WHERE (m."user" = $1 AND p."user" = $2) OR (m."user" = $2 AND p."user" = $1)
+"#;
+
+/// Walks every reply chain down from the message that started it, a message whose parent is
+/// not stored counts as the start of a conversation
+const THREADS_SQL: &str = r#"
+WITH RECURSIVE thread AS (
+    SELECT m.snowflake, m.snowflake AS root, m.channel, m."user" AS starter, 0 AS depth
+    FROM messages m
+    JOIN channels c ON c.snowflake = m.channel
+    WHERE c.guild = $1
+        AND NOT EXISTS (SELECT 1 FROM messages p WHERE p.snowflake = m.replys_to)
+    UNION ALL
+    SELECT r.snowflake, t.root, t.channel, t.starter, t.depth + 1
+    FROM messages r
+    JOIN thread t ON r.replys_to = t.snowflake
+    WHERE t.depth < $2
+)
+SELECT root, channel, starter, max(depth) AS depth, count(*) - 1 AS replies
+FROM thread
+GROUP BY root, channel, starter
+HAVING count(*) > 1
+"#;
+
+#[derive(Debug, FromQueryResult)]
+struct ReplyCount {
+    from_user: i64,
+    to_user: i64,
+    replies: i64,
+}
+
+/// A conversation: a message that is not a reply and every reply below it
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct Thread {
+    pub root: i64,
+    pub channel: i64,
+    pub starter: i64,
+    pub depth: i32,
+    pub replies: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct RepliesBetween {
+    forward: i64,
+    backward: i64,
+}
+
+/// (from, to) -> number of replies from one user to another in a guild
+pub async fn reply_counts(
+    db: &impl ConnectionTrait,
+    guild: u64,
+) -> Result<HashMap<(i64, i64), i64>, Error> {
+    Ok(
+        ReplyCount::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            REPLY_COUNTS_SQL,
+            [(guild as i64).into()],
+        ))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|c| ((c.from_user, c.to_user), c.replies))
+        .collect(),
+    )
+}
+
+/// The conversations of a guild that got at least one reply
+pub async fn threads(db: &impl ConnectionTrait, guild: u64) -> Result<Vec<Thread>, Error> {
+    Ok(Thread::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        THREADS_SQL,
+        [(guild as i64).into(), MAX_THREAD_DEPTH.into()],
+    ))
+    .all(db)
+    .await?)
+}
+
+/// How often `a` replied to `b` and `b` to `a`, without loading the whole guild's graph
+pub async fn replies_between(
+    db: &impl ConnectionTrait,
+    a: i64,
+    b: i64,
+) -> Result<(i64, i64), Error> {
+    let counts = RepliesBetween::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        REPLIES_BETWEEN_SQL,
+        [a.into(), b.into()],
+    ))
+    .one(db)
+    .await?;
+
+    Ok(counts.map(|c| (c.forward, c.backward)).unwrap_or((0, 0)))
+}
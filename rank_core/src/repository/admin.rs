@@ -0,0 +1,61 @@
+use crate::adjustments::{ImportedBatch, NewAdjustment, NewImport};
+use crate::maintenance::{DeletedUser, ReconcileSummary};
+use crate::model::{Adjustment, StoredMessage};
+use crate::Error;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+/// Score adjustments, imports and the jobs that rewrite stored data: rescoring, reconciling,
+/// pruning, retention and exports
+#[async_trait]
+pub trait AdminRepository: Send + Sync {
+    /// Record an adjustment in the audit log and fold it into the user and guild totals
+    async fn apply_adjustment(&self, adjustment: NewAdjustment) -> Result<Adjustment, Error>;
+    /// A page of a guild's adjustments, optionally only those of one user, newest first
+    async fn adjustment_history(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Adjustment>, Error>;
+    /// Store an import all at once, creating the users that aren't stored yet
+    async fn import_adjustments(&self, import: NewImport) -> Result<ImportedBatch, Error>;
+    /// Remove the adjustments of an import and take them back off the totals, returns how
+    /// many users were affected
+    async fn revert_import(&self, guild: u64, batch: &str) -> Result<usize, Error>;
+    /// The imports of a guild that can still be reverted, with how many users each touched
+    async fn import_batches(&self, guild: u64) -> Result<BTreeMap<String, usize>, Error>;
+
+    /// The messages of a guild in snowflake order, from `from` (inclusive) to `until`
+    /// (exclusive), after the message `after` and at most `limit` of them
+    async fn guild_messages(
+        &self,
+        guild: u64,
+        from: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<StoredMessage>, Error>;
+    /// Overwrite the score of messages all at once, totals and rollups are left alone
+    async fn set_message_scores(&self, scores: &[(i64, f32)]) -> Result<(), Error>;
+    /// Null the content of a guild's messages sent before `before`, or truncate it to
+    /// `truncate` characters. Returns how many messages changed.
+    async fn prune_content(
+        &self,
+        guild: u64,
+        before: NaiveDateTime,
+        truncate: Option<i32>,
+    ) -> Result<u64, Error>;
+    /// Delete a user with their messages, reactions, adjustments and voice sessions. Replies
+    /// to their messages are kept, totals and the engagement of other messages are left alone.
+    async fn delete_user(&self, user: i64) -> Result<DeletedUser, Error>;
+
+    /// Recompute the score and message count of users, channels and guilds from their
+    /// messages, score adjustments and voice sessions, for one guild or every guild
+    async fn reconcile_aggregates(&self, guild: Option<u64>) -> Result<ReconcileSummary, Error>;
+    /// Recompute the daily rollups of a guild, or of every guild, from stored messages.
+    /// Returns the number of user and channel days.
+    async fn rebuild_rollups(&self, guild: Option<u64>) -> Result<(u64, u64), Error>;
+}
@@ -0,0 +1,349 @@
+use super::{MemoryRepository, State};
+use crate::adjustments::{ImportedBatch, NewAdjustment, NewImport, IMPORT_SOURCE};
+use crate::maintenance::{DeletedUser, ReconcileSummary, TOLERANCE};
+use crate::model::{Adjustment, StoredMessage, User};
+use crate::repository::AdminRepository;
+use crate::Error;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+impl State {
+    fn apply_adjustment(&mut self, adjustment: NewAdjustment) -> Adjustment {
+        let model = Adjustment {
+            id: self.next_id(),
+            guild: adjustment.guild as i64,
+            user: adjustment.user as i64,
+            moderator: adjustment.moderator.map(|m| m as i64),
+            delta: adjustment.delta,
+            message_delta: adjustment.message_delta,
+            source: adjustment.source.to_string(),
+            batch: adjustment.batch,
+            reason: adjustment.reason,
+            timestamp: chrono::Utc::now().naive_utc(),
+        };
+        self.add_to_user(model.user, model.delta, model.message_delta);
+        self.add_to_guild(model.guild, model.delta, model.message_delta);
+        self.adjustments.push(model.clone());
+        model
+    }
+
+    fn is_import(adjustment: &Adjustment, guild: u64, batch: &str) -> bool {
+        adjustment.guild == guild as i64
+            && adjustment.source == IMPORT_SOURCE
+            && adjustment.batch.as_deref() == Some(batch)
+    }
+}
+
+#[async_trait]
+impl AdminRepository for MemoryRepository {
+    async fn apply_adjustment(&self, adjustment: NewAdjustment) -> Result<Adjustment, Error> {
+        Ok(self.state().apply_adjustment(adjustment))
+    }
+
+    async fn adjustment_history(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Adjustment>, Error> {
+        let mut adjustments = self
+            .state()
+            .adjustments
+            .iter()
+            .filter(|a| a.guild == guild as i64 && user.is_none_or(|user| a.user == user))
+            .cloned()
+            .collect::<Vec<_>>();
+        adjustments.sort_by_key(|a| std::cmp::Reverse((a.timestamp, a.id)));
+        Ok(adjustments
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn import_adjustments(&self, import: NewImport) -> Result<ImportedBatch, Error> {
+        let mut state = self.state();
+
+        let mut batch = import.batch.clone();
+        let mut sequence = 1;
+        while state
+            .adjustments
+            .iter()
+            .any(|a| a.batch.as_deref() == Some(batch.as_str()))
+        {
+            sequence += 1;
+            batch = format!("{}-{}", import.batch, sequence);
+        }
+
+        let mut created_users = 0;
+        for imported in import.users.iter() {
+            let user = imported.user as i64;
+            if let Entry::Vacant(entry) = state.users.entry(user) {
+                entry.insert(User {
+                    snowflake: user,
+                    name: imported.name.clone().unwrap_or_else(|| user.to_string()),
+                    message_count: 0,
+                    score: 0.,
+                    guild: import.guild as i64,
+                });
+                created_users += 1;
+            }
+
+            state.apply_adjustment(NewAdjustment {
+                guild: import.guild,
+                user: imported.user,
+                moderator: import.moderator,
+                delta: imported.delta,
+                message_delta: imported.message_delta,
+                source: IMPORT_SOURCE,
+                batch: Some(batch.clone()),
+                reason: import.reason.clone(),
+            });
+        }
+
+        Ok(ImportedBatch {
+            batch,
+            created_users,
+        })
+    }
+
+    async fn revert_import(&self, guild: u64, batch: &str) -> Result<usize, Error> {
+        let mut state = self.state();
+        let reverted = state
+            .adjustments
+            .iter()
+            .filter(|a| State::is_import(a, guild, batch))
+            .map(|a| (a.user, a.guild, a.delta, a.message_delta))
+            .collect::<Vec<_>>();
+
+        for (user, guild, delta, message_delta) in reverted.iter() {
+            state.add_to_user(*user, -delta, -message_delta);
+            state.add_to_guild(*guild, -delta, -message_delta);
+        }
+        state
+            .adjustments
+            .retain(|a| !State::is_import(a, guild, batch));
+
+        Ok(reverted.len())
+    }
+
+    async fn import_batches(&self, guild: u64) -> Result<BTreeMap<String, usize>, Error> {
+        let mut batches = BTreeMap::new();
+        for adjustment in self
+            .state()
+            .adjustments
+            .iter()
+            .filter(|a| a.guild == guild as i64 && a.source == IMPORT_SOURCE)
+        {
+            if let Some(batch) = &adjustment.batch {
+                *batches.entry(batch.clone()).or_insert(0) += 1;
+            }
+        }
+        Ok(batches)
+    }
+
+    async fn guild_messages(
+        &self,
+        guild: u64,
+        from: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<StoredMessage>, Error> {
+        let state = self.state();
+        Ok(state
+            .messages
+            .values()
+            .filter(|m| state.guild_of_message(m) == Some(guild as i64))
+            .filter(|m| from.is_none_or(|from| m.timestamp >= from))
+            .filter(|m| until.is_none_or(|until| m.timestamp < until))
+            .filter(|m| after.is_none_or(|after| m.snowflake > after))
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_message_scores(&self, scores: &[(i64, f32)]) -> Result<(), Error> {
+        let mut state = self.state();
+        for (message, score) in scores.iter() {
+            if let Some(message) = state.messages.get_mut(message) {
+                message.score = *score;
+            }
+        }
+        Ok(())
+    }
+
+    async fn prune_content(
+        &self,
+        guild: u64,
+        before: NaiveDateTime,
+        truncate: Option<i32>,
+    ) -> Result<u64, Error> {
+        let mut state = self.state();
+        let channels = state
+            .channels
+            .values()
+            .filter(|c| c.guild == guild as i64)
+            .map(|c| c.snowflake)
+            .collect::<HashSet<i64>>();
+
+        let mut pruned = 0;
+        for message in state
+            .messages
+            .values_mut()
+            .filter(|m| m.timestamp < before && channels.contains(&m.channel))
+        {
+            let content = match &message.content {
+                Some(content) => content,
+                None => continue,
+            };
+            match truncate {
+                Some(chars) if content.chars().count() > chars.max(0) as usize => {
+                    message.content = Some(content.chars().take(chars.max(0) as usize).collect());
+                }
+                Some(_) => continue,
+                None => message.content = None,
+            }
+            pruned += 1;
+        }
+        Ok(pruned)
+    }
+
+    async fn delete_user(&self, user: i64) -> Result<DeletedUser, Error> {
+        let mut state = self.state();
+
+        let own = state
+            .messages
+            .values()
+            .filter(|m| m.user == user)
+            .map(|m| m.snowflake)
+            .collect::<HashSet<i64>>();
+        let mut engaged = state
+            .messages
+            .values()
+            .filter(|m| m.user == user)
+            .filter_map(|m| m.replys_to)
+            .collect::<HashSet<i64>>();
+        engaged.extend(
+            state
+                .reactions
+                .iter()
+                .filter(|r| r.user == user)
+                .map(|r| r.message),
+        );
+
+        let reactions = state.reactions.len();
+        state.reactions.retain(|r| r.user != user);
+        let reactions = (reactions - state.reactions.len()) as u64;
+
+        state.remove_messages(&own);
+        state.adjustments.retain(|a| a.user != user);
+        state.voice_sessions.retain(|v| v.user != user);
+        state.users.remove(&user);
+
+        Ok(DeletedUser {
+            messages: own.len() as u64,
+            reactions,
+            engaged,
+        })
+    }
+
+    async fn reconcile_aggregates(&self, guild: Option<u64>) -> Result<ReconcileSummary, Error> {
+        let mut state = self.state();
+        let in_guild = |g: i64| guild.is_none_or(|guild| g == guild as i64);
+        let differs = |score: f32, messages: i32, expected: (f32, i32)| {
+            (score - expected.0).abs() > TOLERANCE || messages != expected.1
+        };
+
+        let mut users: HashMap<i64, (f32, i32)> = HashMap::new();
+        let mut channels: HashMap<i64, (f32, i32)> = HashMap::new();
+        let mut guilds: HashMap<i64, (f32, i32)> = HashMap::new();
+        for message in state.messages.values() {
+            let user = users.entry(message.user).or_default();
+            user.0 += message.score;
+            user.1 += 1;
+            let channel = channels.entry(message.channel).or_default();
+            channel.0 += message.score;
+            channel.1 += 1;
+        }
+        for adjustment in state.adjustments.iter() {
+            for total in [
+                users.entry(adjustment.user).or_default(),
+                guilds.entry(adjustment.guild).or_default(),
+            ] {
+                total.0 += adjustment.delta;
+                total.1 += adjustment.message_delta;
+            }
+        }
+        for session in state.voice_sessions.iter() {
+            users.entry(session.user).or_default().0 += session.score;
+            guilds.entry(session.guild).or_default().0 += session.score;
+        }
+
+        let mut summary = ReconcileSummary {
+            users: 0,
+            channels: 0,
+            guilds: 0,
+        };
+
+        for user in state.users.values_mut().filter(|u| in_guild(u.guild)) {
+            let expected = users.get(&user.snowflake).copied().unwrap_or_default();
+            if differs(user.score, user.message_count, expected) {
+                (user.score, user.message_count) = expected;
+                summary.users += 1;
+            }
+        }
+
+        // guilds are summed from their channels so those go first
+        for channel in state.channels.values_mut().filter(|c| in_guild(c.guild)) {
+            let expected = channels
+                .get(&channel.snowflake)
+                .copied()
+                .unwrap_or_default();
+            if differs(channel.score, channel.message_count, expected) {
+                (channel.score, channel.message_count) = expected;
+                summary.channels += 1;
+            }
+            let guild = guilds.entry(channel.guild).or_default();
+            guild.0 += channel.score;
+            guild.1 += channel.message_count;
+        }
+
+        let mut user_counts: HashMap<i64, i32> = HashMap::new();
+        for user in state.users.values() {
+            *user_counts.entry(user.guild).or_insert(0) += 1;
+        }
+        for stored in state.guilds.values_mut().filter(|g| in_guild(g.snowflake)) {
+            let expected = guilds.get(&stored.snowflake).copied().unwrap_or_default();
+            let user_count = user_counts.get(&stored.snowflake).copied().unwrap_or(0);
+            if differs(stored.score, stored.message_count, expected)
+                || stored.user_count != user_count
+            {
+                (stored.score, stored.message_count) = expected;
+                stored.user_count = user_count;
+                summary.guilds += 1;
+            }
+        }
+
+        Ok(summary)
+    }
+
+    async fn rebuild_rollups(&self, guild: Option<u64>) -> Result<(u64, u64), Error> {
+        // no rollups are kept, report the days they would have
+        let state = self.state();
+        let mut user_days = HashSet::new();
+        let mut channel_days = HashSet::new();
+        for message in state
+            .messages
+            .values()
+            .filter(|m| guild.is_none_or(|guild| state.guild_of_message(m) == Some(guild as i64)))
+        {
+            user_days.insert((message.user, message.timestamp.date()));
+            channel_days.insert((message.channel, message.timestamp.date()));
+        }
+        Ok((user_days.len() as u64, channel_days.len() as u64))
+    }
+}
@@ -0,0 +1,737 @@
+use super::Repository;
+use crate::adjustments::IMPORT_SOURCE;
+use crate::engagement::engagement_bonus;
+use crate::message::Message;
+use crate::model::{Adjustment, Channel, Guild, GuildSettings, Stopword, StoredMessage, User};
+use crate::rollups::UserTotal;
+use crate::scoring::extract_features;
+use crate::Error;
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use entity::{reactions, voice_sessions};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+mod admin;
+mod settings;
+mod stats;
+
+#[derive(Default)]
+struct State {
+    guilds: BTreeMap<i64, Guild>,
+    channels: BTreeMap<i64, Channel>,
+    users: BTreeMap<i64, User>,
+    messages: BTreeMap<i64, StoredMessage>,
+    /// Message, token and count
+    tokens: Vec<(i64, String, i32)>,
+    /// Message, pair of words and count
+    bigrams: Vec<(i64, String, i32)>,
+    reactions: Vec<reactions::Model>,
+    adjustments: Vec<Adjustment>,
+    voice_sessions: Vec<voice_sessions::Model>,
+    guild_settings: BTreeMap<i64, GuildSettings>,
+    /// Keyed by guild and word so a guild's stopwords are in word order
+    stopwords: BTreeMap<(i64, String), Stopword>,
+    /// Guild and role
+    bot_manager_roles: BTreeSet<(i64, i64)>,
+    /// Last id handed out to an adjustment or voice session
+    last_id: i32,
+}
+
+impl State {
+    fn guild_of_channel(&self, channel: i64) -> Option<i64> {
+        self.channels.get(&channel).map(|c| c.guild)
+    }
+
+    fn guild_of_message(&self, message: &StoredMessage) -> Option<i64> {
+        self.guild_of_channel(message.channel)
+    }
+
+    fn next_id(&mut self) -> i32 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn add_to_guild(&mut self, guild: i64, score: f32, messages: i32) {
+        if let Some(guild) = self.guilds.get_mut(&guild) {
+            guild.score += score;
+            guild.message_count += messages;
+        }
+    }
+
+    fn add_to_user(&mut self, user: i64, score: f32, messages: i32) {
+        if let Some(user) = self.users.get_mut(&user) {
+            user.score += score;
+            user.message_count += messages;
+        }
+    }
+
+    /// Remove messages along with their words and reactions, replies to them are detached
+    fn remove_messages(&mut self, removed: &HashSet<i64>) {
+        self.messages.retain(|m, _| !removed.contains(m));
+        for message in self.messages.values_mut() {
+            if message.replys_to.is_some_and(|r| removed.contains(&r)) {
+                message.replys_to = None;
+            }
+        }
+        self.tokens.retain(|t| !removed.contains(&t.0));
+        self.bigrams.retain(|b| !removed.contains(&b.0));
+        self.reactions.retain(|r| !removed.contains(&r.message));
+    }
+
+    /// The open voice session of a user in a guild
+    fn open_voice_session(&mut self, guild: u64, user: i64) -> Option<&mut voice_sessions::Model> {
+        self.voice_sessions
+            .iter_mut()
+            .find(|v| v.guild == guild as i64 && v.user == user && v.ended_at.is_none())
+    }
+}
+
+/// A repository kept in memory, for running the engine without a database.
+/// Daily rollups aren't kept, windowed totals are summed from the messages.
+#[derive(Default)]
+pub struct MemoryRepository {
+    state: Mutex<State>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl Repository for MemoryRepository {
+    async fn guild_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(self.state().guilds.keys().map(|g| *g as u64).collect())
+    }
+
+    async fn channel_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(self.state().channels.keys().map(|c| *c as u64).collect())
+    }
+
+    async fn user_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(self.state().users.keys().map(|u| *u as u64).collect())
+    }
+
+    async fn guild(&self, guild: u64) -> Result<Option<Guild>, Error> {
+        Ok(self.state().guilds.get(&(guild as i64)).cloned())
+    }
+
+    async fn insert_guild(&self, guild: u64, name: String) -> Result<Guild, Error> {
+        let model = Guild {
+            snowflake: guild as i64,
+            name,
+            score: 0.,
+            message_count: 0,
+            user_count: 0,
+        };
+        let mut state = self.state();
+        if state.guilds.contains_key(&model.snowflake) {
+            return Err(format!("guild {} is already stored", guild).into());
+        }
+        state.guilds.insert(model.snowflake, model.clone());
+        Ok(model)
+    }
+
+    async fn ensure_guild(&self, guild: u64, name: &str) -> Result<(), Error> {
+        self.state()
+            .guilds
+            .entry(guild as i64)
+            .or_insert_with(|| Guild {
+                snowflake: guild as i64,
+                name: name.to_string(),
+                score: 0.,
+                message_count: 0,
+                user_count: 0,
+            });
+        Ok(())
+    }
+
+    async fn delete_guild(&self, guild: u64) -> Result<bool, Error> {
+        let guild = guild as i64;
+        let mut state = self.state();
+        if state.guilds.remove(&guild).is_none() {
+            return Ok(false);
+        }
+
+        state.channels.retain(|_, c| c.guild != guild);
+        state.users.retain(|_, u| u.guild != guild);
+        let removed = state
+            .messages
+            .values()
+            .filter(|m| {
+                !state.channels.contains_key(&m.channel) || !state.users.contains_key(&m.user)
+            })
+            .map(|m| m.snowflake)
+            .collect::<HashSet<i64>>();
+        state.remove_messages(&removed);
+        state.adjustments.retain(|a| a.guild != guild);
+        state.voice_sessions.retain(|v| v.guild != guild);
+        Ok(true)
+    }
+
+    async fn channel(&self, channel: u64) -> Result<Option<Channel>, Error> {
+        Ok(self.state().channels.get(&(channel as i64)).cloned())
+    }
+
+    async fn insert_channel(
+        &self,
+        channel: u64,
+        guild: u64,
+        name: String,
+    ) -> Result<Channel, Error> {
+        let model = Channel {
+            snowflake: channel as i64,
+            name,
+            score: 0.,
+            message_count: 0,
+            guild: guild as i64,
+        };
+        let mut state = self.state();
+        if state.channels.contains_key(&model.snowflake) {
+            return Err(format!("channel {} is already stored", channel).into());
+        }
+        if !state.guilds.contains_key(&model.guild) {
+            return Err(format!("guild {} of channel {} is not stored", guild, channel).into());
+        }
+        state.channels.insert(model.snowflake, model.clone());
+        Ok(model)
+    }
+
+    async fn guild_channels(&self, guild: u64) -> Result<Vec<Channel>, Error> {
+        let mut channels = self
+            .state()
+            .channels
+            .values()
+            .filter(|c| c.guild == guild as i64)
+            .cloned()
+            .collect::<Vec<_>>();
+        channels.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(channels)
+    }
+
+    async fn user(&self, user: u64) -> Result<Option<User>, Error> {
+        Ok(self.state().users.get(&(user as i64)).cloned())
+    }
+
+    async fn insert_user(&self, user: u64, guild: u64, name: String) -> Result<User, Error> {
+        let model = User {
+            snowflake: user as i64,
+            name,
+            message_count: 0,
+            score: 0.,
+            guild: guild as i64,
+        };
+        let mut state = self.state();
+        if state.users.contains_key(&model.snowflake) {
+            return Err(format!("user {} is already stored", user).into());
+        }
+        if !state.guilds.contains_key(&model.guild) {
+            return Err(format!("guild {} of user {} is not stored", guild, user).into());
+        }
+        state.users.insert(model.snowflake, model.clone());
+        Ok(model)
+    }
+
+    async fn ensure_user(&self, user: u64, guild: u64, name: &str) -> Result<(), Error> {
+        let mut state = self.state();
+        if state.users.contains_key(&(user as i64)) {
+            return Ok(());
+        }
+        if !state.guilds.contains_key(&(guild as i64)) {
+            return Err(format!("guild {} of user {} is not stored", guild, user).into());
+        }
+        state.users.insert(
+            user as i64,
+            User {
+                snowflake: user as i64,
+                name: name.to_string(),
+                message_count: 0,
+                score: 0.,
+                guild: guild as i64,
+            },
+        );
+        Ok(())
+    }
+
+    async fn users_by_score(
+        &self,
+        guild: u64,
+        offset: u64,
+        limit: Option<u64>,
+    ) -> Result<Vec<User>, Error> {
+        let mut users = self
+            .state()
+            .users
+            .values()
+            .filter(|u| u.guild == guild as i64)
+            .cloned()
+            .collect::<Vec<_>>();
+        users.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(users
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit.map(|l| l as usize).unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    async fn user_names(&self, users: &[i64]) -> Result<HashMap<i64, String>, Error> {
+        let state = self.state();
+        Ok(users
+            .iter()
+            .filter_map(|u| state.users.get(u).map(|user| (*u, user.name.clone())))
+            .collect())
+    }
+
+    async fn user_count(&self, guild: u64) -> Result<u64, Error> {
+        Ok(self
+            .state()
+            .users
+            .values()
+            .filter(|u| u.guild == guild as i64)
+            .count() as u64)
+    }
+
+    async fn users_above(&self, guild: u64, score: f32) -> Result<u64, Error> {
+        Ok(self
+            .state()
+            .users
+            .values()
+            .filter(|u| u.guild == guild as i64 && u.score > score)
+            .count() as u64)
+    }
+
+    async fn add_to_guild(&self, guild: i64, score: f32, messages: i32) -> Result<(), Error> {
+        self.state().add_to_guild(guild, score, messages);
+        Ok(())
+    }
+
+    async fn add_to_channel(&self, channel: i64, score: f32, messages: i32) -> Result<(), Error> {
+        if let Some(channel) = self.state().channels.get_mut(&channel) {
+            channel.score += score;
+            channel.message_count += messages;
+        }
+        Ok(())
+    }
+
+    async fn add_to_user(&self, user: i64, score: f32, messages: i32) -> Result<(), Error> {
+        self.state().add_to_user(user, score, messages);
+        Ok(())
+    }
+
+    async fn message(&self, message: u64) -> Result<Option<StoredMessage>, Error> {
+        Ok(self.state().messages.get(&(message as i64)).cloned())
+    }
+
+    async fn recent_contents(&self, user: u64, limit: u64) -> Result<Vec<String>, Error> {
+        Ok(self
+            .state()
+            .messages
+            .values()
+            .rev()
+            .filter(|m| m.user == user as i64)
+            .take(limit as usize)
+            .filter_map(|m| m.content.clone())
+            .collect())
+    }
+
+    async fn user_messages(
+        &self,
+        user: i64,
+        channel: Option<i64>,
+        since: Option<NaiveDateTime>,
+    ) -> Result<Vec<StoredMessage>, Error> {
+        Ok(self
+            .state()
+            .messages
+            .values()
+            .filter(|m| m.user == user)
+            .filter(|m| channel.is_none_or(|channel| m.channel == channel))
+            .filter(|m| since.is_none_or(|since| m.timestamp > since))
+            .cloned()
+            .collect())
+    }
+
+    async fn store_message(
+        &self,
+        message: &Message,
+        _guild: u64,
+        content: Option<String>,
+        score: f32,
+        replys_to: Option<i64>,
+    ) -> Result<(), Error> {
+        let features = extract_features(message);
+        let model = StoredMessage {
+            snowflake: message.id as i64,
+            content,
+            score,
+            replys_to,
+            channel: message.channel as i64,
+            user: message.author as i64,
+            timestamp: message.timestamp,
+            length: features.length,
+            word_count: features.word_count,
+            engagement: 0.0,
+            reaction_count: 0,
+            attachment_count: features.attachment_count,
+            attachment_types: features.attachment_types,
+            link_count: features.link_count,
+            code_lines: features.code_lines,
+            emoji_only: features.emoji_only,
+            mention_count: features.mention_count,
+        };
+
+        let mut state = self.state();
+        if state.messages.contains_key(&model.snowflake) {
+            return Err(format!("message {} is already stored", message.id).into());
+        }
+        if !state.channels.contains_key(&model.channel) || !state.users.contains_key(&model.user) {
+            return Err(
+                format!("channel or author of message {} is not stored", message.id).into(),
+            );
+        }
+        state.messages.insert(model.snowflake, model);
+        state.tokens.extend(
+            features
+                .tokens
+                .into_iter()
+                .map(|(token, count)| (message.id as i64, token, count)),
+        );
+        state.bigrams.extend(
+            features
+                .bigrams
+                .into_iter()
+                .map(|(bigram, count)| (message.id as i64, bigram, count)),
+        );
+        Ok(())
+    }
+
+    async fn refresh_engagement(&self, message: i64) -> Result<f32, Error> {
+        let mut state = self.state();
+        let (author, channel) = match state.messages.get(&message) {
+            Some(stored) => (stored.user, stored.channel),
+            None => return Ok(0.0),
+        };
+
+        let repliers = state
+            .messages
+            .values()
+            .filter(|m| m.replys_to == Some(message) && m.user != author)
+            .map(|m| m.user)
+            .collect::<HashSet<_>>()
+            .len() as u64;
+        let reactions = state
+            .reactions
+            .iter()
+            .filter(|r| r.message == message && r.user != author)
+            .collect::<Vec<_>>();
+        let reaction_count = reactions.len() as i32;
+        let reactors = reactions
+            .iter()
+            .map(|r| r.user)
+            .collect::<HashSet<_>>()
+            .len() as u64;
+
+        let bonus = engagement_bonus(repliers, reactors);
+        let stored = state.messages.get_mut(&message).unwrap();
+        let delta = bonus - stored.engagement;
+        stored.reaction_count = reaction_count;
+        stored.engagement = bonus;
+        stored.score += delta;
+
+        if delta != 0.0 {
+            let guild = state.guild_of_channel(channel);
+            if let Some(user) = state.users.get_mut(&author) {
+                user.score += delta;
+            }
+            if let Some(channel) = state.channels.get_mut(&channel) {
+                channel.score += delta;
+            }
+            if let Some(guild) = guild.and_then(|g| state.guilds.get_mut(&g)) {
+                guild.score += delta;
+            }
+        }
+
+        Ok(delta)
+    }
+
+    async fn insert_reactions(
+        &self,
+        message: i64,
+        emoji: &str,
+        users: &[i64],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), Error> {
+        let mut state = self.state();
+        for user in users.iter() {
+            if !state
+                .reactions
+                .iter()
+                .any(|r| r.message == message && r.user == *user && r.emoji == emoji)
+            {
+                state.reactions.push(reactions::Model {
+                    message,
+                    user: *user,
+                    emoji: emoji.to_string(),
+                    timestamp,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_reactions(
+        &self,
+        message: i64,
+        user: Option<i64>,
+        emoji: Option<&str>,
+    ) -> Result<(), Error> {
+        self.state().reactions.retain(|r| {
+            r.message != message
+                || user.is_some_and(|user| r.user != user)
+                || emoji.is_some_and(|emoji| r.emoji != emoji)
+        });
+        Ok(())
+    }
+
+    async fn favorite_emoji(&self, user: i64) -> Result<Option<(String, i64)>, Error> {
+        let mut uses = HashMap::new();
+        for reaction in self.state().reactions.iter().filter(|r| r.user == user) {
+            *uses.entry(reaction.emoji.clone()).or_insert(0) += 1;
+        }
+        Ok(uses.into_iter().max_by_key(|(_, uses)| *uses))
+    }
+
+    async fn reactions_given(&self, user: i64) -> Result<u64, Error> {
+        Ok(self
+            .state()
+            .reactions
+            .iter()
+            .filter(|r| r.user == user)
+            .count() as u64)
+    }
+
+    async fn reactions_received(&self, user: i64) -> Result<u64, Error> {
+        let state = self.state();
+        Ok(state
+            .reactions
+            .iter()
+            .filter(|r| r.user != user)
+            .filter(|r| state.messages.get(&r.message).map(|m| m.user) == Some(user))
+            .count() as u64)
+    }
+
+    async fn user_tokens(&self, user: i64) -> Result<Vec<(String, i32)>, Error> {
+        let state = self.state();
+        Ok(state
+            .tokens
+            .iter()
+            .filter(|t| state.messages.get(&t.0).map(|m| m.user) == Some(user))
+            .map(|(_, token, count)| (token.clone(), *count))
+            .collect())
+    }
+
+    async fn user_totals_since(
+        &self,
+        guild: u64,
+        since: NaiveDate,
+    ) -> Result<HashMap<i64, UserTotal>, Error> {
+        let state = self.state();
+        let mut totals: HashMap<i64, UserTotal> = HashMap::new();
+        for message in state.messages.values() {
+            if message.timestamp.date() < since
+                || state.guild_of_channel(message.channel) != Some(guild as i64)
+            {
+                continue;
+            }
+            let total = totals.entry(message.user).or_insert(UserTotal {
+                user: message.user,
+                score: 0.0,
+                messages: 0,
+            });
+            total.score += message.score;
+            total.messages += 1;
+        }
+        Ok(totals)
+    }
+
+    async fn adjustments_since(
+        &self,
+        guild: u64,
+        since: NaiveDateTime,
+    ) -> Result<HashMap<i64, f32>, Error> {
+        let mut totals = HashMap::new();
+        for adjustment in
+            self.state().adjustments.iter().filter(|a| {
+                a.guild == guild as i64 && a.timestamp > since && a.source != IMPORT_SOURCE
+            })
+        {
+            *totals.entry(adjustment.user).or_insert(0.0) += adjustment.delta;
+        }
+        Ok(totals)
+    }
+
+    async fn open_voice_session(
+        &self,
+        guild: u64,
+        user: i64,
+        channel: i64,
+        muted: bool,
+        deafened: bool,
+    ) -> Result<(), Error> {
+        let mut state = self.state();
+        let id = state.next_id();
+        state.voice_sessions.push(voice_sessions::Model {
+            id,
+            guild: guild as i64,
+            user,
+            channel,
+            started_at: chrono::Utc::now().naive_utc(),
+            ended_at: None,
+            muted,
+            deafened,
+            active_seconds: 0,
+            score: 0.0,
+        });
+        Ok(())
+    }
+
+    async fn close_voice_session(&self, guild: u64, user: i64) -> Result<(), Error> {
+        let mut state = self.state();
+        while let Some(session) = state.open_voice_session(guild, user) {
+            session.ended_at = Some(chrono::Utc::now().naive_utc());
+        }
+        Ok(())
+    }
+
+    async fn has_open_voice_session(&self, guild: u64, user: i64) -> Result<bool, Error> {
+        Ok(self.state().open_voice_session(guild, user).is_some())
+    }
+
+    async fn add_voice_activity(
+        &self,
+        guild: u64,
+        user: i64,
+        seconds: i32,
+        score: f32,
+    ) -> Result<(), Error> {
+        if let Some(session) = self.state().open_voice_session(guild, user) {
+            session.active_seconds += seconds;
+            session.score += score;
+        }
+        Ok(())
+    }
+
+    async fn close_stale_voice_sessions(&self) -> Result<u64, Error> {
+        let mut closed = 0;
+        for session in self
+            .state()
+            .voice_sessions
+            .iter_mut()
+            .filter(|v| v.ended_at.is_none())
+        {
+            session.ended_at =
+                Some(session.started_at + chrono::Duration::seconds(session.active_seconds as i64));
+            closed += 1;
+        }
+        Ok(closed)
+    }
+
+    async fn voice_totals(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<(i64, i64, f32)>, Error> {
+        let mut totals: HashMap<i64, (i64, f32)> = HashMap::new();
+        for session in self
+            .state()
+            .voice_sessions
+            .iter()
+            .filter(|v| v.guild == guild as i64)
+            .filter(|v| user.is_none_or(|user| v.user == user))
+        {
+            let total = totals.entry(session.user).or_insert((0, 0.0));
+            total.0 += session.active_seconds as i64;
+            total.1 += session.score;
+        }
+
+        let mut totals = totals
+            .into_iter()
+            .map(|(user, (seconds, score))| (user, seconds, score))
+            .collect::<Vec<_>>();
+        totals.sort_by_key(|total| std::cmp::Reverse(total.1));
+        Ok(totals
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engagement::{REACTION_BONUS, REPLY_BONUS};
+
+    const ALICE: i64 = 100;
+    const BOB: i64 = 101;
+    const CAROL: i64 = 102;
+
+    async fn post(repo: &MemoryRepository, id: u64, author: i64, replys_to: Option<i64>) {
+        let message = Message {
+            id,
+            author: author as u64,
+            channel: 10,
+            content: "a message".to_string(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            attachments: vec![],
+            mention_count: 0,
+        };
+        repo.store_message(&message, 1, None, 5.0, replys_to)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn engagement_counts_every_other_user_once() {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(1, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, 1, "general".to_string())
+            .await
+            .unwrap();
+        for (user, name) in [(ALICE, "alice"), (BOB, "bob"), (CAROL, "carol")] {
+            repo.insert_user(user as u64, 1, name.to_string())
+                .await
+                .unwrap();
+        }
+        post(&repo, 1000, ALICE, None).await;
+        post(&repo, 1001, BOB, Some(1000)).await;
+        post(&repo, 1002, BOB, Some(1000)).await;
+        post(&repo, 1003, CAROL, Some(1000)).await;
+        post(&repo, 1004, ALICE, Some(1000)).await;
+        let now = chrono::Utc::now().naive_utc();
+        repo.insert_reactions(1000, "👍", &[ALICE, BOB, CAROL], now)
+            .await
+            .unwrap();
+        repo.insert_reactions(1000, "🎉", &[BOB], now)
+            .await
+            .unwrap();
+
+        let bonus = 2.0 * REPLY_BONUS + 2.0 * REACTION_BONUS;
+        assert_eq!(repo.refresh_engagement(1000).await.unwrap(), bonus);
+        let message = repo.message(1000).await.unwrap().unwrap();
+        assert_eq!(message.engagement, bonus);
+        assert_eq!(message.score, 5.0 + bonus);
+        assert_eq!(message.reaction_count, 3);
+
+        // nothing changed, nothing to apply
+        assert_eq!(repo.refresh_engagement(1000).await.unwrap(), 0.0);
+    }
+}
@@ -0,0 +1,81 @@
+use super::MemoryRepository;
+use crate::model::{GuildSettings, Stopword};
+use crate::repository::SettingsRepository;
+use crate::Error;
+use async_trait::async_trait;
+
+#[async_trait]
+impl SettingsRepository for MemoryRepository {
+    async fn guild_settings(&self, guild: u64) -> Result<Option<GuildSettings>, Error> {
+        Ok(self.state().guild_settings.get(&(guild as i64)).cloned())
+    }
+
+    async fn save_guild_settings(&self, settings: GuildSettings) -> Result<(), Error> {
+        self.state().guild_settings.insert(settings.guild, settings);
+        Ok(())
+    }
+
+    async fn retention_policies(&self) -> Result<Vec<GuildSettings>, Error> {
+        Ok(self
+            .state()
+            .guild_settings
+            .values()
+            .filter(|s| s.retention_days.is_some())
+            .cloned()
+            .collect())
+    }
+
+    async fn stopwords(&self, guild: u64) -> Result<Vec<Stopword>, Error> {
+        Ok(self
+            .state()
+            .stopwords
+            .values()
+            .filter(|s| s.guild == guild as i64)
+            .cloned()
+            .collect())
+    }
+
+    async fn set_stopword(&self, guild: u64, word: &str, removed: bool) -> Result<(), Error> {
+        self.state().stopwords.insert(
+            (guild as i64, word.to_string()),
+            Stopword {
+                guild: guild as i64,
+                word: word.to_string(),
+                removed,
+            },
+        );
+        Ok(())
+    }
+
+    async fn delete_stopword(&self, guild: u64, word: &str) -> Result<bool, Error> {
+        Ok(self
+            .state()
+            .stopwords
+            .remove(&(guild as i64, word.to_string()))
+            .is_some())
+    }
+
+    async fn bot_manager_roles(&self, guild: u64) -> Result<Vec<u64>, Error> {
+        Ok(self
+            .state()
+            .bot_manager_roles
+            .iter()
+            .filter(|(g, _)| *g == guild as i64)
+            .map(|(_, role)| *role as u64)
+            .collect())
+    }
+
+    async fn add_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error> {
+        self.state()
+            .bot_manager_roles
+            .insert((guild as i64, role as i64));
+        Ok(())
+    }
+
+    async fn remove_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error> {
+        self.state()
+            .bot_manager_roles
+            .remove(&(guild as i64, role as i64));
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+use super::{MemoryRepository, State};
+use crate::model::StoredMessage;
+use crate::replies::{Thread, MAX_THREAD_DEPTH};
+use crate::repository::StatsRepository;
+use crate::rollups::DailyTotal;
+use crate::stats::{ChannelTotal, Contributor, StatsScope, Summary};
+use crate::words::{WordCount, WordScope};
+use crate::Error;
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Whether a message is in scope, ignoring `since`
+fn in_scope(state: &State, scope: &StatsScope, message: &StoredMessage) -> bool {
+    state.guild_of_message(message) == Some(scope.guild as i64)
+        && scope
+            .channel
+            .is_none_or(|channel| message.channel == channel)
+        && scope.user.is_none_or(|user| message.user == user)
+}
+
+fn in_word_scope(state: &State, scope: &WordScope, message: &StoredMessage) -> bool {
+    state.guild_of_message(message) == Some(scope.guild as i64)
+        && scope.user.is_none_or(|user| message.user == user)
+        && scope
+            .channel
+            .is_none_or(|channel| message.channel == channel)
+        && scope.since.is_none_or(|since| message.timestamp > since)
+}
+
+/// The messages in scope from `scope.since` on
+fn scoped<'a>(
+    state: &'a State,
+    scope: &'a StatsScope,
+) -> impl Iterator<Item = &'a StoredMessage> + 'a {
+    state
+        .messages
+        .values()
+        .filter(move |m| m.timestamp >= scope.since && in_scope(state, scope, m))
+}
+
+/// Message count and summed score per key, best first, at most `limit`
+fn ranked(totals: HashMap<i64, (i64, f64)>, limit: u64) -> Vec<(i64, i64, f64)> {
+    let mut totals = totals
+        .into_iter()
+        .map(|(key, (messages, score))| (key, messages, score))
+        .collect::<Vec<_>>();
+    totals.sort_by(|a, b| b.2.total_cmp(&a.2));
+    totals.truncate(limit as usize);
+    totals
+}
+
+/// Summed count per key of the word rows in scope, most used first
+fn counted(
+    state: &State,
+    rows: &[(i64, String, i32)],
+    scope: &WordScope,
+    min_uses: i64,
+    limit: Option<u64>,
+) -> Vec<(String, i64)> {
+    let mut uses: HashMap<&str, i64> = HashMap::new();
+    for (message, word, count) in rows.iter() {
+        if state
+            .messages
+            .get(message)
+            .is_some_and(|m| in_word_scope(state, scope, m))
+        {
+            *uses.entry(word).or_insert(0) += *count as i64;
+        }
+    }
+
+    let mut uses = uses
+        .into_iter()
+        .filter(|(_, uses)| *uses >= min_uses)
+        .map(|(word, uses)| (word.to_string(), uses))
+        .collect::<Vec<_>>();
+    uses.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    uses.truncate(limit.unwrap_or(u64::MAX) as usize);
+    uses
+}
+
+#[async_trait]
+impl StatsRepository for MemoryRepository {
+    async fn message_days(&self, scope: &StatsScope) -> Result<Vec<(NaiveDate, i64)>, Error> {
+        let state = self.state();
+        let mut days = BTreeMap::new();
+        for message in state
+            .messages
+            .values()
+            .filter(|m| in_scope(&state, scope, m))
+        {
+            // the rollups only know the day of a message
+            let counted = match scope.user {
+                None => message.timestamp.date() >= scope.since.date(),
+                Some(_) => message.timestamp >= scope.since,
+            };
+            if counted {
+                *days.entry(message.timestamp.date()).or_insert(0) += 1;
+            }
+        }
+        Ok(days.into_iter().collect())
+    }
+
+    async fn message_hours(
+        &self,
+        scope: &StatsScope,
+        tz: &str,
+    ) -> Result<Vec<(u32, u32, i64)>, Error> {
+        let tz = tz.parse::<chrono_tz::Tz>()?;
+        let state = self.state();
+        let mut hours = BTreeMap::new();
+        for message in scoped(&state, scope) {
+            let local = tz.from_utc_datetime(&message.timestamp);
+            *hours
+                .entry((local.weekday().num_days_from_monday(), local.hour()))
+                .or_insert(0) += 1;
+        }
+        Ok(hours
+            .into_iter()
+            .map(|((weekday, hour), messages)| (weekday, hour, messages))
+            .collect())
+    }
+
+    async fn top_contributors(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<Contributor>, Error> {
+        let state = self.state();
+        let mut totals: HashMap<i64, (i64, f64)> = HashMap::new();
+        for message in scoped(&state, scope) {
+            let total = totals.entry(message.user).or_insert((0, 0.0));
+            total.0 += 1;
+            total.1 += message.score as f64;
+        }
+        Ok(ranked(totals, limit)
+            .into_iter()
+            .map(|(user, messages, score)| Contributor {
+                user,
+                messages,
+                score,
+            })
+            .collect())
+    }
+
+    async fn top_channels(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<ChannelTotal>, Error> {
+        let state = self.state();
+        let mut totals: HashMap<i64, (i64, f64)> = HashMap::new();
+        for message in scoped(&state, scope) {
+            let total = totals.entry(message.channel).or_insert((0, 0.0));
+            total.0 += 1;
+            total.1 += message.score as f64;
+        }
+        Ok(ranked(totals, limit)
+            .into_iter()
+            .map(|(channel, messages, score)| ChannelTotal {
+                channel,
+                messages,
+                score,
+            })
+            .collect())
+    }
+
+    async fn summary(&self, scope: &StatsScope) -> Result<Summary, Error> {
+        let state = self.state();
+        let mut users = HashSet::new();
+        let mut messages = 0;
+        let mut score = 0.0;
+        for message in scoped(&state, scope) {
+            users.insert(message.user);
+            messages += 1;
+            score += message.score as f64;
+        }
+        Ok(Summary {
+            messages,
+            users: users.len() as i64,
+            average_score: (messages > 0).then(|| score / messages as f64),
+        })
+    }
+
+    async fn new_users(&self, scope: &StatsScope) -> Result<i64, Error> {
+        let state = self.state();
+        let mut firsts: HashMap<i64, NaiveDateTime> = HashMap::new();
+        for message in state
+            .messages
+            .values()
+            .filter(|m| in_scope(&state, scope, m))
+        {
+            let first = firsts.entry(message.user).or_insert(message.timestamp);
+            *first = (*first).min(message.timestamp);
+        }
+        Ok(firsts
+            .values()
+            .filter(|first| **first >= scope.since)
+            .count() as i64)
+    }
+
+    async fn daily_totals_since(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        since: NaiveDate,
+    ) -> Result<Vec<DailyTotal>, Error> {
+        let state = self.state();
+        let mut days: BTreeMap<NaiveDate, DailyTotal> = BTreeMap::new();
+        for message in state.messages.values().filter(|m| {
+            m.timestamp.date() >= since
+                && user.is_none_or(|user| m.user == user)
+                && state.guild_of_message(m) == Some(guild as i64)
+        }) {
+            let date = message.timestamp.date();
+            let day = days.entry(date).or_insert(DailyTotal {
+                date,
+                score: 0.0,
+                messages: 0,
+            });
+            day.score += message.score;
+            day.messages += 1;
+        }
+        Ok(days.into_values().collect())
+    }
+
+    async fn reply_counts(&self, guild: u64) -> Result<HashMap<(i64, i64), i64>, Error> {
+        let state = self.state();
+        let mut counts = HashMap::new();
+        for message in state
+            .messages
+            .values()
+            .filter(|m| state.guild_of_message(m) == Some(guild as i64))
+        {
+            if let Some(parent) = message.replys_to.and_then(|p| state.messages.get(&p)) {
+                if parent.user != message.user {
+                    *counts.entry((message.user, parent.user)).or_insert(0) += 1;
+                }
+            }
+        }
+        Ok(counts)
+    }
+
+    async fn threads(&self, guild: u64) -> Result<Vec<Thread>, Error> {
+        let state = self.state();
+        let mut replies: HashMap<i64, Vec<i64>> = HashMap::new();
+        for message in state.messages.values() {
+            if let Some(parent) = message.replys_to {
+                replies.entry(parent).or_default().push(message.snowflake);
+            }
+        }
+
+        let mut threads = Vec::new();
+        for root in state.messages.values().filter(|m| {
+            state.guild_of_message(m) == Some(guild as i64)
+                && !m.replys_to.is_some_and(|p| state.messages.contains_key(&p))
+        }) {
+            let mut thread = Thread {
+                root: root.snowflake,
+                channel: root.channel,
+                starter: root.user,
+                depth: 0,
+                replies: 0,
+            };
+            let mut level = vec![root.snowflake];
+            let mut depth = 0;
+            while depth < MAX_THREAD_DEPTH {
+                level = level
+                    .iter()
+                    .flat_map(|m| replies.get(m).into_iter().flatten().copied())
+                    .collect();
+                if level.is_empty() {
+                    break;
+                }
+                depth += 1;
+                thread.depth = depth;
+                thread.replies += level.len() as i64;
+            }
+            if thread.replies > 0 {
+                threads.push(thread);
+            }
+        }
+        Ok(threads)
+    }
+
+    async fn replies_between(&self, a: i64, b: i64) -> Result<(i64, i64), Error> {
+        let state = self.state();
+        let (mut forward, mut backward) = (0, 0);
+        for message in state.messages.values() {
+            let parent = match message.replys_to.and_then(|p| state.messages.get(&p)) {
+                Some(parent) => parent.user,
+                None => continue,
+            };
+            if (message.user, parent) != (a, b) && (message.user, parent) != (b, a) {
+                continue;
+            }
+            if message.user == a {
+                forward += 1;
+            }
+            if message.user == b {
+                backward += 1;
+            }
+        }
+        Ok((forward, backward))
+    }
+
+    async fn word_counts(
+        &self,
+        scope: &WordScope,
+        limit: Option<u64>,
+    ) -> Result<Vec<(String, i64)>, Error> {
+        let state = self.state();
+        Ok(counted(&state, &state.tokens, scope, 1, limit))
+    }
+
+    async fn bigram_counts(
+        &self,
+        scope: &WordScope,
+        limit: u64,
+    ) -> Result<Vec<(String, i64)>, Error> {
+        let state = self.state();
+        Ok(counted(&state, &state.bigrams, scope, 2, Some(limit)))
+    }
+
+    async fn word_count(&self, scope: &WordScope, word: &str) -> Result<WordCount, Error> {
+        let state = self.state();
+        let mut count = WordCount {
+            uses: 0,
+            messages: 0,
+            first_used: None,
+            top_users: Vec::new(),
+        };
+        let mut users: HashMap<i64, i64> = HashMap::new();
+        for (message, count_in_message) in state
+            .tokens
+            .iter()
+            .filter(|t| t.1 == word)
+            .filter_map(|t| state.messages.get(&t.0).map(|m| (m, t.2)))
+            .filter(|(m, _)| in_word_scope(&state, scope, m))
+        {
+            count.uses += count_in_message as i64;
+            count.messages += 1;
+            count.first_used = Some(
+                count
+                    .first_used
+                    .map_or(message.timestamp, |first| first.min(message.timestamp)),
+            );
+            *users.entry(message.user).or_insert(0) += count_in_message as i64;
+        }
+
+        let mut users = users.into_iter().collect::<Vec<_>>();
+        users.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        users.truncate(5);
+        count.top_users = users;
+        Ok(count)
+    }
+}
@@ -0,0 +1,161 @@
+use crate::message::Message;
+use crate::model::{Channel, Guild, StoredMessage, User};
+use crate::rollups::UserTotal;
+use crate::Error;
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::{HashMap, HashSet};
+
+mod admin;
+mod memory;
+mod postgres;
+mod settings;
+mod stats;
+
+pub use admin::AdminRepository;
+pub use memory::MemoryRepository;
+pub use postgres::PostgresRepository;
+pub use settings::SettingsRepository;
+pub use stats::StatsRepository;
+
+/// Everything the bot reads and writes about guilds, channels, users and their messages,
+/// reactions and voice sessions. Stats, settings and admin jobs are in the traits it extends.
+/// Ids are Discord snowflakes, stored as `i64` in the models.
+#[async_trait]
+pub trait Repository: StatsRepository + SettingsRepository + AdminRepository + Send + Sync {
+    async fn guild_ids(&self) -> Result<HashSet<u64>, Error>;
+    async fn channel_ids(&self) -> Result<HashSet<u64>, Error>;
+    async fn user_ids(&self) -> Result<HashSet<u64>, Error>;
+
+    async fn guild(&self, guild: u64) -> Result<Option<Guild>, Error>;
+    async fn insert_guild(&self, guild: u64, name: String) -> Result<Guild, Error>;
+    /// Store a guild unless it already is
+    async fn ensure_guild(&self, guild: u64, name: &str) -> Result<(), Error>;
+    /// Delete a guild and its channels, users and messages, false if it wasn't stored
+    async fn delete_guild(&self, guild: u64) -> Result<bool, Error>;
+
+    async fn channel(&self, channel: u64) -> Result<Option<Channel>, Error>;
+    async fn insert_channel(
+        &self,
+        channel: u64,
+        guild: u64,
+        name: String,
+    ) -> Result<Channel, Error>;
+    /// Channels of a guild, highest score first
+    async fn guild_channels(&self, guild: u64) -> Result<Vec<Channel>, Error>;
+
+    async fn user(&self, user: u64) -> Result<Option<User>, Error>;
+    async fn insert_user(&self, user: u64, guild: u64, name: String) -> Result<User, Error>;
+    /// Store a user unless it already is
+    async fn ensure_user(&self, user: u64, guild: u64, name: &str) -> Result<(), Error>;
+    /// Users of a guild, highest score first, skipping `offset` and returning at most `limit`
+    async fn users_by_score(
+        &self,
+        guild: u64,
+        offset: u64,
+        limit: Option<u64>,
+    ) -> Result<Vec<User>, Error>;
+    async fn user_names(&self, users: &[i64]) -> Result<HashMap<i64, String>, Error>;
+    /// Number of users stored for a guild
+    async fn user_count(&self, guild: u64) -> Result<u64, Error>;
+    /// Number of users of a guild with a higher score than `score`
+    async fn users_above(&self, guild: u64, score: f32) -> Result<u64, Error>;
+
+    /// Add to a guild's running score and message count
+    async fn add_to_guild(&self, guild: i64, score: f32, messages: i32) -> Result<(), Error>;
+    /// Add to a channel's running score and message count
+    async fn add_to_channel(&self, channel: i64, score: f32, messages: i32) -> Result<(), Error>;
+    /// Add to a user's running score and message count
+    async fn add_to_user(&self, user: i64, score: f32, messages: i32) -> Result<(), Error>;
+
+    async fn message(&self, message: u64) -> Result<Option<StoredMessage>, Error>;
+    /// Stored content of a user's latest messages, newest first
+    async fn recent_contents(&self, user: u64, limit: u64) -> Result<Vec<String>, Error>;
+    /// A user's messages, optionally only in one channel or after a time
+    async fn user_messages(
+        &self,
+        user: i64,
+        channel: Option<i64>,
+        since: Option<NaiveDateTime>,
+    ) -> Result<Vec<StoredMessage>, Error>;
+    /// Store a new message with its features, adding it to the daily rollups but not to the
+    /// running totals. `content` is what the guild's retention policy keeps of it.
+    async fn store_message(
+        &self,
+        message: &Message,
+        guild: u64,
+        content: Option<String>,
+        score: f32,
+        replys_to: Option<i64>,
+    ) -> Result<(), Error>;
+    /// Recompute the engagement bonus of a message from its replies and reactions and apply
+    /// the change everywhere, returns the change in score
+    async fn refresh_engagement(&self, message: i64) -> Result<f32, Error>;
+
+    /// Store reactions of `users` with `emoji`, reactions already stored are skipped.
+    /// The engagement of the message isn't refreshed.
+    async fn insert_reactions(
+        &self,
+        message: i64,
+        emoji: &str,
+        users: &[i64],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), Error>;
+    /// Delete the reactions on a message, optionally only those of one user or emoji
+    async fn delete_reactions(
+        &self,
+        message: i64,
+        user: Option<i64>,
+        emoji: Option<&str>,
+    ) -> Result<(), Error>;
+    /// The emoji a user reacts with most and how often
+    async fn favorite_emoji(&self, user: i64) -> Result<Option<(String, i64)>, Error>;
+    async fn reactions_given(&self, user: i64) -> Result<u64, Error>;
+    /// Reactions from other users on a user's messages
+    async fn reactions_received(&self, user: i64) -> Result<u64, Error>;
+    /// Every stored word of a user's messages with how often it was used in each
+    async fn user_tokens(&self, user: i64) -> Result<Vec<(String, i32)>, Error>;
+
+    /// Score and message count per user of a guild from the start of `since` on
+    async fn user_totals_since(
+        &self,
+        guild: u64,
+        since: NaiveDate,
+    ) -> Result<HashMap<i64, UserTotal>, Error>;
+    /// Net score adjustment per user in a guild since `since`, without imports
+    async fn adjustments_since(
+        &self,
+        guild: u64,
+        since: NaiveDateTime,
+    ) -> Result<HashMap<i64, f32>, Error>;
+    /// Start a voice session of a user in a channel
+    async fn open_voice_session(
+        &self,
+        guild: u64,
+        user: i64,
+        channel: i64,
+        muted: bool,
+        deafened: bool,
+    ) -> Result<(), Error>;
+    /// End the open voice session of a user, if there is one
+    async fn close_voice_session(&self, guild: u64, user: i64) -> Result<(), Error>;
+    async fn has_open_voice_session(&self, guild: u64, user: i64) -> Result<bool, Error>;
+    /// Add active time and points to the open voice session of a user
+    async fn add_voice_activity(
+        &self,
+        guild: u64,
+        user: i64,
+        seconds: i32,
+        score: f32,
+    ) -> Result<(), Error>;
+    /// End every open session at the last time it earned points, returns how many there were
+    async fn close_stale_voice_sessions(&self) -> Result<u64, Error>;
+    /// Active voice seconds and voice score per user in a guild, most active first
+    async fn voice_totals(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<(i64, i64, f32)>, Error>;
+}
@@ -0,0 +1,88 @@
+use super::PostgresRepository;
+use crate::adjustments::{self, ImportedBatch, NewAdjustment, NewImport};
+use crate::maintenance::{self, DeletedUser, ReconcileSummary};
+use crate::model::{Adjustment, StoredMessage};
+use crate::repository::AdminRepository;
+use crate::{rollups, Error};
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+#[async_trait]
+impl AdminRepository for PostgresRepository {
+    async fn apply_adjustment(&self, adjustment: NewAdjustment) -> Result<Adjustment, Error> {
+        Ok(adjustments::apply_adjustment(&self.db, adjustment)
+            .await?
+            .into())
+    }
+
+    async fn adjustment_history(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<Adjustment>, Error> {
+        Ok(
+            adjustments::adjustment_history(&self.db, guild, user, offset, limit)
+                .await?
+                .into_iter()
+                .map(Adjustment::from)
+                .collect(),
+        )
+    }
+
+    async fn import_adjustments(&self, import: NewImport) -> Result<ImportedBatch, Error> {
+        adjustments::import_adjustments(&self.db, import).await
+    }
+
+    async fn revert_import(&self, guild: u64, batch: &str) -> Result<usize, Error> {
+        adjustments::revert_import(&self.db, guild, batch).await
+    }
+
+    async fn import_batches(&self, guild: u64) -> Result<BTreeMap<String, usize>, Error> {
+        adjustments::import_batches(&self.db, guild).await
+    }
+
+    async fn guild_messages(
+        &self,
+        guild: u64,
+        from: Option<NaiveDateTime>,
+        until: Option<NaiveDateTime>,
+        after: Option<i64>,
+        limit: u64,
+    ) -> Result<Vec<StoredMessage>, Error> {
+        Ok(
+            maintenance::guild_messages(&self.db, guild, from, until, after, limit)
+                .await?
+                .into_iter()
+                .map(StoredMessage::from)
+                .collect(),
+        )
+    }
+
+    async fn set_message_scores(&self, scores: &[(i64, f32)]) -> Result<(), Error> {
+        maintenance::set_message_scores(&self.db, scores).await
+    }
+
+    async fn prune_content(
+        &self,
+        guild: u64,
+        before: NaiveDateTime,
+        truncate: Option<i32>,
+    ) -> Result<u64, Error> {
+        maintenance::prune_content(&self.db, guild, before, truncate).await
+    }
+
+    async fn delete_user(&self, user: i64) -> Result<DeletedUser, Error> {
+        maintenance::delete_user(&self.db, user).await
+    }
+
+    async fn reconcile_aggregates(&self, guild: Option<u64>) -> Result<ReconcileSummary, Error> {
+        maintenance::reconcile_aggregates(&self.db, guild).await
+    }
+
+    async fn rebuild_rollups(&self, guild: Option<u64>) -> Result<(u64, u64), Error> {
+        rollups::rebuild_rollups(&self.db, guild).await
+    }
+}
@@ -0,0 +1,119 @@
+use crate::model::{Adjustment, Channel, Guild, GuildSettings, Stopword, StoredMessage, User};
+use entity::{
+    channels, guild_settings, guild_stopwords, guilds, messages, score_adjustments, users,
+};
+
+// Rows are turned into the domain types as soon as they are read
+
+impl From<guilds::Model> for Guild {
+    fn from(guild: guilds::Model) -> Self {
+        Guild {
+            snowflake: guild.snowflake,
+            name: guild.name,
+            score: guild.score,
+            message_count: guild.message_count,
+            user_count: guild.user_count,
+        }
+    }
+}
+
+impl From<channels::Model> for Channel {
+    fn from(channel: channels::Model) -> Self {
+        Channel {
+            snowflake: channel.snowflake,
+            name: channel.name,
+            score: channel.score,
+            message_count: channel.message_count,
+            guild: channel.guild,
+        }
+    }
+}
+
+impl From<users::Model> for User {
+    fn from(user: users::Model) -> Self {
+        User {
+            snowflake: user.snowflake,
+            name: user.name,
+            message_count: user.message_count,
+            score: user.score,
+            guild: user.guild,
+        }
+    }
+}
+
+impl From<messages::Model> for StoredMessage {
+    fn from(message: messages::Model) -> Self {
+        StoredMessage {
+            snowflake: message.snowflake,
+            content: message.content,
+            score: message.score,
+            replys_to: message.replys_to,
+            channel: message.channel,
+            user: message.user,
+            timestamp: message.timestamp,
+            length: message.length,
+            word_count: message.word_count,
+            engagement: message.engagement,
+            reaction_count: message.reaction_count,
+            attachment_count: message.attachment_count,
+            attachment_types: message.attachment_types,
+            link_count: message.link_count,
+            code_lines: message.code_lines,
+            emoji_only: message.emoji_only,
+            mention_count: message.mention_count,
+        }
+    }
+}
+
+impl From<guild_settings::Model> for GuildSettings {
+    fn from(settings: guild_settings::Model) -> Self {
+        GuildSettings {
+            guild: settings.guild,
+            retention_days: settings.retention_days,
+            retention_truncate: settings.retention_truncate,
+            voice_points_per_minute: settings.voice_points_per_minute,
+            scoring: settings.scoring,
+            stopword_languages: settings.stopword_languages,
+        }
+    }
+}
+
+impl From<GuildSettings> for guild_settings::Model {
+    fn from(settings: GuildSettings) -> Self {
+        guild_settings::Model {
+            guild: settings.guild,
+            retention_days: settings.retention_days,
+            retention_truncate: settings.retention_truncate,
+            voice_points_per_minute: settings.voice_points_per_minute,
+            scoring: settings.scoring,
+            stopword_languages: settings.stopword_languages,
+        }
+    }
+}
+
+impl From<guild_stopwords::Model> for Stopword {
+    fn from(stopword: guild_stopwords::Model) -> Self {
+        Stopword {
+            guild: stopword.guild,
+            word: stopword.word,
+            removed: stopword.removed,
+        }
+    }
+}
+
+impl From<score_adjustments::Model> for Adjustment {
+    fn from(adjustment: score_adjustments::Model) -> Self {
+        Adjustment {
+            id: adjustment.id,
+            guild: adjustment.guild,
+            user: adjustment.user,
+            moderator: adjustment.moderator,
+            delta: adjustment.delta,
+            message_delta: adjustment.message_delta,
+            source: adjustment.source,
+            batch: adjustment.batch,
+            reason: adjustment.reason,
+            timestamp: adjustment.timestamp,
+        }
+    }
+}
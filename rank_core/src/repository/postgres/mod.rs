@@ -0,0 +1,552 @@
+use super::Repository;
+use crate::message::Message;
+use crate::model::{Channel, Guild, StoredMessage, User};
+use crate::rollups::UserTotal;
+use crate::{adjustments, aggregates, engagement, ingest, rollups, Error};
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use entity::prelude::{Channels, Guilds, MessageTokens, Messages, Reactions, Users, VoiceSessions};
+use entity::{channels, guilds, messages, reactions, users, voice_sessions};
+use sea_orm::sea_query::{Alias, Expr, OnConflict};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, Select, Set,
+};
+use std::collections::{HashMap, HashSet};
+
+mod admin;
+mod convert;
+#[cfg(test)]
+mod query_plans;
+mod settings;
+mod stats;
+
+/// The repository the bot runs on
+#[derive(Clone)]
+pub struct PostgresRepository {
+    db: DatabaseConnection,
+}
+
+impl PostgresRepository {
+    pub fn new(db: DatabaseConnection) -> Self {
+        PostgresRepository { db }
+    }
+}
+
+/// The query behind `users_by_score`
+fn users_by_score_query(guild: u64, offset: u64, limit: Option<u64>) -> Select<Users> {
+    Users::find()
+        .filter(users::Column::Guild.eq(guild as i64))
+        .order_by_desc(users::Column::Score)
+        .offset(offset)
+        .limit(limit)
+}
+
+/// The query behind `users_above`
+fn users_above_query(guild: u64, score: f32) -> Select<Users> {
+    Users::find()
+        .filter(users::Column::Guild.eq(guild as i64))
+        .filter(users::Column::Score.gt(score))
+}
+
+/// The query behind `recent_contents`
+fn recent_contents_query(user: u64, limit: u64) -> Select<Messages> {
+    Messages::find()
+        .select_only()
+        .column(messages::Column::Content)
+        .filter(messages::Column::User.eq(user as i64))
+        .order_by_desc(messages::Column::Snowflake)
+        .limit(limit)
+}
+
+/// The query behind `user_messages`
+fn user_messages_query(
+    user: i64,
+    channel: Option<i64>,
+    since: Option<NaiveDateTime>,
+) -> Select<Messages> {
+    let mut query = Messages::find().filter(messages::Column::User.eq(user));
+    if let Some(channel) = channel {
+        query = query.filter(messages::Column::Channel.eq(channel));
+    }
+    if let Some(since) = since {
+        query = query.filter(messages::Column::Timestamp.gt(since));
+    }
+    query
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    async fn guild_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(Guilds::find()
+            .select_only()
+            .column(guilds::Column::Snowflake)
+            .into_tuple::<i64>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|g| g as u64)
+            .collect())
+    }
+
+    async fn channel_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(Channels::find()
+            .select_only()
+            .column(channels::Column::Snowflake)
+            .into_tuple::<i64>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|c| c as u64)
+            .collect())
+    }
+
+    async fn user_ids(&self) -> Result<HashSet<u64>, Error> {
+        Ok(Users::find()
+            .select_only()
+            .column(users::Column::Snowflake)
+            .into_tuple::<i64>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|u| u as u64)
+            .collect())
+    }
+
+    async fn guild(&self, guild: u64) -> Result<Option<Guild>, Error> {
+        Ok(Guilds::find_by_id(guild as i64)
+            .one(&self.db)
+            .await?
+            .map(Guild::from))
+    }
+
+    async fn insert_guild(&self, guild: u64, name: String) -> Result<Guild, Error> {
+        Ok(guilds::ActiveModel {
+            snowflake: Set(guild as i64),
+            name: Set(name),
+            score: Set(0.),
+            message_count: Set(0),
+            user_count: Set(0),
+        }
+        .insert(&self.db)
+        .await?
+        .into())
+    }
+
+    async fn ensure_guild(&self, guild: u64, name: &str) -> Result<(), Error> {
+        Guilds::insert(guilds::ActiveModel {
+            snowflake: Set(guild as i64),
+            name: Set(name.to_string()),
+            score: Set(0.),
+            message_count: Set(0),
+            user_count: Set(0),
+        })
+        .on_conflict(
+            OnConflict::column(guilds::Column::Snowflake)
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_guild(&self, guild: u64) -> Result<bool, Error> {
+        match Guilds::find_by_id(guild as i64).one(&self.db).await? {
+            Some(stored) => {
+                // channels, users and their messages cascade
+                stored.delete(&self.db).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn channel(&self, channel: u64) -> Result<Option<Channel>, Error> {
+        Ok(Channels::find_by_id(channel as i64)
+            .one(&self.db)
+            .await?
+            .map(Channel::from))
+    }
+
+    async fn insert_channel(
+        &self,
+        channel: u64,
+        guild: u64,
+        name: String,
+    ) -> Result<Channel, Error> {
+        Ok(channels::ActiveModel {
+            snowflake: Set(channel as i64),
+            name: Set(name),
+            score: Set(0.),
+            message_count: Set(0),
+            guild: Set(guild as i64),
+        }
+        .insert(&self.db)
+        .await?
+        .into())
+    }
+
+    async fn guild_channels(&self, guild: u64) -> Result<Vec<Channel>, Error> {
+        Ok(Channels::find()
+            .filter(channels::Column::Guild.eq(guild as i64))
+            .order_by_desc(channels::Column::Score)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Channel::from)
+            .collect())
+    }
+
+    async fn user(&self, user: u64) -> Result<Option<User>, Error> {
+        Ok(Users::find_by_id(user as i64)
+            .one(&self.db)
+            .await?
+            .map(User::from))
+    }
+
+    async fn insert_user(&self, user: u64, guild: u64, name: String) -> Result<User, Error> {
+        Ok(users::ActiveModel {
+            snowflake: Set(user as i64),
+            name: Set(name),
+            score: Set(0.),
+            message_count: Set(0),
+            guild: Set(guild as i64),
+        }
+        .insert(&self.db)
+        .await?
+        .into())
+    }
+
+    async fn ensure_user(&self, user: u64, guild: u64, name: &str) -> Result<(), Error> {
+        Users::insert(users::ActiveModel {
+            snowflake: Set(user as i64),
+            name: Set(name.to_string()),
+            score: Set(0.),
+            message_count: Set(0),
+            guild: Set(guild as i64),
+        })
+        .on_conflict(
+            OnConflict::column(users::Column::Snowflake)
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn users_by_score(
+        &self,
+        guild: u64,
+        offset: u64,
+        limit: Option<u64>,
+    ) -> Result<Vec<User>, Error> {
+        Ok(users_by_score_query(guild, offset, limit)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(User::from)
+            .collect())
+    }
+
+    async fn user_names(&self, users: &[i64]) -> Result<HashMap<i64, String>, Error> {
+        Ok(Users::find()
+            .filter(users::Column::Snowflake.is_in(users.iter().copied()))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|u| (u.snowflake, u.name))
+            .collect())
+    }
+
+    async fn user_count(&self, guild: u64) -> Result<u64, Error> {
+        Ok(Users::find()
+            .filter(users::Column::Guild.eq(guild as i64))
+            .count(&self.db)
+            .await?)
+    }
+
+    async fn users_above(&self, guild: u64, score: f32) -> Result<u64, Error> {
+        Ok(users_above_query(guild, score).count(&self.db).await?)
+    }
+
+    async fn add_to_guild(&self, guild: i64, score: f32, messages: i32) -> Result<(), Error> {
+        aggregates::add_to_guild(&self.db, guild, score, messages).await
+    }
+
+    async fn add_to_channel(&self, channel: i64, score: f32, messages: i32) -> Result<(), Error> {
+        aggregates::add_to_channel(&self.db, channel, score, messages).await
+    }
+
+    async fn add_to_user(&self, user: i64, score: f32, messages: i32) -> Result<(), Error> {
+        aggregates::add_to_user(&self.db, user, score, messages).await
+    }
+
+    async fn message(&self, message: u64) -> Result<Option<StoredMessage>, Error> {
+        Ok(Messages::find_by_id(message as i64)
+            .one(&self.db)
+            .await?
+            .map(StoredMessage::from))
+    }
+
+    async fn recent_contents(&self, user: u64, limit: u64) -> Result<Vec<String>, Error> {
+        Ok(recent_contents_query(user, limit)
+            .into_tuple::<Option<String>>()
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    async fn user_messages(
+        &self,
+        user: i64,
+        channel: Option<i64>,
+        since: Option<NaiveDateTime>,
+    ) -> Result<Vec<StoredMessage>, Error> {
+        Ok(user_messages_query(user, channel, since)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(StoredMessage::from)
+            .collect())
+    }
+
+    async fn store_message(
+        &self,
+        message: &Message,
+        guild: u64,
+        content: Option<String>,
+        score: f32,
+        replys_to: Option<i64>,
+    ) -> Result<(), Error> {
+        ingest::store_message(&self.db, message, guild, content, score, replys_to).await
+    }
+
+    async fn refresh_engagement(&self, message: i64) -> Result<f32, Error> {
+        engagement::refresh_engagement(&self.db, message).await
+    }
+
+    async fn insert_reactions(
+        &self,
+        message: i64,
+        emoji: &str,
+        users: &[i64],
+        timestamp: NaiveDateTime,
+    ) -> Result<(), Error> {
+        if users.is_empty() {
+            return Ok(());
+        }
+
+        Reactions::insert_many(users.iter().map(|user| reactions::ActiveModel {
+            message: Set(message),
+            user: Set(*user),
+            emoji: Set(emoji.to_string()),
+            timestamp: Set(timestamp),
+        }))
+        .on_conflict(
+            OnConflict::columns([
+                reactions::Column::Message,
+                reactions::Column::User,
+                reactions::Column::Emoji,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(&self.db)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_reactions(
+        &self,
+        message: i64,
+        user: Option<i64>,
+        emoji: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut delete = Reactions::delete_many().filter(reactions::Column::Message.eq(message));
+        if let Some(user) = user {
+            delete = delete.filter(reactions::Column::User.eq(user));
+        }
+        if let Some(emoji) = emoji {
+            delete = delete.filter(reactions::Column::Emoji.eq(emoji));
+        }
+        delete.exec(&self.db).await?;
+        Ok(())
+    }
+
+    async fn favorite_emoji(&self, user: i64) -> Result<Option<(String, i64)>, Error> {
+        Ok(Reactions::find()
+            .select_only()
+            .column(entity::reactions::Column::Emoji)
+            .column_as(entity::reactions::Column::Emoji.count(), "uses")
+            .filter(entity::reactions::Column::User.eq(user))
+            .group_by(entity::reactions::Column::Emoji)
+            .order_by_desc(Expr::col(Alias::new("uses")))
+            .into_tuple::<(String, i64)>()
+            .one(&self.db)
+            .await?)
+    }
+
+    async fn reactions_given(&self, user: i64) -> Result<u64, Error> {
+        Ok(Reactions::find()
+            .filter(entity::reactions::Column::User.eq(user))
+            .count(&self.db)
+            .await?)
+    }
+
+    async fn reactions_received(&self, user: i64) -> Result<u64, Error> {
+        Ok(Reactions::find()
+            .inner_join(Messages)
+            .filter(messages::Column::User.eq(user))
+            .filter(entity::reactions::Column::User.ne(user))
+            .count(&self.db)
+            .await?)
+    }
+
+    async fn user_tokens(&self, user: i64) -> Result<Vec<(String, i32)>, Error> {
+        Ok(MessageTokens::find()
+            .inner_join(Messages)
+            .filter(messages::Column::User.eq(user))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|token| (token.token, token.count))
+            .collect())
+    }
+
+    async fn user_totals_since(
+        &self,
+        guild: u64,
+        since: NaiveDate,
+    ) -> Result<HashMap<i64, UserTotal>, Error> {
+        rollups::user_totals_since(&self.db, guild, since).await
+    }
+
+    async fn adjustments_since(
+        &self,
+        guild: u64,
+        since: NaiveDateTime,
+    ) -> Result<HashMap<i64, f32>, Error> {
+        adjustments::adjustments_since(&self.db, guild, since).await
+    }
+
+    async fn open_voice_session(
+        &self,
+        guild: u64,
+        user: i64,
+        channel: i64,
+        muted: bool,
+        deafened: bool,
+    ) -> Result<(), Error> {
+        voice_sessions::ActiveModel {
+            guild: Set(guild as i64),
+            user: Set(user),
+            channel: Set(channel),
+            started_at: Set(chrono::Utc::now().naive_utc()),
+            ended_at: Set(None),
+            muted: Set(muted),
+            deafened: Set(deafened),
+            active_seconds: Set(0),
+            score: Set(0.0),
+            ..Default::default()
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn close_voice_session(&self, guild: u64, user: i64) -> Result<(), Error> {
+        VoiceSessions::update_many()
+            .col_expr(
+                voice_sessions::Column::EndedAt,
+                Expr::value(chrono::Utc::now().naive_utc()),
+            )
+            .filter(voice_sessions::Column::Guild.eq(guild as i64))
+            .filter(voice_sessions::Column::User.eq(user))
+            .filter(voice_sessions::Column::EndedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn has_open_voice_session(&self, guild: u64, user: i64) -> Result<bool, Error> {
+        Ok(VoiceSessions::find()
+            .filter(voice_sessions::Column::Guild.eq(guild as i64))
+            .filter(voice_sessions::Column::User.eq(user))
+            .filter(voice_sessions::Column::EndedAt.is_null())
+            .one(&self.db)
+            .await?
+            .is_some())
+    }
+
+    async fn add_voice_activity(
+        &self,
+        guild: u64,
+        user: i64,
+        seconds: i32,
+        score: f32,
+    ) -> Result<(), Error> {
+        VoiceSessions::update_many()
+            .col_expr(
+                voice_sessions::Column::ActiveSeconds,
+                Expr::col(voice_sessions::Column::ActiveSeconds).add(seconds),
+            )
+            .col_expr(
+                voice_sessions::Column::Score,
+                Expr::col(voice_sessions::Column::Score).add(score),
+            )
+            .filter(voice_sessions::Column::Guild.eq(guild as i64))
+            .filter(voice_sessions::Column::User.eq(user))
+            .filter(voice_sessions::Column::EndedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn close_stale_voice_sessions(&self) -> Result<u64, Error> {
+        Ok(VoiceSessions::update_many()
+            .col_expr(
+                voice_sessions::Column::EndedAt,
+                Expr::cust("\"started_at\" + \"active_seconds\" * interval '1 second'"),
+            )
+            .filter(voice_sessions::Column::EndedAt.is_null())
+            .exec(&self.db)
+            .await?
+            .rows_affected)
+    }
+
+    async fn voice_totals(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        offset: u64,
+        limit: u64,
+    ) -> Result<Vec<(i64, i64, f32)>, Error> {
+        let mut query = VoiceSessions::find()
+            .select_only()
+            .column(entity::voice_sessions::Column::User)
+            .column_as(
+                entity::voice_sessions::Column::ActiveSeconds.sum(),
+                "seconds",
+            )
+            .column_as(entity::voice_sessions::Column::Score.sum(), "score")
+            .filter(entity::voice_sessions::Column::Guild.eq(guild as i64))
+            .group_by(entity::voice_sessions::Column::User)
+            .order_by_desc(Expr::col(Alias::new("seconds")))
+            .offset(offset)
+            .limit(limit);
+
+        if let Some(user) = user {
+            query = query.filter(entity::voice_sessions::Column::User.eq(user));
+        }
+
+        Ok(query.into_tuple::<(i64, i64, f32)>().all(&self.db).await?)
+    }
+}
@@ -0,0 +1,217 @@
+use super::{recent_contents_query, user_messages_query, users_above_query, users_by_score_query};
+use crate::engagement::repliers_query;
+use crate::rollups::{channel_days_statement, daily_totals_statement, user_totals_statement};
+use crate::stats::{
+    message_days_statement, new_users_statement, summary_statement, top_contributors_statement,
+    StatsScope,
+};
+use chrono::{Duration, Utc};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{
+    ConnectionTrait, Database, DatabaseTransaction, DbBackend, QueryTrait, Statement,
+    TransactionTrait,
+};
+
+// Checks that the queries run for every message and by the stats commands are planned as index
+// scans. Needs a scratch database that migrations may be applied to, so it only runs when asked:
+//
+//     TEST_DATABASE_URL=postgres://... cargo test -p rank_core -- --ignored queries_use_indexes
+//
+// The data is seeded inside a transaction that is always rolled back.
+
+/// Seeded guilds, every guild gets `CHANNELS` channels and a share of the users
+const GUILDS: i64 = 10;
+const CHANNELS: i64 = 5;
+const USERS: i64 = 2_000;
+const MESSAGES: i64 = 100_000;
+
+/// Tables that must never be read in full by the checked queries
+const LARGE_TABLES: [&str; 4] = ["messages", "users", "user_daily", "channel_daily"];
+
+fn seed_sql() -> String {
+    format!(
+        r#"
+        INSERT INTO "guilds" ("snowflake", "name", "score", "message_count", "user_count")
+        SELECT g, 'query plan check', 0, 0, 0 FROM generate_series(1, {guilds}) g;
+
+        INSERT INTO "channels" ("snowflake", "name", "score", "message_count", "guild")
+        SELECT 100 * g + c, 'query plan check', random() * 1000, 0, g
+        FROM generate_series(1, {guilds}) g, generate_series(0, {channels} - 1) c;
+
+        INSERT INTO "users" ("snowflake", "name", "message_count", "score", "guild")
+        SELECT u, 'query plan check', 0, random() * 10000, 1 + u % {guilds}
+        FROM generate_series(1, {users}) u;
+
+        INSERT INTO "messages"
+            ("snowflake", "content", "score", "replys_to", "channel", "user", "timestamp")
+        SELECT m, NULL, random() * 100, NULLIF(m - 1, 0),
+            100 * (1 + (1 + m % {users}) % {guilds}) + (m / {users}) % {channels}, 1 + m % {users},
+            now() - m * interval '5 minutes'
+        FROM generate_series(1, {messages}) m;
+
+        INSERT INTO "user_daily" ("user", "date", "guild", "score", "message_count", "chars")
+        SELECT m."user", m."timestamp"::date, c."guild", sum(m."score"), count(*), 0
+        FROM "messages" m JOIN "channels" c ON c."snowflake" = m."channel"
+        GROUP BY m."user", m."timestamp"::date, c."guild";
+
+        INSERT INTO "channel_daily" ("channel", "date", "guild", "score", "message_count", "chars")
+        SELECT m."channel", m."timestamp"::date, c."guild", sum(m."score"), count(*), 0
+        FROM "messages" m JOIN "channels" c ON c."snowflake" = m."channel"
+        GROUP BY m."channel", m."timestamp"::date, c."guild";
+
+        ANALYZE "guilds", "channels", "users", "messages", "user_daily", "channel_daily";
+        "#,
+        guilds = GUILDS,
+        channels = CHANNELS,
+        users = USERS,
+        messages = MESSAGES
+    )
+}
+
+/// The statements the repository sends, named by who sends them
+fn checked_queries() -> Vec<(&'static str, Statement)> {
+    let (guild, channel, user, message) = (1, 101, 10, 4_000);
+    let now = Utc::now().naive_utc();
+    let (week, year) = (
+        now.date() - Duration::days(6),
+        now.date() - Duration::days(364),
+    );
+    let user_month = StatsScope {
+        guild,
+        channel: None,
+        since: now - Duration::days(30),
+        user: Some(user),
+    };
+    let channel_week = StatsScope {
+        guild,
+        channel: Some(channel),
+        since: now - Duration::weeks(1),
+        user: None,
+    };
+
+    vec![
+        (
+            "handle_message: last five messages of a user",
+            recent_contents_query(user as u64, 5).build(DbBackend::Postgres),
+        ),
+        (
+            "refresh_engagement: users replying to a message",
+            repliers_query(message, user).build(DbBackend::Postgres),
+        ),
+        (
+            "stats: messages of a user",
+            user_messages_query(user, None, None).build(DbBackend::Postgres),
+        ),
+        (
+            "stats: messages of a user in a channel this week",
+            user_messages_query(user, Some(channel), Some(now - Duration::weeks(1)))
+                .build(DbBackend::Postgres),
+        ),
+        (
+            "stats: summary of a user this month",
+            summary_statement(&user_month),
+        ),
+        (
+            "stats: days a user was active this month",
+            message_days_statement(&user_month),
+        ),
+        (
+            "channelstats: summary of a channel this week",
+            summary_statement(&channel_week),
+        ),
+        (
+            "channelstats: top contributors of a channel this week",
+            top_contributors_statement(&channel_week, 5),
+        ),
+        (
+            "channelstats: new users of a channel this week",
+            new_users_statement(&channel_week),
+        ),
+        (
+            "leaderboard: scores of a guild this week",
+            user_totals_statement(guild, week),
+        ),
+        (
+            "serverstats: messages per day of a guild this year",
+            channel_days_statement(guild, None, year),
+        ),
+        (
+            "channelstats: messages per day of a channel this year",
+            channel_days_statement(guild, Some(channel), year),
+        ),
+        (
+            "charts: score per day of a guild this year",
+            daily_totals_statement(guild, None, year),
+        ),
+        (
+            "charts: score per day of a user this year",
+            daily_totals_statement(guild, Some(user), year),
+        ),
+        (
+            "leaderboard: first page of a guild",
+            users_by_score_query(guild, 0, Some(10)).build(DbBackend::Postgres),
+        ),
+        (
+            "stats: users of a guild above a score",
+            users_above_query(guild, 9_500.0).build(DbBackend::Postgres),
+        ),
+    ]
+}
+
+async fn explain(txn: &DatabaseTransaction, statement: &Statement) -> String {
+    let statement = Statement {
+        sql: format!("EXPLAIN {}", statement.sql),
+        ..statement.clone()
+    };
+    let mut plan = vec![];
+    for row in txn.query_all(statement).await.unwrap() {
+        plan.push(row.try_get::<String>("", "QUERY PLAN").unwrap());
+    }
+    plan.join("\n")
+}
+
+/// Why a plan is not good enough, `None` if it only reads the large tables through indexes
+fn problem(plan: &str) -> Option<String> {
+    if let Some(table) = LARGE_TABLES
+        .iter()
+        .find(|table| plan.contains(&format!("Seq Scan on {}", table)))
+    {
+        return Some(format!("sequential scan on {}", table));
+    }
+    if !plan.contains("Index") {
+        return Some("no index used".to_string());
+    }
+    None
+}
+
+#[tokio::test]
+#[ignore = "needs a scratch database in TEST_DATABASE_URL"]
+async fn queries_use_indexes() {
+    let url = std::env::var("TEST_DATABASE_URL")
+        .expect("TEST_DATABASE_URL must point to a database the check may migrate");
+    assert!(
+        std::env::var("DATABASE_URL").ok() != Some(url.clone()),
+        "TEST_DATABASE_URL must not be the bot's database"
+    );
+    let db = Database::connect(url).await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+
+    let txn = db.begin().await.unwrap();
+    txn.execute_unprepared(&seed_sql()).await.unwrap();
+
+    let mut failed = vec![];
+    for (name, statement) in checked_queries() {
+        let plan = explain(&txn, &statement).await;
+        if let Some(problem) = problem(&plan) {
+            failed.push(format!("{}: {}\n{}", name, problem, plan));
+        }
+    }
+
+    txn.rollback().await.unwrap();
+
+    assert!(
+        failed.is_empty(),
+        "queries not using indexes:\n{}",
+        failed.join("\n\n")
+    );
+}
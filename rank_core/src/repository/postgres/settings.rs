@@ -0,0 +1,123 @@
+use super::PostgresRepository;
+use crate::model::{GuildSettings, Stopword};
+use crate::repository::SettingsRepository;
+use crate::Error;
+use async_trait::async_trait;
+use entity::prelude::{BotManagerRoles, GuildSettings as GuildSettingsEntity, GuildStopwords};
+use entity::{bot_manager_roles, guild_settings, guild_stopwords};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ColumnTrait, EntityTrait, IntoActiveModel, Iterable, QueryFilter, QueryOrder, Set};
+
+#[async_trait]
+impl SettingsRepository for PostgresRepository {
+    async fn guild_settings(&self, guild: u64) -> Result<Option<GuildSettings>, Error> {
+        Ok(GuildSettingsEntity::find_by_id(guild as i64)
+            .one(&self.db)
+            .await?
+            .map(GuildSettings::from))
+    }
+
+    async fn save_guild_settings(&self, settings: GuildSettings) -> Result<(), Error> {
+        let settings = guild_settings::Model::from(settings);
+        GuildSettingsEntity::insert::<guild_settings::ActiveModel>(settings.into_active_model())
+            .on_conflict(
+                OnConflict::column(guild_settings::Column::Guild)
+                    .update_columns(
+                        guild_settings::Column::iter()
+                            .filter(|c| !matches!(c, guild_settings::Column::Guild)),
+                    )
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn retention_policies(&self) -> Result<Vec<GuildSettings>, Error> {
+        Ok(GuildSettingsEntity::find()
+            .filter(guild_settings::Column::RetentionDays.is_not_null())
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(GuildSettings::from)
+            .collect())
+    }
+
+    async fn stopwords(&self, guild: u64) -> Result<Vec<Stopword>, Error> {
+        Ok(GuildStopwords::find()
+            .filter(guild_stopwords::Column::Guild.eq(guild as i64))
+            .order_by_asc(guild_stopwords::Column::Word)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Stopword::from)
+            .collect())
+    }
+
+    async fn set_stopword(&self, guild: u64, word: &str, removed: bool) -> Result<(), Error> {
+        GuildStopwords::insert(guild_stopwords::ActiveModel {
+            guild: Set(guild as i64),
+            word: Set(word.to_string()),
+            removed: Set(removed),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                guild_stopwords::Column::Guild,
+                guild_stopwords::Column::Word,
+            ])
+            .update_column(guild_stopwords::Column::Removed)
+            .to_owned(),
+        )
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_stopword(&self, guild: u64, word: &str) -> Result<bool, Error> {
+        Ok(GuildStopwords::delete_many()
+            .filter(guild_stopwords::Column::Guild.eq(guild as i64))
+            .filter(guild_stopwords::Column::Word.eq(word))
+            .exec(&self.db)
+            .await?
+            .rows_affected
+            > 0)
+    }
+
+    async fn bot_manager_roles(&self, guild: u64) -> Result<Vec<u64>, Error> {
+        Ok(BotManagerRoles::find()
+            .filter(bot_manager_roles::Column::Guild.eq(guild as i64))
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|r| r.role as u64)
+            .collect())
+    }
+
+    async fn add_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error> {
+        BotManagerRoles::insert(bot_manager_roles::ActiveModel {
+            guild: Set(guild as i64),
+            role: Set(role as i64),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                bot_manager_roles::Column::Guild,
+                bot_manager_roles::Column::Role,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error> {
+        BotManagerRoles::delete_many()
+            .filter(bot_manager_roles::Column::Guild.eq(guild as i64))
+            .filter(bot_manager_roles::Column::Role.eq(role as i64))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use super::PostgresRepository;
+use crate::replies::{self, Thread};
+use crate::repository::StatsRepository;
+use crate::rollups::{self, DailyTotal};
+use crate::stats::{self, ChannelTotal, Contributor, StatsScope, Summary};
+use crate::words::{self, WordCount, WordScope};
+use crate::Error;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+#[async_trait]
+impl StatsRepository for PostgresRepository {
+    async fn message_days(&self, scope: &StatsScope) -> Result<Vec<(NaiveDate, i64)>, Error> {
+        stats::message_days(&self.db, scope).await
+    }
+
+    async fn message_hours(
+        &self,
+        scope: &StatsScope,
+        tz: &str,
+    ) -> Result<Vec<(u32, u32, i64)>, Error> {
+        stats::message_hours(&self.db, scope, tz).await
+    }
+
+    async fn top_contributors(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<Contributor>, Error> {
+        stats::top_contributors(&self.db, scope, limit).await
+    }
+
+    async fn top_channels(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<ChannelTotal>, Error> {
+        stats::top_channels(&self.db, scope, limit).await
+    }
+
+    async fn summary(&self, scope: &StatsScope) -> Result<Summary, Error> {
+        stats::summary(&self.db, scope).await
+    }
+
+    async fn new_users(&self, scope: &StatsScope) -> Result<i64, Error> {
+        stats::new_users(&self.db, scope).await
+    }
+
+    async fn daily_totals_since(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        since: NaiveDate,
+    ) -> Result<Vec<DailyTotal>, Error> {
+        rollups::daily_totals_since(&self.db, guild, user, since).await
+    }
+
+    async fn reply_counts(&self, guild: u64) -> Result<HashMap<(i64, i64), i64>, Error> {
+        replies::reply_counts(&self.db, guild).await
+    }
+
+    async fn threads(&self, guild: u64) -> Result<Vec<Thread>, Error> {
+        replies::threads(&self.db, guild).await
+    }
+
+    async fn replies_between(&self, a: i64, b: i64) -> Result<(i64, i64), Error> {
+        replies::replies_between(&self.db, a, b).await
+    }
+
+    async fn word_counts(
+        &self,
+        scope: &WordScope,
+        limit: Option<u64>,
+    ) -> Result<Vec<(String, i64)>, Error> {
+        words::word_counts(&self.db, scope, limit).await
+    }
+
+    async fn bigram_counts(
+        &self,
+        scope: &WordScope,
+        limit: u64,
+    ) -> Result<Vec<(String, i64)>, Error> {
+        words::bigram_counts(&self.db, scope, limit).await
+    }
+
+    async fn word_count(&self, scope: &WordScope, word: &str) -> Result<WordCount, Error> {
+        words::word_count(&self.db, scope, word).await
+    }
+}
@@ -0,0 +1,26 @@
+use crate::model::{GuildSettings, Stopword};
+use crate::Error;
+use async_trait::async_trait;
+
+/// What guilds configured: their settings, stopwords and bot manager roles
+#[async_trait]
+pub trait SettingsRepository: Send + Sync {
+    /// The stored settings of a guild, `None` if it never configured anything
+    async fn guild_settings(&self, guild: u64) -> Result<Option<GuildSettings>, Error>;
+    /// Insert or replace the settings of a guild
+    async fn save_guild_settings(&self, settings: GuildSettings) -> Result<(), Error>;
+    /// The settings of every guild with a retention policy
+    async fn retention_policies(&self) -> Result<Vec<GuildSettings>, Error>;
+
+    /// The words a guild added to or removed from its common words, by word
+    async fn stopwords(&self, guild: u64) -> Result<Vec<Stopword>, Error>;
+    /// Add a word to a guild's common words, or remove it when `removed`
+    async fn set_stopword(&self, guild: u64, word: &str, removed: bool) -> Result<(), Error>;
+    /// Forget an addition or removal, false if there was none
+    async fn delete_stopword(&self, guild: u64, word: &str) -> Result<bool, Error>;
+
+    /// The roles of a guild that may use the bot's admin commands
+    async fn bot_manager_roles(&self, guild: u64) -> Result<Vec<u64>, Error>;
+    async fn add_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error>;
+    async fn remove_bot_manager_role(&self, guild: u64, role: u64) -> Result<(), Error>;
+}
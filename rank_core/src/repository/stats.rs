@@ -0,0 +1,69 @@
+use crate::replies::Thread;
+use crate::rollups::DailyTotal;
+use crate::stats::{ChannelTotal, Contributor, StatsScope, Summary};
+use crate::words::{WordCount, WordScope};
+use crate::Error;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// Counts and rankings over the stored messages, for the stats commands
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    /// Messages per UTC day in scope, days without messages are left out
+    async fn message_days(&self, scope: &StatsScope) -> Result<Vec<(NaiveDate, i64)>, Error>;
+    /// Messages per weekday (Monday is 0) and hour in the timezone `tz`, an IANA name.
+    /// Hours without messages are left out.
+    async fn message_hours(
+        &self,
+        scope: &StatsScope,
+        tz: &str,
+    ) -> Result<Vec<(u32, u32, i64)>, Error>;
+    /// The users with the most message score, best first
+    async fn top_contributors(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<Contributor>, Error>;
+    /// The channels with the most message score, best first
+    async fn top_channels(
+        &self,
+        scope: &StatsScope,
+        limit: u64,
+    ) -> Result<Vec<ChannelTotal>, Error>;
+    /// Number of messages, number of users that sent them and their average score
+    async fn summary(&self, scope: &StatsScope) -> Result<Summary, Error>;
+    /// Users whose first message in scope is after `scope.since`
+    async fn new_users(&self, scope: &StatsScope) -> Result<i64, Error>;
+    /// Score and message count per day of a guild, or of one user in it, from the start of
+    /// `since` on. Days without messages are left out.
+    async fn daily_totals_since(
+        &self,
+        guild: u64,
+        user: Option<i64>,
+        since: NaiveDate,
+    ) -> Result<Vec<DailyTotal>, Error>;
+
+    /// (from, to) -> number of replies from one user to another in a guild
+    async fn reply_counts(&self, guild: u64) -> Result<HashMap<(i64, i64), i64>, Error>;
+    /// The conversations of a guild that got at least one reply
+    async fn threads(&self, guild: u64) -> Result<Vec<Thread>, Error>;
+    /// How often `a` replied to `b` and `b` to `a`
+    async fn replies_between(&self, a: i64, b: i64) -> Result<(i64, i64), Error>;
+
+    /// Uses of every word in scope, most used first, at most `limit` words
+    async fn word_counts(
+        &self,
+        scope: &WordScope,
+        limit: Option<u64>,
+    ) -> Result<Vec<(String, i64)>, Error>;
+    /// Uses of every pair of words in scope that was used more than once, most used first, at
+    /// most `limit` pairs
+    async fn bigram_counts(
+        &self,
+        scope: &WordScope,
+        limit: u64,
+    ) -> Result<Vec<(String, i64)>, Error>;
+    /// How often `word` is used in scope and by whom
+    async fn word_count(&self, scope: &WordScope, word: &str) -> Result<WordCount, Error>;
+}
@@ -0,0 +1,264 @@
+use crate::Error;
+use chrono::NaiveDate;
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, FromQueryResult, Statement, TransactionTrait,
+};
+use std::collections::HashMap;
+
+// Daily totals kept next to the running totals in `aggregates` so windowed numbers are a range
+// scan over days instead of a scan over every message
+
+const ADD_TO_USER_DAILY_SQL: &str = r#"
+INSERT INTO "user_daily" ("user", "date", "guild", "score", "message_count", "chars")
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT ("user", "date") DO UPDATE
+SET "score" = "user_daily"."score" + excluded."score",
+    "message_count" = "user_daily"."message_count" + excluded."message_count",
+    "chars" = "user_daily"."chars" + excluded."chars"
+"#;
+
+const ADD_TO_CHANNEL_DAILY_SQL: &str = r#"
+INSERT INTO "channel_daily" ("channel", "date", "guild", "score", "message_count", "chars")
+VALUES ($1, $2, $3, $4, $5, $6)
+ON CONFLICT ("channel", "date") DO UPDATE
+SET "score" = "channel_daily"."score" + excluded."score",
+    "message_count" = "channel_daily"."message_count" + excluded."message_count",
+    "chars" = "channel_daily"."chars" + excluded."chars"
+"#;
+
+const USER_WINDOW_SQL: &str = r#"
+SELECT "user", sum("score")::float4 AS "score", sum("message_count")::int8 AS "messages"
+FROM "user_daily"
+WHERE "guild" = $1 AND "date" >= $2
+GROUP BY "user"
+"#;
+
+const CHANNEL_DAYS_SQL: &str = r#"
+SELECT "date", sum("message_count")::int8 AS "messages"
+FROM "channel_daily"
+WHERE "guild" = $1 AND ($2::bigint IS NULL OR "channel" = $2) AND "date" >= $3
+GROUP BY "date"
+"#;
+
+const USER_DAYS_SQL: &str = r#"
+SELECT "date", "score", "message_count"::int8 AS "messages"
+FROM "user_daily"
+WHERE "guild" = $1 AND "user" = $2 AND "date" >= $3
+"#;
+
+const GUILD_DAYS_SQL: &str = r#"
+SELECT "date", sum("score")::float4 AS "score", sum("message_count")::int8 AS "messages"
+FROM "channel_daily"
+WHERE "guild" = $1 AND "date" >= $2
+GROUP BY "date"
+"#;
+
+// $1 is the guild to rebuild or null for every guild
+const DELETE_USER_DAILY_SQL: &str =
+    r#"DELETE FROM "user_daily" WHERE $1::bigint IS NULL OR "guild" = $1"#;
+const DELETE_CHANNEL_DAILY_SQL: &str =
+    r#"DELETE FROM "channel_daily" WHERE $1::bigint IS NULL OR "guild" = $1"#;
+
+const REBUILD_USER_DAILY_SQL: &str = r#"
+INSERT INTO "user_daily" ("user", "date", "guild", "score", "message_count", "chars")
+SELECT m."user", m."timestamp"::date, c."guild", sum(m."score"), count(*), sum(m."length")
+FROM "messages" m JOIN "channels" c ON c."snowflake" = m."channel"
+WHERE $1::bigint IS NULL OR c."guild" = $1
+GROUP BY m."user", m."timestamp"::date, c."guild"
+ON CONFLICT ("user", "date") DO UPDATE
+SET "score" = "user_daily"."score" + excluded."score",
+    "message_count" = "user_daily"."message_count" + excluded."message_count",
+    "chars" = "user_daily"."chars" + excluded."chars"
+"#;
+
+const REBUILD_CHANNEL_DAILY_SQL: &str = r#"
+INSERT INTO "channel_daily" ("channel", "date", "guild", "score", "message_count", "chars")
+SELECT m."channel", m."timestamp"::date, c."guild", sum(m."score"), count(*), sum(m."length")
+FROM "messages" m JOIN "channels" c ON c."snowflake" = m."channel"
+WHERE $1::bigint IS NULL OR c."guild" = $1
+GROUP BY m."channel", m."timestamp"::date, c."guild"
+"#;
+
+#[derive(Debug, FromQueryResult)]
+pub struct UserTotal {
+    pub user: i64,
+    pub score: f32,
+    pub messages: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct DayTotal {
+    date: NaiveDate,
+    messages: i64,
+}
+
+/// Score and message count of a day
+#[derive(Debug, FromQueryResult)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub score: f32,
+    pub messages: i64,
+}
+
+/// A change to the totals of a day
+#[derive(Debug, Clone, Copy)]
+pub struct DailyDelta {
+    pub date: NaiveDate,
+    pub score: f32,
+    pub messages: i32,
+    pub chars: i64,
+}
+
+async fn add(
+    db: &impl ConnectionTrait,
+    sql: &str,
+    id: i64,
+    guild: i64,
+    delta: DailyDelta,
+) -> Result<(), Error> {
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        sql,
+        [
+            id.into(),
+            delta.date.into(),
+            guild.into(),
+            delta.score.into(),
+            delta.messages.into(),
+            delta.chars.into(),
+        ],
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Add to a user's totals for a day
+pub async fn add_to_user_daily(
+    db: &impl ConnectionTrait,
+    user: i64,
+    guild: i64,
+    delta: DailyDelta,
+) -> Result<(), Error> {
+    add(db, ADD_TO_USER_DAILY_SQL, user, guild, delta).await
+}
+
+/// Add to a channel's totals for a day
+pub async fn add_to_channel_daily(
+    db: &impl ConnectionTrait,
+    channel: i64,
+    guild: i64,
+    delta: DailyDelta,
+) -> Result<(), Error> {
+    add(db, ADD_TO_CHANNEL_DAILY_SQL, channel, guild, delta).await
+}
+
+pub(crate) fn user_totals_statement(guild: u64, since: NaiveDate) -> Statement {
+    Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        USER_WINDOW_SQL,
+        [(guild as i64).into(), since.into()],
+    )
+}
+
+pub(crate) fn channel_days_statement(
+    guild: u64,
+    channel: Option<i64>,
+    since: NaiveDate,
+) -> Statement {
+    Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        CHANNEL_DAYS_SQL,
+        [(guild as i64).into(), channel.into(), since.into()],
+    )
+}
+
+pub(crate) fn daily_totals_statement(guild: u64, user: Option<i64>, since: NaiveDate) -> Statement {
+    match user {
+        Some(user) => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            USER_DAYS_SQL,
+            [(guild as i64).into(), user.into(), since.into()],
+        ),
+        None => Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            GUILD_DAYS_SQL,
+            [(guild as i64).into(), since.into()],
+        ),
+    }
+}
+
+/// Score and message count per user of a guild from the start of `since` on
+pub async fn user_totals_since(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    since: NaiveDate,
+) -> Result<HashMap<i64, UserTotal>, Error> {
+    Ok(
+        UserTotal::find_by_statement(user_totals_statement(guild, since))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|total| (total.user, total))
+            .collect(),
+    )
+}
+
+/// Messages per day in a guild or one of its channels from the start of `since` on.
+/// Days without messages are left out.
+pub async fn channel_days_since(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    channel: Option<i64>,
+    since: NaiveDate,
+) -> Result<Vec<(NaiveDate, i64)>, Error> {
+    Ok(
+        DayTotal::find_by_statement(channel_days_statement(guild, channel, since))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|day| (day.date, day.messages))
+            .collect(),
+    )
+}
+
+/// Score and message count per day of a guild, or of one user in it, from the start of `since`
+/// on. Days without messages are left out.
+pub async fn daily_totals_since(
+    db: &impl ConnectionTrait,
+    guild: u64,
+    user: Option<i64>,
+    since: NaiveDate,
+) -> Result<Vec<DailyTotal>, Error> {
+    Ok(
+        DailyTotal::find_by_statement(daily_totals_statement(guild, user, since))
+            .all(db)
+            .await?,
+    )
+}
+
+/// Recompute the daily rollups of a guild, or of every guild, from stored messages.
+/// Returns the number of user and channel days written.
+pub async fn rebuild_rollups(
+    db: &DatabaseConnection,
+    guild: Option<u64>,
+) -> Result<(u64, u64), Error> {
+    let txn = db.begin().await?;
+    let statement = |sql: &str| {
+        Statement::from_sql_and_values(DbBackend::Postgres, sql, [guild.map(|g| g as i64).into()])
+    };
+
+    txn.execute(statement(DELETE_USER_DAILY_SQL)).await?;
+    txn.execute(statement(DELETE_CHANNEL_DAILY_SQL)).await?;
+    let users = txn
+        .execute(statement(REBUILD_USER_DAILY_SQL))
+        .await?
+        .rows_affected();
+    let channels = txn
+        .execute(statement(REBUILD_CHANNEL_DAILY_SQL))
+        .await?
+        .rows_affected();
+
+    txn.commit().await?;
+
+    Ok((users, channels))
+}
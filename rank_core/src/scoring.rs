@@ -0,0 +1,407 @@
+use crate::message::{Attachment, Message};
+use crate::model::GuildSettings;
+use crate::tokenizer::tokenize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Function to score a message based on the word count and # of unique words (Non-spammy score)
+fn count_words(message: &str) -> (u32, u32) {
+    let mut words = HashMap::new();
+    let mut num_words = 0;
+    for word in tokenize(message) {
+        *words.entry(word).or_insert(0) += 1;
+        num_words += 1;
+    }
+    (num_words, words.len() as u32)
+}
+
+/// Features of a message that are computed at ingest so they survive the content being pruned
+pub struct MessageFeatures {
+    pub length: i32,
+    pub word_count: i32,
+    pub tokens: HashMap<String, i32>,
+    /// Pairs of consecutive words, separated by a space
+    pub bigrams: HashMap<String, i32>,
+    pub attachment_count: i32,
+    /// Kind of every attachment (image, video, audio or file), comma separated
+    pub attachment_types: Option<String>,
+    pub link_count: i32,
+    pub code_lines: i32,
+    pub emoji_only: bool,
+    pub mention_count: i32,
+}
+
+impl MessageFeatures {
+    fn attachments_of(&self, kind: &str) -> usize {
+        self.attachment_types
+            .as_deref()
+            .map(|types| types.split(',').filter(|t| *t == kind).count())
+            .unwrap_or(0)
+    }
+}
+
+/// Weights of the parts of a message's score, configurable per guild
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringConfig {
+    /// Points for every image attached
+    pub image_points: f32,
+    /// Points for every video attached
+    pub video_points: f32,
+    /// Points for every other attachment
+    pub file_points: f32,
+    /// Points for every link, up to `max_links`
+    pub link_points: f32,
+    pub max_links: i32,
+    /// Points for every line inside code blocks, up to `max_code_lines`.
+    /// Code is left out of the length and word scores.
+    pub code_line_points: f32,
+    pub max_code_lines: i32,
+    /// Points for every user or role mentioned (negative to discourage pings)
+    pub mention_points: f32,
+    /// The text score of a message that is only emoji is multiplied by this
+    pub emoji_only_multiplier: f32,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        ScoringConfig {
+            image_points: 20.0,
+            video_points: 25.0,
+            file_points: 10.0,
+            link_points: 5.0,
+            max_links: 3,
+            code_line_points: 2.0,
+            max_code_lines: 50,
+            mention_points: 0.0,
+            emoji_only_multiplier: 0.2,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// The scoring config of a guild, the defaults if it never set one
+    pub fn from_settings(settings: &GuildSettings) -> ScoringConfig {
+        settings
+            .scoring
+            .clone()
+            .and_then(|scoring| serde_json::from_value(scoring).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Split message content into the text outside of code blocks and the number of lines of code.
+/// Only closed fences make a block, Discord shows an unmatched one and the text after it as is.
+fn split_code_blocks(content: &str) -> (String, i32) {
+    let mut prose = String::new();
+    let mut code_lines = 0;
+    let mut rest = content;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        let end = match after.find("```") {
+            Some(end) => end,
+            None => break,
+        };
+        prose.push_str(&rest[..start]);
+        prose.push(' ');
+        // the first line of a fenced block is the language (or empty)
+        let block = &after[..end];
+        let code = match block.split_once('\n') {
+            Some((_, code)) => code,
+            None => block,
+        };
+        code_lines += code.lines().filter(|l| !l.trim().is_empty()).count() as i32;
+        rest = &after[end + 3..];
+    }
+    prose.push_str(rest);
+    (prose, code_lines)
+}
+
+fn is_emoji_char(c: char) -> bool {
+    matches!(c as u32,
+        0x1F000..=0x1FAFF
+        | 0x2600..=0x27BF
+        | 0x2B00..=0x2BFF
+        | 0x2190..=0x21FF
+        | 0xE0020..=0xE007F
+        | 0xFE0F
+        | 0x200D
+        | 0x20E3
+        | 0x00A9
+        | 0x00AE
+        | 0x2122
+        | 0x3030
+        | 0x303D
+        | 0x3297
+        | 0x3299)
+}
+
+/// Whether every word of the content is a custom or unicode emoji
+fn is_emoji_only(content: &str) -> bool {
+    let mut words = content.split_whitespace().peekable();
+    words.peek().is_some()
+        && words.all(|word| {
+            (word.starts_with("<:") || word.starts_with("<a:")) && word.ends_with('>')
+                || word.chars().all(is_emoji_char)
+        })
+}
+
+fn attachment_kind(attachment: &Attachment) -> &'static str {
+    match attachment
+        .content_type
+        .as_deref()
+        .and_then(|t| t.split('/').next())
+    {
+        Some("image") => "image",
+        Some("video") => "video",
+        Some("audio") => "audio",
+        _ => "file",
+    }
+}
+
+/// Function to extract the stored features (length, word count, token and bigram counts,
+/// attachments, links, code and mentions) of a message
+pub fn extract_features(message: &Message) -> MessageFeatures {
+    let content = &message.content;
+    let words = tokenize(content);
+    let mut tokens = HashMap::new();
+    for word in words.iter() {
+        *tokens.entry(word.clone()).or_insert(0) += 1;
+    }
+    let mut bigrams = HashMap::new();
+    for pair in words.windows(2) {
+        *bigrams.entry(pair.join(" ")).or_insert(0) += 1;
+    }
+
+    let link_count = content
+        .split_whitespace()
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .count() as i32;
+
+    let (_, code_lines) = split_code_blocks(content);
+
+    MessageFeatures {
+        length: content.chars().count() as i32,
+        word_count: words.len() as i32,
+        tokens,
+        bigrams,
+        attachment_count: message.attachments.len() as i32,
+        attachment_types: if message.attachments.is_empty() {
+            None
+        } else {
+            Some(
+                message
+                    .attachments
+                    .iter()
+                    .map(attachment_kind)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+        },
+        link_count,
+        code_lines,
+        emoji_only: is_emoji_only(content),
+        mention_count: message.mention_count as i32,
+    }
+}
+
+/// Score message content and its features, `content` is used for the length and word scores
+pub fn score_features(content: &str, features: &MessageFeatures, config: &ScoringConfig) -> f32 {
+    let (prose, _) = split_code_blocks(content);
+
+    // Score based on the word count and # of unique words (lower repetition = higher score)
+    let (num_words, num_unique_words) = count_words(&prose);
+    let word_score = if num_words == 0 {
+        0.0
+    } else {
+        num_unique_words as f32 / num_words as f32
+    } * 50.;
+
+    // Longer messages score higher, with diminishing returns
+    let length_score = (prose.trim().len() as f32).sqrt() * 50.;
+
+    // Text score is a combination of word score and length score
+    let mut text_score = (word_score * 0.7) + (length_score * 0.3);
+    if features.emoji_only {
+        text_score *= config.emoji_only_multiplier;
+    }
+
+    let attachment_score = features.attachments_of("image") as f32 * config.image_points
+        + features.attachments_of("video") as f32 * config.video_points
+        + (features.attachment_count as usize
+            - features.attachments_of("image")
+            - features.attachments_of("video")) as f32
+            * config.file_points;
+
+    text_score
+        + attachment_score
+        + features.link_count.min(config.max_links) as f32 * config.link_points
+        + features.code_lines.min(config.max_code_lines) as f32 * config.code_line_points
+        + features.mention_count as f32 * config.mention_points
+}
+
+/// Score messages based on how constructive they are
+pub fn score_message(message: &Message, recent_messages: &[String], config: &ScoringConfig) -> f32 {
+    // If there's any repetition in the recent messages, the score is lowered
+    // (attachment only posts have no content to repeat)
+    if !message.content.is_empty()
+        && recent_messages
+            .iter()
+            .any(|recent_message| recent_message == &message.content)
+    {
+        return 0.;
+    }
+
+    score_features(&message.content, &extract_features(message), config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn message(content: &str, attachments: &[&str]) -> Message {
+        Message {
+            id: 1,
+            author: 2,
+            channel: 3,
+            content: content.to_string(),
+            timestamp: NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap(),
+            attachments: attachments
+                .iter()
+                .map(|t| Attachment {
+                    content_type: Some(t.to_string()),
+                })
+                .collect(),
+            mention_count: 0,
+        }
+    }
+
+    fn settings(scoring: Option<serde_json::Value>) -> GuildSettings {
+        GuildSettings {
+            guild: 1,
+            retention_days: None,
+            retention_truncate: None,
+            voice_points_per_minute: 5.0,
+            scoring,
+            stopword_languages: None,
+        }
+    }
+
+    #[test]
+    fn repeating_a_recent_message_scores_nothing() {
+        let config = ScoringConfig::default();
+        let hello = message("hello there, how is everyone", &[]);
+
+        assert!(score_message(&hello, &[], &config) > 0.0);
+        assert_eq!(
+            score_message(
+                &hello,
+                &["hello there, how is everyone".to_string()],
+                &config
+            ),
+            0.0
+        );
+        // an attachment without text never counts as a repeat
+        let image = message("", &["image/png"]);
+        assert_eq!(score_message(&image, &[String::new()], &config), 20.0);
+    }
+
+    #[test]
+    fn varied_words_score_higher_than_repeated_ones() {
+        let config = ScoringConfig::default();
+        let varied = score_message(&message("one two three four", &[]), &[], &config);
+        let repeated = score_message(&message("spam spam spam spam", &[]), &[], &config);
+        assert!(varied > repeated);
+    }
+
+    #[test]
+    fn attachments_and_links_use_the_configured_points() {
+        let config = ScoringConfig {
+            link_points: 1.0,
+            max_links: 2,
+            ..ScoringConfig::default()
+        };
+        let features = extract_features(&message(
+            "",
+            &["image/png", "video/mp4", "application/pdf", "audio/ogg"],
+        ));
+        assert_eq!(
+            features.attachment_types.as_deref(),
+            Some("image,video,file,audio")
+        );
+        assert_eq!(
+            score_features("", &features, &config),
+            20.0 + 25.0 + 10.0 + 10.0
+        );
+
+        let content = "https://a.com https://b.com http://c.com";
+        let links = extract_features(&message(content, &[]));
+        assert_eq!(links.link_count, 3);
+        let without_links = MessageFeatures {
+            link_count: 0,
+            ..extract_features(&message(content, &[]))
+        };
+        // only `max_links` of them count
+        assert_eq!(
+            score_features(content, &links, &config),
+            score_features(content, &without_links, &config) + 2.0
+        );
+    }
+
+    #[test]
+    fn only_closed_fences_are_code() {
+        let (prose, code_lines) =
+            split_code_blocks("try\n```rust\nfn main() {\n\n}\n```\nthen ```this");
+        assert_eq!(code_lines, 2);
+        assert!(prose.contains("try") && prose.contains("then ```this"));
+        assert!(!prose.contains("main"));
+
+        let unmatched = "half a fence ``` and the rest is still prose";
+        assert_eq!(split_code_blocks(unmatched), (unmatched.to_string(), 0));
+        assert_eq!(extract_features(&message(unmatched, &[])).code_lines, 0);
+    }
+
+    #[test]
+    fn emoji_only_messages_are_scaled_down() {
+        let features = extract_features(&message("🎉 <:pog:123> <a:dance:456>", &[]));
+        assert!(features.emoji_only);
+        assert!(!extract_features(&message("🎉 nice", &[])).emoji_only);
+
+        let config = ScoringConfig::default();
+        let plain = MessageFeatures {
+            emoji_only: false,
+            ..extract_features(&message("🎉 <:pog:123> <a:dance:456>", &[]))
+        };
+        let content = "🎉 <:pog:123> <a:dance:456>";
+        assert!(
+            (score_features(content, &features, &config)
+                - score_features(content, &plain, &config) * config.emoji_only_multiplier)
+                .abs()
+                < 1e-3
+        );
+    }
+
+    #[test]
+    fn config_from_settings_falls_back_to_defaults() {
+        assert_eq!(
+            ScoringConfig::from_settings(&settings(None)),
+            ScoringConfig::default()
+        );
+        assert_eq!(
+            ScoringConfig::from_settings(&settings(Some(serde_json::json!({"image_points": 3.0})))),
+            ScoringConfig {
+                image_points: 3.0,
+                ..ScoringConfig::default()
+            }
+        );
+        // settings that don't parse are ignored rather than breaking scoring
+        assert_eq!(
+            ScoringConfig::from_settings(&settings(Some(serde_json::json!("nonsense")))),
+            ScoringConfig::default()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use crate::rollups::channel_days_since;
+use crate::Error;
+use chrono::{NaiveDate, NaiveDateTime};
+use sea_orm::{ConnectionTrait, DbBackend, FromQueryResult, Statement, Value};
+
+/// Messages in a guild, optionally only one channel or user, from a time on.
+/// $1 is the guild, $2 the channel or null, $3 the start and $4 the user or null.
+const SCOPE: &str = r#"
+    "channel" IN (SELECT "snowflake" FROM "channels" WHERE "guild" = $1)
+    AND ($2::bigint IS NULL OR "channel" = $2)
+    AND "timestamp" >= $3
+    AND ($4::bigint IS NULL OR "user" = $4)
+"#;
+
+#[derive(Debug, FromQueryResult)]
+struct DayCount {
+    day: NaiveDate,
+    messages: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct WeekdayHourCount {
+    weekday: i32,
+    hour: i32,
+    messages: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct Contributor {
+    pub user: i64,
+    pub messages: i64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ChannelTotal {
+    pub channel: i64,
+    pub messages: i64,
+    pub score: f64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, FromQueryResult)]
+pub struct Summary {
+    pub messages: i64,
+    pub users: i64,
+    pub average_score: Option<f64>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Count {
+    count: i64,
+}
+
+/// Which messages are counted, a guild or one of its channels since a time, optionally
+/// only those of one user
+#[derive(Debug, Clone, Copy)]
+pub struct StatsScope {
+    pub guild: u64,
+    pub channel: Option<i64>,
+    pub since: NaiveDateTime,
+    pub user: Option<i64>,
+}
+
+impl StatsScope {
+    fn values(&self) -> Vec<Value> {
+        vec![
+            (self.guild as i64).into(),
+            self.channel.into(),
+            self.since.into(),
+            self.user.into(),
+        ]
+    }
+
+    fn statement(&self, sql: &str) -> Statement {
+        Statement::from_sql_and_values(DbBackend::Postgres, sql, self.values())
+    }
+}
+
+/// Messages per UTC day in scope, days without messages are left out.
+/// Read from the channel rollups unless only one user's messages are counted.
+pub async fn message_days(
+    db: &impl ConnectionTrait,
+    scope: &StatsScope,
+) -> Result<Vec<(NaiveDate, i64)>, Error> {
+    match scope.user {
+        None => channel_days_since(db, scope.guild, scope.channel, scope.since.date()).await,
+        Some(_) => Ok(DayCount::find_by_statement(message_days_statement(scope))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|count| (count.day, count.messages))
+            .collect()),
+    }
+}
+
+/// The query behind `message_days` for one user
+pub(crate) fn message_days_statement(scope: &StatsScope) -> Statement {
+    let sql = format!(
+        r#"SELECT "timestamp"::date AS "day", count(*) AS "messages" FROM "messages"
+        WHERE {} GROUP BY "day""#,
+        SCOPE
+    );
+    scope.statement(&sql)
+}
+
+/// The query behind `message_hours`
+pub(crate) fn message_hours_statement(scope: &StatsScope, tz: &str) -> Statement {
+    let sql = format!(
+        r#"SELECT extract(isodow FROM "local")::int - 1 AS "weekday",
+            extract(hour FROM "local")::int AS "hour", count(*) AS "messages"
+        FROM (SELECT "timestamp" AT TIME ZONE 'UTC' AT TIME ZONE $5 AS "local"
+            FROM "messages" WHERE {}) "local_messages"
+        GROUP BY "weekday", "hour""#,
+        SCOPE
+    );
+    let mut values = scope.values();
+    values.push(tz.into());
+    Statement::from_sql_and_values(DbBackend::Postgres, &sql, values)
+}
+
+/// Messages per weekday (Monday is 0) and hour in the timezone `tz`, an IANA name.
+/// Hours without messages are left out.
+pub async fn message_hours(
+    db: &impl ConnectionTrait,
+    scope: &StatsScope,
+    tz: &str,
+) -> Result<Vec<(u32, u32, i64)>, Error> {
+    Ok(
+        WeekdayHourCount::find_by_statement(message_hours_statement(scope, tz))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|count| (count.weekday as u32, count.hour as u32, count.messages))
+            .collect(),
+    )
+}
+
+/// The query behind `top_contributors`
+pub(crate) fn top_contributors_statement(scope: &StatsScope, limit: u64) -> Statement {
+    let sql = format!(
+        r#"SELECT "user", count(*) AS "messages", sum("score")::float8 AS "score"
+        FROM "messages" WHERE {} GROUP BY "user" ORDER BY "score" DESC LIMIT {}"#,
+        SCOPE, limit
+    );
+    scope.statement(&sql)
+}
+
+/// The users with the most message score, best first
+pub async fn top_contributors(
+    db: &impl ConnectionTrait,
+    scope: &StatsScope,
+    limit: u64,
+) -> Result<Vec<Contributor>, Error> {
+    Ok(
+        Contributor::find_by_statement(top_contributors_statement(scope, limit))
+            .all(db)
+            .await?,
+    )
+}
+
+/// The query behind `top_channels`
+pub(crate) fn top_channels_statement(scope: &StatsScope, limit: u64) -> Statement {
+    let sql = format!(
+        r#"SELECT "channel", count(*) AS "messages", sum("score")::float8 AS "score"
+        FROM "messages" WHERE {} GROUP BY "channel" ORDER BY "score" DESC LIMIT {}"#,
+        SCOPE, limit
+    );
+    scope.statement(&sql)
+}
+
+/// The channels with the most message score, best first
+pub async fn top_channels(
+    db: &impl ConnectionTrait,
+    scope: &StatsScope,
+    limit: u64,
+) -> Result<Vec<ChannelTotal>, Error> {
+    Ok(
+        ChannelTotal::find_by_statement(top_channels_statement(scope, limit))
+            .all(db)
+            .await?,
+    )
+}
+
+/// The query behind `summary`
+pub(crate) fn summary_statement(scope: &StatsScope) -> Statement {
+    let sql = format!(
+        r#"SELECT count(*) AS "messages", count(DISTINCT "user") AS "users",
+        avg("score")::float8 AS "average_score"
+        FROM "messages" WHERE {}"#,
+        SCOPE
+    );
+    scope.statement(&sql)
+}
+
+/// Number of messages, number of users that sent them and their average score
+pub async fn summary(db: &impl ConnectionTrait, scope: &StatsScope) -> Result<Summary, Error> {
+    Ok(Summary::find_by_statement(summary_statement(scope))
+        .one(db)
+        .await?
+        .unwrap_or_default())
+}
+
+/// The query behind `new_users`
+pub(crate) fn new_users_statement(scope: &StatsScope) -> Statement {
+    let sql = r#"SELECT count(*) AS "count" FROM (
+            SELECT min("timestamp") AS "first" FROM "messages"
+            WHERE "channel" IN (SELECT "snowflake" FROM "channels" WHERE "guild" = $1)
+            AND ($2::bigint IS NULL OR "channel" = $2)
+            AND ($4::bigint IS NULL OR "user" = $4)
+            GROUP BY "user"
+        ) "firsts" WHERE "first" >= $3"#;
+    scope.statement(sql)
+}
+
+/// Users whose first message in scope is after `scope.since`
+pub async fn new_users(db: &impl ConnectionTrait, scope: &StatsScope) -> Result<i64, Error> {
+    Ok(Count::find_by_statement(new_users_statement(scope))
+        .one(db)
+        .await?
+        .map(|c| c.count)
+        .unwrap_or(0))
+}
@@ -0,0 +1,151 @@
+/// Prefixes of the inside of Discord's `<...>` markup: user, role and channel mentions,
+/// custom emoji, timestamps, slash command mentions and links with embeds suppressed
+const MARKUP_PREFIXES: [&str; 6] = ["@", "#", ":", "a:", "t:", "/"];
+
+/// Replace every span between a pair of `delimiter`s with a space. An unmatched delimiter
+/// doesn't start a span, Discord shows it and the text after it as is.
+fn strip_delimited(content: &str, delimiter: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find(delimiter) {
+        out.push_str(&rest[..start]);
+        out.push(' ');
+        let after = &rest[start + delimiter.len()..];
+        rest = match after.find(delimiter) {
+            Some(end) => &after[end + delimiter.len()..],
+            None => after,
+        };
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Remove fenced code blocks and inline code, code isn't words
+fn strip_code(content: &str) -> String {
+    strip_delimited(&strip_delimited(content, "```"), "`")
+}
+
+/// Replace Discord's `<...>` markup with spaces
+fn strip_markup(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('>') {
+            Some(end)
+                if !after[..end].contains(char::is_whitespace)
+                    && (MARKUP_PREFIXES.iter().any(|p| after.starts_with(p))
+                        || after.starts_with("http")) =>
+            {
+                out.push(' ');
+                rest = &after[end + 1..];
+            }
+            _ => {
+                out.push('<');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_url(word: &str) -> bool {
+    word.contains("://") || word.starts_with("www.")
+}
+
+/// Normalize a single whitespace separated word: lowercase it, drop spoiler bars and the
+/// punctuation and markdown around it. `None` for URLs, markup and words without any letters
+/// or digits.
+pub fn normalize_word(word: &str) -> Option<String> {
+    if is_url(word) {
+        return None;
+    }
+
+    let word = strip_markup(&word.replace("||", ""));
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+
+    if word.is_empty() {
+        return None;
+    }
+
+    Some(word.to_lowercase())
+}
+
+/// Split message content into normalized words.
+/// Code, mentions, emoji, URLs and other Discord markup are left out and spoilers are unwrapped.
+pub fn tokenize(content: &str) -> Vec<String> {
+    strip_markup(&strip_code(content))
+        .split_whitespace()
+        .filter_map(normalize_word)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercases_and_trims_punctuation() {
+        assert_eq!(tokenize("Hello, World!"), ["hello", "world"]);
+        assert_eq!(
+            tokenize("**bold** _it_ ||spoiler||"),
+            ["bold", "it", "spoiler"]
+        );
+        assert_eq!(tokenize("... -- !!"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn drops_markup_and_urls() {
+        assert_eq!(
+            tokenize("hi <@123> in <#456> <:pog:789> <a:dance:1> <t:1700000000:R> </rank:2>"),
+            ["hi", "in"]
+        );
+        assert_eq!(
+            tokenize("see https://example.com and <https://example.org> or www.example.net"),
+            ["see", "and", "or"]
+        );
+        // not markup, the angle brackets are punctuation
+        assert_eq!(tokenize("a <b> c < d"), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn drops_code() {
+        assert_eq!(tokenize("run `cargo build` now"), ["run", "now"]);
+        assert_eq!(
+            tokenize("before\n```rust\nfn main() {}\n```\nafter"),
+            ["before", "after"]
+        );
+        assert_eq!(
+            tokenize("```a``` one ```b``` two `c` three"),
+            ["one", "two", "three"]
+        );
+    }
+
+    #[test]
+    fn keeps_text_after_unmatched_fences() {
+        assert_eq!(
+            tokenize("it's a ``` and more words"),
+            ["it's", "a", "and", "more", "words"]
+        );
+        assert_eq!(
+            tokenize("a stray ` backtick here"),
+            ["a", "stray", "backtick", "here"]
+        );
+        assert_eq!(
+            tokenize("```x``` open ``` rest `y` end"),
+            ["open", "rest", "end"]
+        );
+    }
+
+    #[test]
+    fn keeps_unicode() {
+        assert_eq!(
+            tokenize("Ça va? Größe 東京 ÉTÉ"),
+            ["ça", "va", "größe", "東京", "été"]
+        );
+        assert_eq!(tokenize("😀 🎉🎉"), Vec::<String>::new());
+        assert_eq!(normalize_word("«Привет»"), Some("привет".to_string()));
+    }
+}
@@ -0,0 +1,146 @@
+use crate::Error;
+use chrono::NaiveDateTime;
+use entity::prelude::{Channels, MessageBigrams, MessageTokens, Messages};
+use entity::{message_bigrams, message_tokens, messages};
+use sea_orm::sea_query::{Alias, Expr, Query};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Related,
+    Select,
+};
+
+/// Which messages word stats are computed over
+#[derive(Debug, Clone, Copy)]
+pub struct WordScope {
+    pub guild: u64,
+    pub user: Option<i64>,
+    pub channel: Option<i64>,
+    pub since: Option<NaiveDateTime>,
+}
+
+impl WordScope {
+    pub fn guild(guild: u64) -> WordScope {
+        WordScope {
+            guild,
+            user: None,
+            channel: None,
+            since: None,
+        }
+    }
+
+    /// Join a query over a per message table to its messages and keep those in scope
+    fn apply<E: EntityTrait + Related<Messages>>(&self, select: Select<E>) -> Select<E> {
+        let guild_channels = Query::select()
+            .column(entity::channels::Column::Snowflake)
+            .from(Channels)
+            .and_where(entity::channels::Column::Guild.eq(self.guild as i64))
+            .to_owned();
+
+        let mut select = select
+            .inner_join(Messages)
+            .filter(messages::Column::Channel.in_subquery(guild_channels));
+
+        if let Some(user) = self.user {
+            select = select.filter(messages::Column::User.eq(user));
+        }
+        if let Some(channel) = self.channel {
+            select = select.filter(messages::Column::Channel.eq(channel));
+        }
+        if let Some(since) = self.since {
+            select = select.filter(messages::Column::Timestamp.gt(since));
+        }
+        select
+    }
+}
+
+/// How often a word is used in scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordCount {
+    pub uses: i64,
+    pub messages: i64,
+    pub first_used: Option<NaiveDateTime>,
+    /// Users that used the word most, with their uses
+    pub top_users: Vec<(i64, i64)>,
+}
+
+/// Uses of every word in scope, most used first, at most `limit` words
+pub async fn word_counts(
+    db: &impl ConnectionTrait,
+    scope: &WordScope,
+    limit: Option<u64>,
+) -> Result<Vec<(String, i64)>, Error> {
+    Ok(scope
+        .apply(MessageTokens::find())
+        .select_only()
+        .column(message_tokens::Column::Token)
+        .column_as(message_tokens::Column::Count.sum(), "uses")
+        .group_by(message_tokens::Column::Token)
+        .order_by_desc(Expr::col(Alias::new("uses")))
+        .limit(limit)
+        .into_tuple::<(String, i64)>()
+        .all(db)
+        .await?)
+}
+
+/// Uses of every pair of words in scope that was used more than once, most used first, at
+/// most `limit` pairs
+pub async fn bigram_counts(
+    db: &impl ConnectionTrait,
+    scope: &WordScope,
+    limit: u64,
+) -> Result<Vec<(String, i64)>, Error> {
+    Ok(scope
+        .apply(MessageBigrams::find())
+        .select_only()
+        .column(message_bigrams::Column::Bigram)
+        .column_as(message_bigrams::Column::Count.sum(), "uses")
+        .group_by(message_bigrams::Column::Bigram)
+        // a phrase said once isn't a phrase
+        .having(Expr::expr(message_bigrams::Column::Count.sum()).gt(1))
+        .order_by_desc(Expr::col(Alias::new("uses")))
+        .limit(limit)
+        .into_tuple::<(String, i64)>()
+        .all(db)
+        .await?)
+}
+
+/// How often `word` is used in scope and by whom
+pub async fn word_count(
+    db: &impl ConnectionTrait,
+    scope: &WordScope,
+    word: &str,
+) -> Result<WordCount, Error> {
+    let (uses, messages, first_used) = scope
+        .apply(MessageTokens::find())
+        .select_only()
+        .column_as(
+            Expr::expr(message_tokens::Column::Count.sum()).if_null(0),
+            "uses",
+        )
+        .column_as(message_tokens::Column::Message.count(), "messages")
+        .column_as(messages::Column::Timestamp.min(), "first_used")
+        .filter(message_tokens::Column::Token.eq(word))
+        .into_tuple::<(i64, i64, Option<NaiveDateTime>)>()
+        .one(db)
+        .await?
+        .unwrap_or((0, 0, None));
+
+    let top_users = scope
+        .apply(MessageTokens::find())
+        .select_only()
+        .column(messages::Column::User)
+        .column_as(message_tokens::Column::Count.sum(), "uses")
+        .filter(message_tokens::Column::Token.eq(word))
+        .group_by(messages::Column::User)
+        .order_by_desc(Expr::col(Alias::new("uses")))
+        .limit(5)
+        .into_tuple::<(i64, i64)>()
+        .all(db)
+        .await?;
+
+    Ok(WordCount {
+        uses,
+        messages,
+        first_used,
+        top_users,
+    })
+}
@@ -0,0 +1,23 @@
+use rank_core::message::{Attachment, Message as CoreMessage};
+use serenity::model::prelude::Message;
+
+// Conversions from serenity's types to the plain ones `rank_core` works on
+
+/// The parts of a Discord message the engine scores and stores
+pub fn core_message(message: &Message) -> CoreMessage {
+    CoreMessage {
+        id: message.id.get(),
+        author: message.author.id.get(),
+        channel: message.channel_id.get(),
+        content: message.content.clone(),
+        timestamp: message.timestamp.naive_utc(),
+        attachments: message
+            .attachments
+            .iter()
+            .map(|attachment| Attachment {
+                content_type: attachment.content_type.clone(),
+            })
+            .collect(),
+        mention_count: message.mentions.len() + message.mention_roles.len(),
+    }
+}
@@ -0,0 +1,297 @@
+use crate::adapters::core_message;
+use crate::guild_settings;
+use crate::handlers::message::handle_message;
+use crate::handlers::reaction::backfill_reactions;
+use crate::stats_cache::invalidate_guild;
+use crate::{Data, Error};
+use async_iterator::Iterator;
+use indicatif::ProgressIterator;
+use log::{debug, warn};
+use rank_core::scoring::{score_message, ScoringConfig};
+use serenity::all::{GuildId, Http};
+use serenity::builder::GetMessages;
+use serenity::cache::Cache;
+use serenity::model::channel::GuildChannel;
+use serenity::model::id::MessageId;
+use serenity::model::prelude::Message;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+struct HistoryIterator<'a> {
+    channel: &'a GuildChannel,
+    http: Arc<Http>,
+    limit: u32,
+    current: u32,
+    before: Option<u64>,
+    after: Option<u64>,
+    around: Option<u64>,
+    messages: Vec<Message>,
+}
+
+async fn fill_messages(
+    channel: &GuildChannel,
+    http: Arc<Http>,
+    last_message_id: Option<MessageId>,
+    limit: u8,
+    before: Option<u64>,
+    after: Option<u64>,
+    around: Option<u64>,
+) -> Result<Vec<Message>, Error> {
+    let messages_gotten = {
+        let mut config = GetMessages::default();
+        if let Some(last_message_id) = last_message_id {
+            config = config.before(last_message_id);
+        } else if let Some(before) = before {
+            config = config.before(before);
+        }
+
+        debug!(
+            "selecting before {:?} on {} ({})",
+            last_message_id, channel.id, channel.name
+        );
+        if let Some(after) = after {
+            config = config.after(after);
+        }
+
+        if let Some(around) = around {
+            config = config.around(around);
+        }
+
+        config = config.limit(limit);
+        channel.messages(&http, config).await?
+    };
+
+    debug!(
+        "got {} messages before {:?} on {} ({})",
+        messages_gotten.len(),
+        last_message_id,
+        channel.id,
+        channel.name,
+    );
+    Ok(messages_gotten)
+}
+
+impl HistoryIterator<'_> {
+    async fn new<'a>(
+        channel: &'a GuildChannel,
+        http: Arc<Http>,
+        limit: u32,
+        before: Option<u64>,
+        after: Option<u64>,
+        around: Option<u64>,
+    ) -> HistoryIterator<'a> {
+        let messages_gotten = match fill_messages(
+            channel,
+            http.clone(),
+            None,
+            limit.min(100) as u8,
+            before,
+            after,
+            around,
+        )
+        .await
+        {
+            Ok(messages_gotten) => messages_gotten,
+            Err(e) => {
+                warn!("failed to get messages: {:?}", e);
+                Vec::new()
+            }
+        };
+        HistoryIterator {
+            channel,
+            http,
+            limit,
+            current: 0,
+            before,
+            after,
+            around,
+            messages: messages_gotten,
+        }
+    }
+}
+
+impl async_iterator::Iterator for HistoryIterator<'_> {
+    type Item = Message;
+
+    async fn next(&mut self) -> Option<Message> {
+        if self.limit < self.current {
+            return None;
+        }
+
+        if self.messages.is_empty() {
+            return None;
+        }
+
+        let message = self.messages.remove(0);
+        self.current += 1;
+
+        if self.messages.is_empty() && self.limit - self.current != 0 {
+            debug!("{}", self.limit - self.current);
+            let messages_gotten = fill_messages(
+                self.channel,
+                self.http.clone(),
+                Some(message.id),
+                (self.limit - self.current).min(100) as u8,
+                self.before,
+                self.after,
+                self.around,
+            )
+            .await
+            .unwrap();
+            self.messages = messages_gotten;
+        }
+
+        Some(message)
+    }
+}
+
+pub struct BackfillSummary {
+    pub messages: i32,
+    pub elapsed: Duration,
+}
+
+/// Load the whole message history of a guild into the database, scoring every message the
+/// same way as if it was just sent. With `reset` the guild and everything stored for it is
+/// deleted first, otherwise messages already stored are skipped. With `reactions` who reacted
+/// to every message is loaded too, which takes a request per emoji and message.
+pub async fn backfill_guild(
+    data: &Data,
+    http: &Arc<Http>,
+    cache: &Arc<Cache>,
+    guild: GuildId,
+    reset: bool,
+    reactions: bool,
+) -> Result<BackfillSummary, Error> {
+    if reset && data.repo.delete_guild(guild.get()).await? {
+        warn!("deleted guild {:?}", guild.get());
+        // the cascade took the guild's channels and users with it
+        data.guild_in_db.write().await.remove(&guild.get());
+        data.channel_in_db.write().await.clear();
+        data.user_in_db.write().await.clear();
+    }
+
+    let timer = Instant::now();
+
+    let channels = guild.channels(http).await?;
+    let mut messages: Vec<Message> =
+        futures::future::join_all(channels.values().map(|channel| async move {
+            HistoryIterator::new(channel, http.clone(), u32::MAX, None, None, None)
+                .await
+                .collect::<Vec<_>>()
+                .await
+        }))
+        .await
+        .into_iter()
+        .progress()
+        .flatten()
+        .filter(|message| !message.author.bot)
+        .collect();
+
+    let mut message_log_file = std::fs::File::create("messages_recall.txt")?;
+
+    messages.sort_unstable_by_key(|message| message.id);
+
+    let mut guild_score = 0.;
+    let mut guild_message_count = 0;
+    let mut channel_totals = HashMap::new();
+    let mut user_totals = HashMap::new();
+
+    let mut message_log = String::new();
+
+    let config = ScoringConfig::from_settings(&guild_settings::get(data, guild.get()).await?);
+
+    for message in messages.into_iter().progress() {
+        message_log.push_str(
+            format!(
+                "{} [#{}] [{}] {}\n",
+                message.timestamp.format("[%d-%m-%Y][%H:%M:%S]"),
+                channels
+                    .get(&message.channel_id)
+                    .map(|c| c.name.as_str())
+                    .unwrap_or_default(),
+                message.author.name,
+                message.content
+            )
+            .as_str(),
+        );
+
+        let score;
+        {
+            let mut last_five = data.last_five_map.write().await;
+
+            let last_five = last_five.entry(message.author.id).or_insert(vec![]);
+
+            score = score_message(&core_message(&message), last_five, &config);
+
+            last_five.push(message.content.clone());
+
+            if last_five.len() == 6 {
+                last_five.remove(0);
+            }
+            debug_assert!(last_five.len() < 6);
+        }
+
+        match handle_message(
+            score,
+            http,
+            data,
+            &message,
+            Some(guild),
+            cache,
+            false,
+            &data.guild_in_db,
+            &data.channel_in_db,
+            &data.user_in_db,
+        )
+        .await
+        {
+            // already stored, its score is in the totals
+            Ok(false) => {}
+            Ok(true) => {
+                if reactions {
+                    if let Err(e) = backfill_reactions(data, http, &message).await {
+                        warn!("failed to load reactions of {}: {:?}", message.id, e);
+                    }
+                }
+
+                guild_score += score;
+                guild_message_count += 1;
+
+                let channel = channel_totals
+                    .entry(message.channel_id.get() as i64)
+                    .or_insert((0.0, 0));
+                channel.0 += score;
+                channel.1 += 1;
+
+                let user = user_totals
+                    .entry(message.author.id.get() as i64)
+                    .or_insert((0.0, 0));
+                user.0 += score;
+                user.1 += 1;
+            }
+            Err(e) => {
+                warn!("failed to handle message: {:?} (as long as you dont see a billion of these messages you are probably fine)", e);
+            }
+        }
+    }
+
+    message_log_file.write_all(message_log.as_bytes())?;
+
+    data.repo
+        .add_to_guild(guild.get() as i64, guild_score, guild_message_count)
+        .await?;
+    for (channel, (score, messages)) in channel_totals {
+        data.repo.add_to_channel(channel, score, messages).await?;
+    }
+    for (user, (score, messages)) in user_totals {
+        data.repo.add_to_user(user, score, messages).await?;
+    }
+    invalidate_guild(data, Some(guild.get())).await;
+
+    Ok(BackfillSummary {
+        messages: guild_message_count,
+        elapsed: timer.elapsed(),
+    })
+}
@@ -0,0 +1,63 @@
+/// Characters for the levels of a sparkline, lowest to highest
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Characters for the levels of a heatmap cell, empty to hottest
+const HEAT_LEVELS: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Longest sparkline that fits an embed field, longer series are merged into fewer points
+pub const MAX_SPARKLINE_LEN: usize = 60;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Merge neighbouring values so there are at most `max_len` of them, summing each group
+pub fn resample(values: &[f32], max_len: usize) -> Vec<f32> {
+    if values.len() <= max_len || max_len == 0 {
+        return values.to_vec();
+    }
+    let group = values.len().div_ceil(max_len);
+    values.chunks(group).map(|c| c.iter().sum()).collect()
+}
+
+/// Render values as a one line chart of block characters
+pub fn sparkline(values: &[f32]) -> String {
+    let max = values.iter().cloned().fold(0.0, f32::max);
+    values
+        .iter()
+        .map(|v| {
+            if max <= 0.0 {
+                SPARK_LEVELS[0]
+            } else {
+                let level = (v / max * (SPARK_LEVELS.len() - 1) as f32).round() as usize;
+                SPARK_LEVELS[level.min(SPARK_LEVELS.len() - 1)]
+            }
+        })
+        .collect()
+}
+
+/// Render a weekday × hour-of-day grid (Monday first) as a text heatmap.
+/// The output is meant to go in a code block so the columns line up.
+pub fn heatmap(grid: &[[f32; 24]; 7]) -> String {
+    let max = grid
+        .iter()
+        .flat_map(|row| row.iter())
+        .cloned()
+        .fold(0.0, f32::max);
+
+    let mut out = String::from("    0     6     12    18    \n");
+    for (day, row) in grid.iter().enumerate() {
+        out.push_str(WEEKDAYS[day]);
+        out.push(' ');
+        for value in row.iter() {
+            let level = if max <= 0.0 || *value <= 0.0 {
+                0
+            } else {
+                // anything non zero gets at least the lightest shade
+                ((value / max * (HEAT_LEVELS.len() - 1) as f32).ceil() as usize)
+                    .clamp(1, HEAT_LEVELS.len() - 1)
+            };
+            out.push(HEAT_LEVELS[level]);
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,335 @@
+use crate::backfill::backfill_guild;
+use crate::export::{export_guild, ExportFormat, ExportOptions};
+use crate::import::{import_records, parse_records, ImportFormat};
+use crate::leaderboard::{leaderboard_page, Ranking, PAGE_SIZE};
+use crate::maintenance::{prune_user, rescore_guild};
+use crate::stats_cache::{invalidate_guild, Window};
+use crate::{Data, Error};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+use log::info;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::DatabaseConnection;
+use serenity::all::{GuildId, Http};
+use serenity::cache::Cache;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Discord bot that ranks users by how constructive their messages are"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start the bot, the default without a subcommand
+    Run,
+    /// Apply, revert or list migrations
+    Migrate {
+        #[command(subcommand)]
+        action: MigrateAction,
+    },
+    /// Drop every table and apply all migrations again, this deletes all data
+    Fresh {
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Export the users, channels and messages of a guild to disk
+    Export {
+        /// Id of the guild to export
+        #[arg(long)]
+        guild: u64,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// First day of messages to export (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day of messages to export (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<NaiveDate>,
+        /// Include message content
+        #[arg(long)]
+        include_content: bool,
+        /// Directory the files are written to
+        #[arg(long, default_value = "export")]
+        out: PathBuf,
+    },
+    /// Seed user levels from another levelling bot's JSON or CSV export
+    Import {
+        /// Id of the guild the users belong to
+        #[arg(long)]
+        guild: u64,
+        /// Export file from the other bot
+        file: PathBuf,
+        /// File format (default: guessed from the file name)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+    },
+    /// Undo an import
+    RevertImport {
+        #[arg(long)]
+        guild: u64,
+        /// Batch printed when the import finished
+        batch: String,
+    },
+    /// Recompute the daily user and channel rollups from stored messages
+    RebuildRollups {
+        /// Id of the guild to rebuild (default: every guild)
+        #[arg(long)]
+        guild: Option<u64>,
+    },
+    /// Load the message history of a guild from Discord, like /load_messages
+    Backfill {
+        #[arg(long)]
+        guild: u64,
+        /// Delete everything stored for the guild first
+        #[arg(long)]
+        reset: bool,
+        /// Also load who reacted to every message, slow on large guilds
+        #[arg(long)]
+        reactions: bool,
+    },
+    /// Score the stored messages of a guild again with its current scoring settings
+    Rescore {
+        #[arg(long)]
+        guild: u64,
+    },
+    /// Recompute user, channel and guild totals from the stored messages
+    Reconcile {
+        /// Id of the guild to reconcile (default: every guild)
+        #[arg(long)]
+        guild: Option<u64>,
+    },
+    /// Delete a user and all their messages, reactions and score from a guild
+    Prune {
+        #[arg(long)]
+        guild: u64,
+        #[arg(long)]
+        user: u64,
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Print a page of a guild's leaderboard
+    Leaderboard {
+        #[arg(long)]
+        guild: u64,
+        #[arg(long, default_value_t = 1)]
+        page: u64,
+        #[arg(long, value_enum, default_value_t = Ranking::Score)]
+        ranking: Ranking,
+        /// Only count score from recent days (default: all time)
+        #[arg(long, value_enum)]
+        window: Option<Window>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateAction {
+    /// Apply pending migrations
+    Up {
+        /// Number of migrations to apply (default: all)
+        #[arg(long)]
+        steps: Option<u32>,
+    },
+    /// Revert applied migrations, newest first
+    Down {
+        /// Number of migrations to revert
+        #[arg(long, default_value_t = 1)]
+        steps: u32,
+    },
+    /// List every migration and whether it is applied
+    Status,
+}
+
+impl Command {
+    /// Commands that change the schema themselves run before migrations are applied or the
+    /// schema is checked
+    pub fn manages_schema(&self) -> bool {
+        matches!(self, Command::Migrate { .. } | Command::Fresh { .. })
+    }
+
+    /// Commands that talk to Discord and need the bot's token
+    pub fn needs_token(&self) -> bool {
+        matches!(self, Command::Run | Command::Backfill { .. })
+    }
+}
+
+/// Ask on the terminal, true if `answer` was typed
+fn confirm(question: &str, answer: &str) -> Result<bool, Error> {
+    print!("{} Type \"{}\" to continue: ", question, answer);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim() == answer)
+}
+
+/// Run a subcommand that changes the schema, before the schema is checked or any state is
+/// loaded from it
+pub async fn manage_schema(command: Command, db: &DatabaseConnection) -> Result<(), Error> {
+    match command {
+        Command::Migrate { action } => match action {
+            MigrateAction::Up { steps } => {
+                Migrator::up(db, steps).await?;
+                info!("migrations applied");
+            }
+            MigrateAction::Down { steps } => {
+                Migrator::down(db, Some(steps)).await?;
+                info!("reverted {} migrations", steps);
+            }
+            MigrateAction::Status => {
+                for migration in Migrator::get_migration_with_status(db).await? {
+                    info!("{:<8} {}", migration.status(), migration.name());
+                }
+            }
+        },
+        Command::Fresh { yes } => {
+            if !yes
+                && !confirm(
+                    "This drops every table and deletes all ranks, messages and settings.",
+                    "fresh",
+                )?
+            {
+                info!("cancelled, nothing was changed");
+                return Ok(());
+            }
+            Migrator::fresh(db).await?;
+            info!("dropped every table and applied all migrations");
+        }
+        _ => unreachable!("only called for commands that manage the schema"),
+    }
+
+    Ok(())
+}
+
+/// Run a CLI subcommand against the database, with the same state the bot would start with
+pub async fn run(command: Command, data: &Data, token: Option<&str>) -> Result<(), Error> {
+    let repo = data.repo.as_ref();
+    match command {
+        // started by main once the schema is ready, or run by `manage_schema`
+        Command::Run | Command::Migrate { .. } | Command::Fresh { .. } => {}
+        Command::Export {
+            guild,
+            format,
+            from,
+            to,
+            include_content,
+            out,
+        } => {
+            let options = ExportOptions {
+                guild,
+                format,
+                from,
+                to,
+                include_content,
+            };
+            let summary = export_guild(repo, &options, &out).await?;
+            info!(
+                "exported {} users, {} channels and {} messages to {}",
+                summary.users,
+                summary.channels,
+                summary.messages,
+                out.display()
+            );
+        }
+        Command::Import {
+            guild,
+            file,
+            format,
+        } => {
+            let format = format.unwrap_or_else(|| {
+                ImportFormat::from_file_name(
+                    &file.file_name().unwrap_or_default().to_string_lossy(),
+                )
+            });
+            let records = parse_records(format, &std::fs::read(&file)?)?;
+            let summary = import_records(repo, guild, None, &records).await?;
+            invalidate_guild(data, Some(guild)).await;
+            info!(
+                "imported {} users ({} new) adding {:.0} score, revert with batch {}",
+                summary.users, summary.created_users, summary.score, summary.batch
+            );
+        }
+        Command::RevertImport { guild, batch } => {
+            let users = repo.revert_import(guild, &batch).await?;
+            invalidate_guild(data, Some(guild)).await;
+            info!("reverted import {} for {} users", batch, users);
+        }
+        Command::RebuildRollups { guild } => {
+            let (users, channels) = repo.rebuild_rollups(guild).await?;
+            invalidate_guild(data, guild).await;
+            info!(
+                "rebuilt rollups with {} user days and {} channel days",
+                users, channels
+            );
+        }
+        Command::Backfill {
+            guild,
+            reset,
+            reactions,
+        } => {
+            let http = Arc::new(Http::new(token.ok_or("backfilling needs the TOKEN")?));
+            let cache = Arc::new(Cache::new());
+            let summary =
+                backfill_guild(data, &http, &cache, GuildId::new(guild), reset, reactions).await?;
+            info!("got {} messages in {:?}", summary.messages, summary.elapsed);
+        }
+        Command::Rescore { guild } => {
+            let summary = rescore_guild(data, guild).await?;
+            info!(
+                "rescored {} messages, {} changed and {} skipped without their full content",
+                summary.messages, summary.changed, summary.skipped
+            );
+        }
+        Command::Reconcile { guild } => {
+            let summary = repo.reconcile_aggregates(guild).await?;
+            invalidate_guild(data, guild).await;
+            info!(
+                "fixed the totals of {} users, {} channels and {} guilds",
+                summary.users, summary.channels, summary.guilds
+            );
+        }
+        Command::Prune { guild, user, yes } => {
+            if !yes
+                && !confirm(
+                    &format!(
+                        "This deletes user {} and all their messages and reactions from guild {}.",
+                        user, guild
+                    ),
+                    "prune",
+                )?
+            {
+                info!("cancelled, nothing was changed");
+                return Ok(());
+            }
+            let summary = prune_user(repo, guild, user).await?;
+            invalidate_guild(data, Some(guild)).await;
+            info!(
+                "deleted {} messages and {} reactions, restart a running bot so it forgets the user",
+                summary.messages, summary.reactions
+            );
+        }
+        Command::Leaderboard {
+            guild,
+            page,
+            ranking,
+            window,
+        } => {
+            let first = (page.max(1) - 1) * PAGE_SIZE + 1;
+            let page = leaderboard_page(repo, guild, ranking, window, page).await?;
+            println!("{}", page.title);
+            for (position, (name, value)) in page.rows.iter().enumerate() {
+                println!("{:>3}. {:<32} {}", first + position as u64, name, value);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use crate::charts::{heatmap, resample, sparkline, MAX_SPARKLINE_LEN};
+use crate::server_stats::weekday_hour_messages;
+use crate::{Context, Error};
+use chrono_tz::Tz;
+use poise::CreateReply;
+use rank_core::stats::StatsScope;
+use serenity::builder::CreateEmbed;
+use serenity::model::prelude::User;
+
+const WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+/// Show messages and score per day and when people are active
+#[poise::command(slash_command, guild_only)]
+pub async fn activity(
+    ctx: Context<'_>,
+    #[description = "User (default: the whole server)"] user: Option<User>,
+    #[description = "Number of days to show (default 30, max 365)"] days: Option<u32>,
+    #[description = "Timezone for hours, e.g. Europe/London (default UTC)"] timezone: Option<
+        String,
+    >,
+) -> Result<(), Error> {
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+    let days = days.unwrap_or(30).clamp(1, 365);
+
+    let tz = match timezone
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or("UTC")
+        .parse::<Tz>()
+    {
+        Ok(tz) => tz,
+        Err(_) => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Invalid timezone")
+                        .description(format!(
+                            "`{}` is not a timezone, use a name like `Europe/London` or `America/New_York`",
+                            timezone.unwrap_or_default()
+                        ))
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.defer().await?;
+
+    // the daily rollups are kept in UTC days, only the hours are shifted into the timezone
+    let today = chrono::Utc::now().date_naive();
+    let first_day = today - chrono::Duration::try_days(days as i64 - 1).unwrap();
+    let user_id = user.as_ref().map(|user| user.id.get() as i64);
+
+    let mut messages_per_day = vec![0.0; days as usize];
+    let mut score_per_day = vec![0.0; days as usize];
+
+    for total in repo
+        .daily_totals_since(guild_id.get(), user_id, first_day)
+        .await?
+    {
+        let day = (total.date - first_day).num_days();
+        if day < 0 || day >= days as i64 {
+            continue;
+        }
+        messages_per_day[day as usize] += total.messages as f32;
+        score_per_day[day as usize] += total.score;
+    }
+
+    let scope = StatsScope {
+        guild: guild_id.get(),
+        channel: None,
+        since: first_day.and_hms_opt(0, 0, 0).unwrap(),
+        user: user_id,
+    };
+    let hours = weekday_hour_messages(repo, &scope, tz.name()).await?;
+
+    let total_messages = messages_per_day.iter().sum::<f32>();
+    let total_score = score_per_day.iter().sum::<f32>();
+
+    let title = match user {
+        Some(ref user) => format!("Activity of {} - last {} days", user.name, days),
+        None => format!("Server activity - last {} days", days),
+    };
+
+    if total_messages == 0.0 {
+        ctx.send(
+            CreateReply::default().embed(
+                CreateEmbed::default()
+                    .title(title)
+                    .description("No messages in this time")
+                    .colour(0xff0000),
+            ),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    let busiest_hour = (0..24)
+        .max_by(|a, b| {
+            let a = hours.iter().map(|d| d[*a]).sum::<f32>();
+            let b = hours.iter().map(|d| d[*b]).sum::<f32>();
+            a.partial_cmp(&b).unwrap()
+        })
+        .unwrap();
+    let busiest_weekday = (0..7)
+        .max_by(|a, b| {
+            hours[*a]
+                .iter()
+                .sum::<f32>()
+                .partial_cmp(&hours[*b].iter().sum::<f32>())
+                .unwrap()
+        })
+        .unwrap();
+
+    let per = if days as usize > MAX_SPARKLINE_LEN {
+        format!("per {} days", (days as usize).div_ceil(MAX_SPARKLINE_LEN))
+    } else {
+        "per day".to_string()
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(title)
+                .description(format!(
+                    "{} to {} (days in UTC, hours in {})",
+                    first_day,
+                    today,
+                    tz.name()
+                ))
+                .field(
+                    format!("Messages {}", per),
+                    format!(
+                        "`{}`\n{} total, peak {}",
+                        sparkline(&resample(&messages_per_day, MAX_SPARKLINE_LEN)),
+                        total_messages,
+                        messages_per_day.iter().cloned().fold(0.0, f32::max)
+                    ),
+                    false,
+                )
+                .field(
+                    format!("Score {}", per),
+                    format!(
+                        "`{}`\n{:.2} total, peak {:.2}",
+                        sparkline(&resample(&score_per_day, MAX_SPARKLINE_LEN)),
+                        total_score,
+                        score_per_day.iter().cloned().fold(0.0, f32::max)
+                    ),
+                    false,
+                )
+                .field("Busiest hour", format!("{:02}:00", busiest_hour), true)
+                .field("Busiest day", WEEKDAYS[busiest_weekday], true)
+                .field(
+                    "Messages by hour and weekday",
+                    format!("```\n{}```", heatmap(&hours)),
+                    false,
+                )
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
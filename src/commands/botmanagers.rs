@@ -0,0 +1,76 @@
+use crate::permissions::bot_manager_roles;
+use crate::{Context, Error};
+use poise::CreateReply;
+use serenity::all::Role;
+use serenity::builder::CreateEmbed;
+
+/// Manage the roles that may use the bot's admin commands
+#[poise::command(
+    slash_command,
+    guild_only,
+    required_permissions = "ADMINISTRATOR",
+    subcommands("add", "remove", "list")
+)]
+pub async fn botmanagers(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply_with_roles(ctx: Context<'_>, title: &str) -> Result<(), Error> {
+    let roles = bot_manager_roles(ctx.data().repo.as_ref(), ctx.guild_id().unwrap().get()).await?;
+
+    let description = if roles.is_empty() {
+        "Only members with Administrator or Manage Server can use admin commands".to_string()
+    } else {
+        roles
+            .iter()
+            .map(|role| format!("<@&{}>", role))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(title)
+                .description(description)
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Allow a role to use the bot's admin commands
+#[poise::command(slash_command, guild_only)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Role to make bot managers"] role: Role,
+) -> Result<(), Error> {
+    ctx.data()
+        .repo
+        .add_bot_manager_role(ctx.guild_id().unwrap().get(), role.id.get())
+        .await?;
+
+    reply_with_roles(ctx, "Bot manager roles").await
+}
+
+/// Stop a role from using the bot's admin commands
+#[poise::command(slash_command, guild_only)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Role to remove"] role: Role,
+) -> Result<(), Error> {
+    ctx.data()
+        .repo
+        .remove_bot_manager_role(ctx.guild_id().unwrap().get(), role.id.get())
+        .await?;
+
+    reply_with_roles(ctx, "Bot manager roles").await
+}
+
+/// Show the roles that may use the bot's admin commands
+#[poise::command(slash_command, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    reply_with_roles(ctx, "Bot manager roles").await
+}
@@ -0,0 +1,147 @@
+use crate::charts::{resample, sparkline, MAX_SPARKLINE_LEN};
+use crate::progressive_embed::ProgressiveEmbed;
+use crate::server_stats::{busiest_hours, daily_messages, growth, hourly_messages};
+use crate::{Context, Error};
+use chrono::{DateTime, Duration, Utc};
+use futures::FutureExt;
+use num_format::Locale::en;
+use num_format::ToFormattedString;
+use poise::CreateReply;
+use rank_core::repository::Repository;
+use rank_core::stats::StatsScope;
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::GuildChannel;
+
+/// Number of top contributors shown
+const TOP: u64 = 5;
+
+/// Show the score, top contributors and activity of a channel
+#[poise::command(slash_command, guild_only)]
+pub async fn channelstats(
+    ctx: Context<'_>,
+    #[description = "Channel (default: this channel)"] channel: Option<GuildChannel>,
+    #[description = "Number of days for the trend and busiest hours (default 30, max 365)"]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+    let channel_id = channel.map(|c| c.id).unwrap_or(ctx.channel_id());
+    let days = days.unwrap_or(30).clamp(2, 365);
+
+    let channel = match repo.channel(channel_id.get()).await? {
+        Some(channel) if channel.guild == guild_id.get() as i64 => channel,
+        _ => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Channel not found")
+                        .description(format!(
+                            "Channel <#{}> not found in database (Try saying something there)",
+                            channel_id
+                        ))
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let window_contributors = format!("Top contributors - last {} days", days);
+    let mut msg = ProgressiveEmbed::new(
+        format!("Stats for #{}", channel.name),
+        vec![
+            "Score",
+            "Messages",
+            "Average message score",
+            "Top contributors",
+            &window_contributors,
+            "Activity trend",
+            "Busiest hours",
+        ],
+    )
+    .send(ctx)
+    .await?;
+
+    msg.set("Score", format!("{:.2}", channel.score)).await?;
+    msg.set("Messages", channel.message_count.to_formatted_string(&en))
+        .await?;
+
+    let all_time = StatsScope {
+        guild: guild_id.get(),
+        channel: Some(channel.snowflake),
+        since: DateTime::UNIX_EPOCH.naive_utc(),
+        user: None,
+    };
+    let now = Utc::now().naive_utc();
+    let window = StatsScope {
+        since: (now - Duration::days(days as i64 - 1))
+            .date()
+            .and_hms_opt(0, 0, 0)
+            .unwrap(),
+        ..all_time
+    };
+
+    msg.compute(vec![
+        (
+            "Average message score".to_string(),
+            async {
+                let summary = repo.summary(&all_time).await?;
+                Ok(match summary.average_score {
+                    Some(average) => format!("{:.2} from {} users", average, summary.users),
+                    None => "No messages yet".to_string(),
+                })
+            }
+            .boxed(),
+        ),
+        (
+            "Top contributors".to_string(),
+            contributors(repo, &all_time).boxed(),
+        ),
+        (
+            window_contributors.clone(),
+            contributors(repo, &window).boxed(),
+        ),
+        (
+            "Activity trend".to_string(),
+            async {
+                let per_day = daily_messages(repo, &window, now.date()).await?;
+                let half = per_day.len() / 2;
+                Ok(format!(
+                    "`{}`\n{} messages in {} days, {} in the last {} days compared to the {} before",
+                    sparkline(&resample(&per_day, MAX_SPARKLINE_LEN)),
+                    per_day.iter().sum::<f32>(),
+                    days,
+                    growth(
+                        per_day[per_day.len() - 2 * half..per_day.len() - half]
+                            .iter()
+                            .sum(),
+                        per_day[per_day.len() - half..].iter().sum()
+                    ),
+                    half,
+                    half
+                ))
+            }
+            .boxed(),
+        ),
+        (
+            "Busiest hours".to_string(),
+            async { Ok(busiest_hours(&hourly_messages(repo, &window).await?, 3)) }.boxed(),
+        ),
+    ])
+    .await?;
+
+    Ok(())
+}
+
+async fn contributors(repo: &dyn Repository, scope: &StatsScope) -> Result<String, Error> {
+    let top = repo.top_contributors(scope, TOP).await?;
+    if top.is_empty() {
+        return Ok("Nobody yet".to_string());
+    }
+    Ok(top
+        .iter()
+        .map(|c| format!("<@{}> - {:.2} ({} messages)", c.user, c.score, c.messages))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
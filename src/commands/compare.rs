@@ -0,0 +1,237 @@
+use crate::progressive_embed::{EmbedStyle, ProgressiveEmbed};
+use crate::stats_cache::{window_stats, Window};
+use crate::{Context, Error};
+use chrono::DateTime;
+use num_format::Locale::en;
+use num_format::ToFormattedString;
+use poise::CreateReply;
+use rank_core::levels::UserScore;
+use rank_core::model;
+use rank_core::repository::Repository;
+use rank_core::stats::StatsScope;
+use serenity::builder::CreateEmbed;
+use serenity::model::prelude::User;
+
+/// Number of top channels shown per user
+const TOP_CHANNELS: u64 = 3;
+
+/// One line per user with their value
+fn side_by_side(a: &model::User, b: &model::User, values: (String, String)) -> String {
+    format!("**{}**: {}\n**{}**: {}", a.name, values.0, b.name, values.1)
+}
+
+/// Marks for the higher of two values
+fn lead(a: f64, b: f64) -> (&'static str, &'static str) {
+    if a > b {
+        (" 🏆", "")
+    } else if b > a {
+        ("", " 🏆")
+    } else {
+        ("", "")
+    }
+}
+
+/// Two numbers side by side with the higher one marked
+fn compared(a: &model::User, b: &model::User, values: (f64, f64), precision: usize) -> String {
+    let (mark_a, mark_b) = lead(values.0, values.1);
+    side_by_side(
+        a,
+        b,
+        (
+            format!("{:.*}{}", precision, values.0, mark_a),
+            format!("{:.*}{}", precision, values.1, mark_b),
+        ),
+    )
+}
+
+async fn rank(repo: &dyn Repository, user: &model::User) -> Result<u64, Error> {
+    Ok(repo.users_above(user.guild as u64, user.score).await? + 1)
+}
+
+async fn channels(repo: &dyn Repository, scope: &StatsScope) -> Result<String, Error> {
+    let top = repo.top_channels(scope, TOP_CHANNELS).await?;
+    if top.is_empty() {
+        return Ok("None".to_string());
+    }
+    Ok(top
+        .iter()
+        .map(|c| format!("<#{}> {:.0} ({} messages)", c.channel, c.score, c.messages))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Compare the stats of two users
+#[poise::command(slash_command, guild_only)]
+pub async fn compare(
+    ctx: Context<'_>,
+    #[description = "First user"] user_a: User,
+    #[description = "Second user (default: you)"] user_b: Option<User>,
+) -> Result<(), Error> {
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+    let user_b = user_b.as_ref().unwrap_or(ctx.author());
+
+    let error = |title: &str, description: String| {
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(title)
+                .description(description)
+                .colour(0xff0000),
+        )
+    };
+
+    if user_a.id == user_b.id {
+        ctx.send(error(
+            "Nothing to compare",
+            "Pick two different users".to_string(),
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    let mut found = vec![];
+    for user in [&user_a, user_b] {
+        match repo.user(user.id.get()).await? {
+            Some(model) => found.push(model),
+            None => {
+                ctx.send(error(
+                    "User not found",
+                    format!(
+                        "User {} not found in database (Try saying something)",
+                        user.tag()
+                    ),
+                ))
+                .await?;
+                return Ok(());
+            }
+        }
+    }
+    let (a, b) = (&found[0], &found[1]);
+
+    let mut msg = ProgressiveEmbed::with_style(
+        format!("{} vs {}", a.name, b.name),
+        vec![
+            "Score",
+            "Level",
+            "Rank",
+            "Score - week",
+            "Score - month",
+            "Score - year",
+            "Messages",
+            "Average score for messages",
+            "Top channels",
+            "Replies to each other",
+        ],
+        EmbedStyle {
+            loading_footer: "Comparing...".to_string(),
+            finished_footer: "Finished comparing".to_string(),
+            ..EmbedStyle::default()
+        },
+    )
+    .send(ctx)
+    .await?;
+
+    msg.set("Score", compared(a, b, (a.score as f64, b.score as f64), 2))
+        .await?;
+    msg.set(
+        "Level",
+        side_by_side(
+            a,
+            b,
+            (
+                UserScore::new(a.score).display_score(),
+                UserScore::new(b.score).display_score(),
+            ),
+        ),
+    )
+    .await?;
+
+    let (rank_a, rank_b) = (rank(repo, a).await?, rank(repo, b).await?);
+    let (mark_a, mark_b) = lead(rank_b as f64, rank_a as f64);
+    msg.set(
+        "Rank",
+        side_by_side(
+            a,
+            b,
+            (
+                format!("#{}{}", rank_a, mark_a),
+                format!("#{}{}", rank_b, mark_b),
+            ),
+        ),
+    )
+    .await?;
+
+    let scope = |user: &model::User, since| StatsScope {
+        guild: guild_id.get(),
+        channel: None,
+        since,
+        user: Some(user.snowflake),
+    };
+
+    for (window, field) in [
+        (Window::Week, "Score - week"),
+        (Window::Month, "Score - month"),
+        (Window::Year, "Score - year"),
+    ] {
+        let score_a = window_stats(ctx.data(), guild_id.get(), a.snowflake, window)
+            .await?
+            .score;
+        let score_b = window_stats(ctx.data(), guild_id.get(), b.snowflake, window)
+            .await?
+            .score;
+        msg.set(field, compared(a, b, (score_a as f64, score_b as f64), 2))
+            .await?;
+    }
+
+    msg.set(
+        "Messages",
+        side_by_side(
+            a,
+            b,
+            (
+                a.message_count.to_formatted_string(&en),
+                b.message_count.to_formatted_string(&en),
+            ),
+        ),
+    )
+    .await?;
+
+    let all_time = DateTime::UNIX_EPOCH.naive_utc();
+    let average_a = repo.summary(&scope(a, all_time)).await?.average_score;
+    let average_b = repo.summary(&scope(b, all_time)).await?.average_score;
+    msg.set(
+        "Average score for messages",
+        compared(
+            a,
+            b,
+            (average_a.unwrap_or(0.0), average_b.unwrap_or(0.0)),
+            2,
+        ),
+    )
+    .await?;
+
+    msg.set(
+        "Top channels",
+        side_by_side(
+            a,
+            b,
+            (
+                channels(repo, &scope(a, all_time)).await?,
+                channels(repo, &scope(b, all_time)).await?,
+            ),
+        ),
+    )
+    .await?;
+
+    let (a_to_b, b_to_a) = repo.replies_between(a.snowflake, b.snowflake).await?;
+    msg.set(
+        "Replies to each other",
+        format!(
+            "{} → {}: {}\n{} → {}: {}",
+            a.name, b.name, a_to_b, b.name, a.name, b_to_a
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::reply_graph::ReplyGraph;
+use crate::{Context, Error};
+use poise::CreateReply;
+use rank_core::replies::Thread;
+use serenity::all::GuildId;
+use serenity::builder::CreateEmbed;
+use serenity::model::prelude::User;
+use std::cmp::Reverse;
+
+/// Number of entries in each list
+const TOP: usize = 5;
+
+fn list(entries: Vec<String>) -> String {
+    if entries.is_empty() {
+        "Nobody yet".to_string()
+    } else {
+        entries.join("\n")
+    }
+}
+
+fn ranked(counts: impl IntoIterator<Item = (i64, i64)>, unit: &str) -> String {
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_by_key(|p| Reverse(p.1));
+    list(
+        counts
+            .iter()
+            .take(TOP)
+            .map(|(user, count)| format!("<@{}> - {} {}", user, count, unit))
+            .collect(),
+    )
+}
+
+fn depth(guild_id: GuildId, depth: Option<(f32, &Thread)>) -> String {
+    match depth {
+        Some((average, deepest)) => format!(
+            "Average {:.1}, deepest {} with {} replies ([jump](https://discord.com/channels/{}/{}/{}))",
+            average, deepest.depth, deepest.replies, guild_id, deepest.channel, deepest.root
+        ),
+        None => "No conversations yet".to_string(),
+    }
+}
+
+fn percent(share: Option<f32>) -> String {
+    share
+        .map(|share| format!("{:.0}%", share * 100.0))
+        .unwrap_or("No replies yet".to_string())
+}
+
+/// Show who talks to whom, built from replies
+#[poise::command(slash_command, guild_only)]
+pub async fn connections(
+    ctx: Context<'_>,
+    #[description = "User (default: the whole server)"] user: Option<User>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let graph = ReplyGraph::load(ctx.data().repo.as_ref(), guild_id.get()).await?;
+
+    let embed = match user {
+        Some(user) => {
+            let id = user.id.get() as i64;
+            let started = graph.starters().get(&id).copied().unwrap_or(0);
+            let replied = graph.responders().get(&id).copied().unwrap_or(0);
+
+            CreateEmbed::default()
+                .title(format!("Connections of {}", user.name))
+                .field(
+                    "Replies to",
+                    list(
+                        graph
+                            .replies_to(id, TOP)
+                            .iter()
+                            .map(|(other, replies)| {
+                                format!(
+                                    "<@{}> - {} replies, {} back",
+                                    other,
+                                    replies,
+                                    graph.replies(*other, id)
+                                )
+                            })
+                            .collect(),
+                    ),
+                    true,
+                )
+                .field(
+                    "Replied to by",
+                    list(
+                        graph
+                            .replied_by(id, TOP)
+                            .iter()
+                            .map(|(other, replies)| format!("<@{}> - {} replies", other, replies))
+                            .collect(),
+                    ),
+                    true,
+                )
+                .field("Reciprocity", percent(graph.reciprocity(Some(id))), false)
+                .field("Conversations started", started.to_string(), true)
+                .field("Replies sent", replied.to_string(), true)
+                .field(
+                    "Thread depth of their conversations",
+                    depth(guild_id, graph.thread_depth(Some(id))),
+                    false,
+                )
+        }
+        None => CreateEmbed::default()
+            .title("Server connections")
+            .field(
+                "Most replies",
+                list(
+                    graph
+                        .top_pairs(TOP)
+                        .iter()
+                        .map(|((from, to), replies)| {
+                            format!("<@{}> → <@{}> - {} replies", from, to, replies)
+                        })
+                        .collect(),
+                ),
+                false,
+            )
+            .field(
+                "Closest pairs",
+                list(
+                    graph
+                        .mutual_pairs(TOP)
+                        .iter()
+                        .map(|((a, b), replies)| {
+                            format!(
+                                "<@{}> ↔ <@{}> - at least {} replies each way",
+                                a, b, replies
+                            )
+                        })
+                        .collect(),
+                ),
+                false,
+            )
+            .field(
+                "Conversation starters",
+                ranked(graph.starters(), "conversations"),
+                true,
+            )
+            .field("Responders", ranked(graph.responders(), "replies"), true)
+            .field("Reciprocity", percent(graph.reciprocity(None)), false)
+            .field(
+                "Thread depth",
+                depth(guild_id, graph.thread_depth(None)),
+                false,
+            ),
+    };
+
+    ctx.send(CreateReply::default().embed(embed.colour(0x00ff00)))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use crate::export::{export_guild, ExportFormat, ExportOptions};
+use crate::{Context, Error};
+use chrono::NaiveDate;
+use poise::CreateReply;
+use serenity::builder::{CreateAttachment, CreateEmbed};
+
+/// Largest total upload a bot can attach to a message
+const MAX_ATTACHMENT_SIZE: u64 = 25 * 1024 * 1024;
+
+fn parse_date(date: Option<String>) -> Result<Option<NaiveDate>, String> {
+    date.map(|date| {
+        NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("`{}` is not a date, use YYYY-MM-DD", date))
+    })
+    .transpose()
+}
+
+/// Export this server's users, channels and messages as files
+#[poise::command(slash_command, guild_only, check = "crate::permissions::bot_manager")]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "File format (default CSV)"] format: Option<ExportFormat>,
+    #[description = "First day of messages to export (YYYY-MM-DD)"] from: Option<String>,
+    #[description = "Last day of messages to export (YYYY-MM-DD)"] to: Option<String>,
+    #[description = "Include message content (default off)"] include_content: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let (from, to) = match (parse_date(from), parse_date(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Invalid date")
+                        .description(e)
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    ctx.defer().await?;
+
+    let options = ExportOptions {
+        guild: guild_id.get(),
+        format: format.unwrap_or(ExportFormat::Csv),
+        from,
+        to,
+        include_content: include_content.unwrap_or(false),
+    };
+
+    let dir = std::env::temp_dir().join(format!("rank_bot_export_{}_{}", guild_id, ctx.id()));
+    let summary = export_guild(ctx.data().repo.as_ref(), &options, &dir).await?;
+
+    let mut size = 0;
+    for file in summary.files.iter() {
+        size += std::fs::metadata(file)?.len();
+    }
+
+    let description = format!(
+        "{} users, {} channels and {} messages",
+        summary.users, summary.channels, summary.messages
+    );
+
+    if size > MAX_ATTACHMENT_SIZE {
+        ctx.send(
+            CreateReply::default().embed(
+                CreateEmbed::default()
+                    .title("Export too large")
+                    .description(format!(
+                        "{} is too large to upload, narrow the date range or use the `export` CLI command",
+                        description
+                    ))
+                    .colour(0xff0000),
+            ),
+        )
+        .await?;
+    } else {
+        let mut reply = CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Export")
+                .description(description)
+                .colour(0x00ff00),
+        );
+        for file in summary.files.iter() {
+            reply = reply.attachment(CreateAttachment::path(file).await?);
+        }
+        ctx.send(reply).await?;
+    }
+
+    std::fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
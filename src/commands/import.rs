@@ -0,0 +1,141 @@
+use crate::import::{import_records, parse_records, ImportFormat};
+use crate::stats_cache::invalidate_guild;
+use crate::{Context, Error};
+use poise::CreateReply;
+use serenity::all::Attachment;
+use serenity::builder::CreateEmbed;
+
+/// Import levels from another levelling bot
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "crate::permissions::bot_manager",
+    subcommands("upload", "revert", "list")
+)]
+pub async fn import(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Import a JSON or CSV export of user id, xp, level and message count
+#[poise::command(slash_command, guild_only)]
+pub async fn upload(
+    ctx: Context<'_>,
+    #[description = "Export file from the other bot"] file: Attachment,
+    #[description = "File format (default: guessed from the file name)"] format: Option<
+        ImportFormat,
+    >,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let format = format.unwrap_or_else(|| ImportFormat::from_file_name(&file.filename));
+    let records = match parse_records(format, &file.download().await?) {
+        Ok(records) => records,
+        Err(e) => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Could not read export")
+                        .description(e.to_string())
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let guild_name = ctx.guild().map(|g| g.name.clone()).unwrap_or_default();
+    ctx.data()
+        .repo
+        .ensure_guild(guild_id.get(), &guild_name)
+        .await?;
+
+    let summary = import_records(
+        ctx.data().repo.as_ref(),
+        guild_id.get(),
+        Some(ctx.author().id.get()),
+        &records,
+    )
+    .await?;
+    invalidate_guild(ctx.data(), Some(guild_id.get())).await;
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Import finished")
+                .field("Users", summary.users.to_string(), true)
+                .field("New users", summary.created_users.to_string(), true)
+                .field("Score added", format!("{:.0}", summary.score), true)
+                .field("Batch", format!("`{}`", summary.batch), false)
+                .description("Use `/import revert` with the batch to undo this import")
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Undo an import
+#[poise::command(slash_command, guild_only)]
+pub async fn revert(
+    ctx: Context<'_>,
+    #[description = "Batch shown when the import finished"] batch: String,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let users = ctx
+        .data()
+        .repo
+        .revert_import(guild_id.get(), batch.trim())
+        .await?;
+    invalidate_guild(ctx.data(), Some(guild_id.get())).await;
+
+    let embed = if users == 0 {
+        CreateEmbed::default()
+            .title("Import not found")
+            .description(format!("No import with batch `{}`", batch.trim()))
+            .colour(0xff0000)
+    } else {
+        CreateEmbed::default()
+            .title("Import reverted")
+            .description(format!("Removed imported score from {} users", users))
+            .colour(0x00ff00)
+    };
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
+
+/// List the imports of this server
+#[poise::command(slash_command, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+
+    let batches = ctx.data().repo.import_batches(guild_id.get()).await?;
+
+    let description = if batches.is_empty() {
+        "No imports".to_string()
+    } else {
+        batches
+            .iter()
+            .map(|(batch, users)| format!("`{}` - {} users", batch, users))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Imports")
+                .description(description)
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -1,3 +1,17 @@
+pub(crate) mod activity;
+pub(crate) mod botmanagers;
+pub(crate) mod channelstats;
+pub(crate) mod compare;
+pub(crate) mod connections;
+pub(crate) mod export;
+pub(crate) mod import;
 pub(crate) mod leaderboard;
 pub(crate) mod messages;
+pub(crate) mod retention;
+pub(crate) mod score;
+pub(crate) mod scoring;
+pub(crate) mod serverstats;
 pub(crate) mod stats;
+pub(crate) mod stopwords;
+pub(crate) mod voicepoints;
+pub(crate) mod words;
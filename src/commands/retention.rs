@@ -0,0 +1,65 @@
+use crate::retention::apply_guild_retention;
+use crate::{guild_settings, Context, Error};
+use poise::CreateReply;
+use serenity::builder::CreateEmbed;
+
+/// Configure how long message content is kept for this server
+#[poise::command(slash_command, guild_only, check = "crate::permissions::bot_manager")]
+pub async fn retention(
+    ctx: Context<'_>,
+    #[description = "Days to keep message content (0 = never store content)"]
+    #[max = 36500]
+    days: Option<u32>,
+    #[description = "Truncate old content to this many characters instead of removing it"]
+    #[max = 4000]
+    truncate_to: Option<u32>,
+    #[description = "Keep message content forever (default off)"] disable: Option<bool>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let mut settings = guild_settings::get(ctx.data(), guild_id).await?;
+
+    if disable.unwrap_or(false) {
+        settings.retention_days = None;
+        settings.retention_truncate = None;
+    } else {
+        if let Some(days) = days {
+            settings.retention_days = Some(i32::try_from(days)?);
+        }
+        if let Some(chars) = truncate_to {
+            settings.retention_truncate = Some(i32::try_from(chars)?);
+        }
+    }
+
+    let changed = disable.is_some() || days.is_some() || truncate_to.is_some();
+    let mut pruned = 0;
+    if changed {
+        guild_settings::save(ctx.data(), settings.clone()).await?;
+        pruned = apply_guild_retention(ctx.data().repo.as_ref(), &settings).await?;
+    }
+
+    let policy = match (settings.retention_days, settings.retention_truncate) {
+        (None, _) => "Message content is kept forever".to_string(),
+        (Some(0), None) => "Message content is never stored".to_string(),
+        (Some(0), Some(chars)) => format!("Only the first {} characters are stored", chars),
+        (Some(days), None) => format!("Message content is removed after {} days", days),
+        (Some(days), Some(chars)) => format!(
+            "Message content is truncated to {} characters after {} days",
+            chars, days
+        ),
+    };
+
+    let mut embed = CreateEmbed::default()
+        .title("Content retention")
+        .description(policy)
+        .colour(0x00ff00);
+
+    if changed {
+        embed = embed.field("Messages pruned now", pruned.to_string(), true);
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use crate::stats_cache::invalidate_user;
+use crate::{Context, Error};
+use poise::CreateReply;
+use rank_core::adjustments::{NewAdjustment, MANUAL_SOURCE};
+use rank_core::levels::UserScore;
+use serenity::builder::CreateEmbed;
+use serenity::model::prelude::User;
+
+/// Manually adjust scores, every change is kept in an audit log
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "crate::permissions::bot_manager",
+    subcommands("add", "remove", "set", "history")
+)]
+pub async fn score(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn adjust(
+    ctx: Context<'_>,
+    user: &User,
+    delta: impl FnOnce(f32) -> f32,
+    reason: Option<String>,
+) -> Result<(), Error> {
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+
+    let db_user = match repo.user(user.id.get()).await? {
+        Some(db_user) => db_user,
+        None => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("User not found")
+                        .description(format!("User {} not found in database", user.tag()))
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let delta = delta(db_user.score);
+
+    repo.apply_adjustment(NewAdjustment {
+        guild: guild_id.get(),
+        user: user.id.get(),
+        moderator: Some(ctx.author().id.get()),
+        delta,
+        message_delta: 0,
+        source: MANUAL_SOURCE,
+        batch: None,
+        reason: reason.clone(),
+    })
+    .await?;
+    invalidate_user(ctx.data(), guild_id.get(), db_user.snowflake).await;
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(format!("Adjusted score of {}", db_user.name))
+                .field("Change", format!("{:+.2}", delta), true)
+                .field(
+                    "Before",
+                    UserScore::new(db_user.score).display_score(),
+                    false,
+                )
+                .field(
+                    "After",
+                    UserScore::new(db_user.score + delta).display_score(),
+                    false,
+                )
+                .field("Reason", reason.unwrap_or("None given".to_string()), false)
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Add to a user's score
+#[poise::command(slash_command, guild_only)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "User to adjust"] user: User,
+    #[description = "Score to add"] amount: f32,
+    #[description = "Why the score is being changed"] reason: Option<String>,
+) -> Result<(), Error> {
+    adjust(ctx, &user, |_| amount.abs(), reason).await
+}
+
+/// Remove from a user's score
+#[poise::command(slash_command, guild_only)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "User to adjust"] user: User,
+    #[description = "Score to remove"] amount: f32,
+    #[description = "Why the score is being changed"] reason: Option<String>,
+) -> Result<(), Error> {
+    adjust(ctx, &user, |_| -amount.abs(), reason).await
+}
+
+/// Set a user's score
+#[poise::command(slash_command, guild_only)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "User to adjust"] user: User,
+    #[description = "New score"] score: f32,
+    #[description = "Why the score is being changed"] reason: Option<String>,
+) -> Result<(), Error> {
+    adjust(ctx, &user, |current| score - current, reason).await
+}
+
+/// Show the audit log of score adjustments
+#[poise::command(slash_command, guild_only)]
+pub async fn history(
+    ctx: Context<'_>,
+    #[description = "Only show adjustments of this user"] user: Option<User>,
+    #[description = "Page (default 1)"] page: Option<u16>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let page = page.unwrap_or(1).max(1);
+
+    let adjustments = ctx
+        .data()
+        .repo
+        .adjustment_history(
+            guild_id.get(),
+            user.as_ref().map(|u| u.id.get() as i64),
+            (page as u64 - 1) * 10,
+            10,
+        )
+        .await?;
+
+    let description = if adjustments.is_empty() {
+        "No adjustments".to_string()
+    } else {
+        adjustments
+            .iter()
+            .map(|a| {
+                format!(
+                    "<t:{}:f> **{:+.2}** <@{}> by {} ({}){}",
+                    a.timestamp.and_utc().timestamp(),
+                    a.delta,
+                    a.user,
+                    a.moderator
+                        .map(|m| format!("<@{}>", m))
+                        .unwrap_or("the bot".to_string()),
+                    a.source,
+                    a.reason
+                        .as_ref()
+                        .map(|r| format!(": {}", r))
+                        .unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(format!("Score adjustments page: {}", page))
+                .description(description)
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
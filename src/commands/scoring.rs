@@ -0,0 +1,109 @@
+use crate::{guild_settings, Context, Error};
+use poise::CreateReply;
+use rank_core::scoring::ScoringConfig;
+use serenity::builder::CreateEmbed;
+
+/// Configure how attachments, links, code, mentions and emoji count towards a message's score
+#[allow(clippy::too_many_arguments)]
+#[poise::command(slash_command, guild_only, check = "crate::permissions::bot_manager")]
+pub async fn scoring(
+    ctx: Context<'_>,
+    #[description = "Points per image"] image_points: Option<f32>,
+    #[description = "Points per video"] video_points: Option<f32>,
+    #[description = "Points per other attachment"] file_points: Option<f32>,
+    #[description = "Points per link"] link_points: Option<f32>,
+    #[description = "Most links that earn points"]
+    #[max = 1000]
+    max_links: Option<u32>,
+    #[description = "Points per line of code in code blocks"] code_line_points: Option<f32>,
+    #[description = "Most lines of code that earn points"]
+    #[max = 1000]
+    max_code_lines: Option<u32>,
+    #[description = "Points per mention (negative to discourage pings)"] mention_points: Option<
+        f32,
+    >,
+    #[description = "Multiplier for messages that are only emoji"] emoji_only_multiplier: Option<
+        f32,
+    >,
+    #[description = "Go back to the default weights"] reset: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let mut settings = guild_settings::get(ctx.data(), guild_id).await?;
+    let mut config = if reset.unwrap_or(false) {
+        ScoringConfig::default()
+    } else {
+        ScoringConfig::from_settings(&settings)
+    };
+
+    if let Some(points) = image_points {
+        config.image_points = points;
+    }
+    if let Some(points) = video_points {
+        config.video_points = points;
+    }
+    if let Some(points) = file_points {
+        config.file_points = points;
+    }
+    if let Some(points) = link_points {
+        config.link_points = points;
+    }
+    if let Some(max) = max_links {
+        config.max_links = i32::try_from(max)?;
+    }
+    if let Some(points) = code_line_points {
+        config.code_line_points = points;
+    }
+    if let Some(max) = max_code_lines {
+        config.max_code_lines = i32::try_from(max)?;
+    }
+    if let Some(points) = mention_points {
+        config.mention_points = points;
+    }
+    if let Some(multiplier) = emoji_only_multiplier {
+        config.emoji_only_multiplier = multiplier;
+    }
+
+    if config != ScoringConfig::from_settings(&settings) {
+        settings.scoring = Some(serde_json::to_value(&config)?);
+        guild_settings::save(ctx.data(), settings).await?;
+    }
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Scoring")
+                .description("Applies to new messages, existing scores are not changed")
+                .field("Image", format!("{} points", config.image_points), true)
+                .field("Video", format!("{} points", config.video_points), true)
+                .field(
+                    "Other attachment",
+                    format!("{} points", config.file_points),
+                    true,
+                )
+                .field(
+                    "Link",
+                    format!("{} points, up to {}", config.link_points, config.max_links),
+                    true,
+                )
+                .field(
+                    "Line of code",
+                    format!(
+                        "{} points, up to {}",
+                        config.code_line_points, config.max_code_lines
+                    ),
+                    true,
+                )
+                .field("Mention", format!("{} points", config.mention_points), true)
+                .field(
+                    "Emoji only",
+                    format!("× {}", config.emoji_only_multiplier),
+                    true,
+                )
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,154 @@
+use crate::charts::{resample, sparkline, MAX_SPARKLINE_LEN};
+use crate::progressive_embed::ProgressiveEmbed;
+use crate::server_stats::{busiest_hours, daily_messages, growth, hourly_messages};
+use crate::{Context, Error};
+use chrono::{Duration, NaiveDateTime, Utc};
+use futures::FutureExt;
+use num_format::Locale::en;
+use num_format::ToFormattedString;
+use poise::CreateReply;
+use rank_core::stats::StatsScope;
+use serenity::builder::CreateEmbed;
+
+/// Number of top channels shown
+const TOP: usize = 5;
+
+/// Show the size, activity and growth of the server
+#[poise::command(slash_command, guild_only)]
+pub async fn serverstats(
+    ctx: Context<'_>,
+    #[description = "Number of days for growth, the trend and busiest hours (default 30, max 365)"]
+    days: Option<u32>,
+) -> Result<(), Error> {
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+    let days = days.unwrap_or(30).clamp(1, 365);
+
+    let guild = match repo.guild(guild_id.get()).await? {
+        Some(guild) => guild,
+        None => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Server not found")
+                        .description("This server has no messages in the database yet")
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let growth_field = format!("Growth - last {} days", days);
+    let mut msg = ProgressiveEmbed::new(
+        format!("Stats for {}", guild.name),
+        vec![
+            "Messages",
+            "Score",
+            "Members",
+            "Active users - day",
+            "Active users - week",
+            "Active users - month",
+            &growth_field,
+            "Activity trend",
+            "Top channels",
+            "Busiest hours",
+        ],
+    )
+    .send(ctx)
+    .await?;
+
+    msg.set("Messages", guild.message_count.to_formatted_string(&en))
+        .await?;
+    msg.set("Score", format!("{:.2}", guild.score)).await?;
+    // Discord's count when the guild is cached, otherwise the users that have posted
+    let members = match ctx.guild().map(|g| g.member_count) {
+        Some(members) => members,
+        None => repo.user_count(guild_id.get()).await?,
+    };
+    msg.set("Members", members.to_formatted_string(&en)).await?;
+
+    let now = Utc::now().naive_utc();
+    let since = |days: i64| -> NaiveDateTime { now - Duration::days(days) };
+    let scope = |since: NaiveDateTime| StatsScope {
+        guild: guild_id.get(),
+        channel: None,
+        since,
+        user: None,
+    };
+
+    let active_users = |days: i64| {
+        async move {
+            let users = repo.summary(&scope(since(days))).await?.users;
+            Ok(users.to_formatted_string(&en))
+        }
+        .boxed()
+    };
+    let window = scope(since(days as i64 - 1).date().and_hms_opt(0, 0, 0).unwrap());
+
+    msg.compute(vec![
+        ("Active users - day".to_string(), active_users(1)),
+        ("Active users - week".to_string(), active_users(7)),
+        ("Active users - month".to_string(), active_users(30)),
+        (
+            growth_field.clone(),
+            async {
+                let recent = repo.summary(&scope(since(days as i64))).await?.messages;
+                let both = repo.summary(&scope(since(2 * days as i64))).await?.messages;
+                let joined = repo.new_users(&scope(since(days as i64))).await?;
+                Ok(format!(
+                    "{} messages, {} compared to the {} days before\n{} new members",
+                    recent.to_formatted_string(&en),
+                    growth((both - recent) as f32, recent as f32),
+                    days,
+                    joined.to_formatted_string(&en)
+                ))
+            }
+            .boxed(),
+        ),
+        (
+            "Activity trend".to_string(),
+            async {
+                let per_day = daily_messages(repo, &window, now.date()).await?;
+                Ok(format!(
+                    "`{}`\npeak {} messages in a day",
+                    sparkline(&resample(&per_day, MAX_SPARKLINE_LEN)),
+                    per_day.iter().cloned().fold(0.0, f32::max)
+                ))
+            }
+            .boxed(),
+        ),
+        (
+            "Top channels".to_string(),
+            async {
+                let mut channels = repo.guild_channels(guild_id.get()).await?;
+                channels.truncate(TOP);
+                Ok(if channels.is_empty() {
+                    "No channels yet".to_string()
+                } else {
+                    channels
+                        .iter()
+                        .map(|c| {
+                            format!(
+                                "<#{}> - {:.2} ({} messages)",
+                                c.snowflake,
+                                c.score,
+                                c.message_count.to_formatted_string(&en)
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                })
+            }
+            .boxed(),
+        ),
+        (
+            "Busiest hours".to_string(),
+            async { Ok(busiest_hours(&hourly_messages(repo, &window).await?, 3)) }.boxed(),
+        ),
+    ])
+    .await?;
+
+    Ok(())
+}
@@ -1,176 +1,30 @@
-use crate::scores::UserScore;
+use crate::common_words::guild_common_words;
+use crate::progressive_embed::ProgressiveEmbed;
+use crate::stats_cache::{window_stats, Window};
+use crate::voice::format_voice_time;
 use crate::Context;
 use crate::Error;
-
-use entity::prelude::{Channels, Messages, Users};
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::FutureExt;
+use rank_core::levels::UserScore;
+use rank_core::repository::Repository;
 
 use num_format::Locale::en;
 use num_format::ToFormattedString;
 use poise::CreateReply;
-use poise::ReplyHandle;
-use sea_orm::ColumnTrait;
-use sea_orm::QueryFilter;
-use sea_orm::{EntityTrait, QueryOrder};
 use serenity::all::ChannelId;
 use serenity::builder::CreateEmbed;
-use serenity::builder::CreateEmbedFooter;
 
 use serenity::model::prelude::User;
 use serenity::prelude::Mentionable;
-use std::collections::{BTreeMap, HashMap};
-
-struct StatMessage<'a> {
-    title: String,
-    fields: BTreeMap<String, Option<String>>,
-    message: ReplyHandle<'a>,
-    ctx: &'a Context<'a>,
-}
-
-impl StatMessage<'_> {
-    async fn new<'a>(
-        title: impl ToString,
-        ctx: &'a Context<'a>,
-        fields: Vec<impl ToString>,
-    ) -> Result<StatMessage<'a>, Error> {
-        Ok(StatMessage {
-            title: title.to_string(),
-            fields: fields
-                .iter()
-                .map(|field| (field.to_string(), None))
-                .collect(),
-            message: ctx
-                .send(
-                    CreateReply::default().embed(
-                        CreateEmbed::default()
-                            .fields(
-                                fields
-                                    .iter()
-                                    .map(|field| {
-                                        (
-                                            field.to_string(),
-                                            "<a:recall_loading:1163546685994188934>".to_string(),
-                                            true,
-                                        )
-                                    })
-                                    .collect::<Vec<(String, String, bool)>>(),
-                            )
-                            .title(title.to_string())
-                            .colour(0x00ff00),
-                    ),
-                )
-                .await?,
-            ctx,
-        })
-    }
-
-    async fn set(
-        &mut self,
-        name: impl ToString,
-        value: Option<impl ToString>,
-    ) -> Result<(), Error> {
-        self.fields
-            .insert(name.to_string(), value.map(|v| v.to_string()));
-        self.edit().await?;
-
-        Ok(())
-    }
-
-    fn get_progress(&self) -> f32 {
-        let mut progress = 0.0;
-        for (_, value) in self.fields.iter() {
-            if value.is_some() {
-                progress += 1.0;
-            }
-        }
-
-        progress / self.fields.len() as f32
-    }
-
-    async fn edit(&self) -> Result<(), Error> {
-        let fields = self.fields.clone();
-        // self.message
-        //     .edit(self.ctx.clone(), |m| {
-        //         m.embed(|e| {
-        //             e.title(&self.title);
-        //
-        //             for (name, value) in fields {
-        //                 e.field(
-        //                     name,
-        //                     value.unwrap_or("<a:recall_loading:1163546685994188934>".to_string()),
-        //                     true,
-        //                 );
-        //             }
-        //
-        //             if self.get_progress() == 1.0 {
-        //                 e.footer(|f| {
-        //                     f.icon_url(
-        //                         "https://cdn.discordapp.com/emojis/1163591120840831046.gif?v=1",
-        //                     );
-        //                     f.text("Finished loading stats")
-        //                 })
-        //             } else {
-        //                 e.footer(|f| {
-        //                     f.icon_url(
-        //                         "https://cdn.discordapp.com/emojis/1163546685994188934.gif?v=1",
-        //                     );
-        //                     f.text(format!(
-        //                         "Loading stats... {:.2}%",
-        //                         self.get_progress() * 100.0
-        //                     ))
-        //                 })
-        //             };
-        //             e.colour(0x00ff00)
-        //         })
-        //     })
-        //     .await?;
-        self.message
-            .edit(
-                *self.ctx,
-                CreateReply::default().embed(
-                    CreateEmbed::default()
-                        .title(&self.title)
-                        .fields(
-                            fields
-                                .iter()
-                                .map(|(name, value)| {
-                                    (
-                                        name,
-                                        value.clone().unwrap_or(
-                                            "<a:recall_loading:1163546685994188934>".to_string(),
-                                        ),
-                                        true,
-                                    )
-                                })
-                                .collect::<Vec<(&String, String, bool)>>(),
-                        )
-                        .colour(0x00ff00)
-                        .footer(
-                            CreateEmbedFooter::new(if self.get_progress() == 1.0 {
-                                "Finished loading stats".to_string()
-                            } else {
-                                format!("Loading stats... {:.2}%", self.get_progress() * 100.0)
-                            })
-                            .icon_url(if self.get_progress() == 1.0 {
-                                "https://cdn.discordapp.com/emojis/1163591120840831046.gif?v=1"
-                                    .to_string()
-                            } else {
-                                "https://cdn.discordapp.com/emojis/1163546685994188934.gif?v=1"
-                                    .to_string()
-                            }),
-                        ),
-                ),
-            )
-            .await?;
-        Ok(())
-    }
-}
+use std::collections::HashMap;
 
 #[poise::command(slash_command, guild_only)]
 pub async fn stats(
     ctx: Context<'_>,
     #[description = "User (defualt: you)"] user: Option<User>,
 ) -> Result<(), Error> {
-    let db = &ctx.data().db;
+    let repo = &ctx.data().repo;
     let user = match user {
         Some(ref user) => user,
         None => ctx.author(),
@@ -178,7 +32,7 @@ pub async fn stats(
 
     let guild_id = ctx.guild_id().unwrap();
 
-    let user = match Users::find_by_id(user.id.get() as i64).one(db).await? {
+    let user = match repo.user(user.id.get()).await? {
         None => {
             // ctx.send(|m| {
             //     m.embed(|e| {
@@ -209,9 +63,8 @@ pub async fn stats(
         }
         Some(user) => user,
     };
-    let mut msg = StatMessage::new(
+    let mut msg = ProgressiveEmbed::new(
         format!("Stats for {}", user.name),
-        &ctx,
         vec![
             "Score",
             "Score - week",
@@ -226,454 +79,249 @@ pub async fn stats(
             "Best Channel - month",
             "Best Channel - year",
             "Messages",
+            "Voice time",
             "XP summary",
             "Average score for messages",
             "Average score for messages - rank",
             "'Best' message",
             "Average post length",
+            "Most reacted message",
+            "Favorite emoji",
+            "Reactions given",
+            "Reactions received",
             "3 most common uncommon words",
         ],
     )
+    .send(ctx)
     .await?;
 
-    msg.set("Score", Some(user.score)).await?;
-    msg.set(
-        "Messages",
-        Some(user.message_count.to_formatted_string(&en)),
-    )
-    .await?;
-    msg.set(
-        "XP summary",
-        Some(UserScore::new(user.score).display_score()),
-    )
-    .await?;
-
-    let last_week = chrono::Utc::now() - chrono::Duration::try_weeks(1).unwrap();
-
-    let last_week_of_messages = Messages::find()
-        .filter(entity::messages::Column::User.eq(user.snowflake))
-        .filter(entity::messages::Column::Timestamp.gt(last_week))
-        .all(db)
+    msg.set("Score", user.score).await?;
+    msg.set("Messages", user.message_count.to_formatted_string(&en))
         .await?;
-
-    msg.set(
-        "Score - week",
-        Some(
-            last_week_of_messages
-                .iter()
-                .map(|m| m.score)
-                .sum::<f32>()
-                .to_string(),
-        ),
-    )
-    .await?;
-
-    let last_month = chrono::Utc::now() - chrono::Duration::try_days(30).unwrap();
-
-    let last_month_of_messages = Messages::find()
-        .filter(entity::messages::Column::User.eq(user.snowflake))
-        .filter(entity::messages::Column::Timestamp.gt(last_month))
-        .all(db)
+    msg.set("XP summary", UserScore::new(user.score).display_score())
         .await?;
 
-    msg.set(
-        "Score - month",
-        Some(last_month_of_messages.iter().map(|m| m.score).sum::<f32>()),
+    // both the score and the rank of a window come from one computation
+    let windows = futures::future::try_join_all(
+        Window::ALL.map(|window| window_stats(ctx.data(), guild_id.get(), user.snowflake, window)),
     )
     .await?;
-
-    let last_year = chrono::Utc::now() - chrono::Duration::try_days(365).unwrap();
-
-    let last_year_of_messages = Messages::find()
-        .filter(entity::messages::Column::User.eq(user.snowflake))
-        .filter(entity::messages::Column::Timestamp.gt(last_year))
-        .all(db)
+    for (stats, name) in windows.iter().zip(["week", "month", "year"]) {
+        msg.set(
+            format!("Score - {}", name),
+            format!("{} ({} messages)", stats.score, stats.messages),
+        )
         .await?;
+        msg.set(format!("Rank - {}", name), stats.rank).await?;
+    }
 
-    msg.set(
-        "Score - year",
-        Some(last_year_of_messages.iter().map(|m| m.score).sum::<f32>()),
-    )
-    .await?;
-
-    let mut users = Users::find()
-        .order_by_desc(entity::users::Column::Score)
-        .all(db)
-        .await?;
+    // the fields about single messages all read the same messages
+    let messages = repo.user_messages(user.snowflake, None, None).await?;
 
     msg.set(
-        "Rank",
-        Some(
-            (users
-                .iter()
-                .position(|u| u.snowflake == user.snowflake)
-                .unwrap()
-                + 1)
-            .to_string(),
-        ),
+        "Average score for messages",
+        if messages.is_empty() {
+            "None".to_string()
+        } else {
+            format!(
+                "{:.2}",
+                messages.iter().map(|m| m.score).sum::<f32>() / messages.len() as f32
+            )
+        },
     )
     .await?;
 
-    let last_week_of_messages = Messages::find()
-        .filter(entity::messages::Column::Timestamp.gt(last_week))
-        .all(db)
-        .await?;
-
-    // use last week of messages
-    let last_week_ranking = users
-        .iter()
-        .map(|u| {
-            (
-                u.snowflake,
-                last_week_of_messages
-                    .iter()
-                    .filter(|m| m.user == u.snowflake)
-                    .map(|m| m.score)
-                    .sum::<f32>(),
-            )
-        })
-        .collect::<HashMap<i64, f32>>();
+    // 'best' message
 
-    users.sort_by(|a, b| {
-        last_week_ranking
-            .get(&b.snowflake)
-            .unwrap()
-            .partial_cmp(last_week_ranking.get(&a.snowflake).unwrap())
-            .unwrap()
-    });
+    let best_message = messages.iter().max_by(|a, b| a.score.total_cmp(&b.score));
 
     msg.set(
-        "Rank - week",
-        Some(
-            (users
-                .iter()
-                .position(|u| u.snowflake == user.snowflake)
-                .unwrap()
-                + 1)
-            .to_string(),
-        ),
+        "'Best' message",
+        match best_message {
+            Some(best_message) => format!(
+                "https://discord.com/channels/{}/{}/{} - {}",
+                guild_id.get(),
+                best_message.channel,
+                best_message.snowflake,
+                best_message.score
+            ),
+            None => "None".to_string(),
+        },
     )
     .await?;
 
-    let last_month_of_messages = Messages::find()
-        .filter(entity::messages::Column::Timestamp.gt(last_week))
-        .all(db)
-        .await?;
-
-    // use last month of messages
-    let last_month_ranking = users
-        .iter()
-        .map(|u| {
-            (
-                u.snowflake,
-                last_month_of_messages
-                    .iter()
-                    .filter(|m| m.user == u.snowflake)
-                    .map(|m| m.score)
-                    .sum::<f32>(),
-            )
-        })
-        .collect::<HashMap<i64, f32>>();
-
-    users.sort_by(|a, b| {
-        last_month_ranking
-            .get(&b.snowflake)
-            .unwrap()
-            .partial_cmp(last_month_ranking.get(&a.snowflake).unwrap())
-            .unwrap()
-    });
-
     msg.set(
-        "Rank - month",
-        Some(
-            (users
-                .iter()
-                .position(|u| u.snowflake == user.snowflake)
-                .unwrap()
-                + 1)
-            .to_string(),
-        ),
+        "Average post length",
+        if messages.is_empty() {
+            "None".to_string()
+        } else {
+            format!(
+                "{}",
+                messages.iter().map(|m| m.length as usize).sum::<usize>() / messages.len()
+            )
+        },
     )
     .await?;
 
-    let last_year_of_messages = Messages::find()
-        .filter(entity::messages::Column::Timestamp.gt(last_week))
-        .all(db)
-        .await?;
-
-    // use last year of messages
-    let last_year_ranking = users
-        .iter()
-        .map(|u| {
-            (
-                u.snowflake,
-                last_year_of_messages
-                    .iter()
-                    .filter(|m| m.user == u.snowflake)
-                    .map(|m| m.score)
-                    .sum::<f32>(),
-            )
-        })
-        .collect::<HashMap<i64, f32>>();
-
-    users.sort_by(|a, b| {
-        last_year_ranking
-            .get(&b.snowflake)
-            .unwrap()
-            .partial_cmp(last_year_ranking.get(&a.snowflake).unwrap())
-            .unwrap()
-    });
+    let most_reacted = messages.iter().max_by_key(|m| m.reaction_count);
 
     msg.set(
-        "Rank - year",
-        Some(
-            (users
-                .iter()
-                .position(|u| u.snowflake == user.snowflake)
-                .unwrap()
-                + 1)
-            .to_string(),
-        ),
+        "Most reacted message",
+        match most_reacted {
+            Some(most_reacted) if most_reacted.reaction_count > 0 => format!(
+                "https://discord.com/channels/{}/{}/{} - {} reactions",
+                guild_id.get(),
+                most_reacted.channel,
+                most_reacted.snowflake,
+                most_reacted.reaction_count
+            ),
+            _ => "None".to_string(),
+        },
     )
     .await?;
 
-    let channels = Channels::find()
-        .filter(entity::channels::Column::Guild.eq(ctx.guild_id().unwrap().get() as i64))
-        .order_by_desc(entity::channels::Column::Score)
-        .all(db)
-        .await?;
-
-    let mut highest_score_channel: Option<(&entity::channels::Model, f32)> = None;
-    let mut week_highest_score_channel: Option<(&entity::channels::Model, f32)> = None;
-    let mut month_highest_score_channel: Option<(&entity::channels::Model, f32)> = None;
-    let mut year_highest_score_channel: Option<(&entity::channels::Model, f32)> = None;
-
-    for channel in channels.iter() {
-        let messages = Messages::find()
-            .filter(entity::messages::Column::User.eq(user.snowflake))
-            .filter(entity::messages::Column::Channel.eq(channel.snowflake))
-            .all(db)
-            .await?;
-
-        let score = messages.iter().map(|m| m.score).sum::<f32>();
-
-        if highest_score_channel.is_none() || score > highest_score_channel.unwrap().1 {
-            highest_score_channel = Some((channel, score));
-        }
-
-        let last_week_of_messages = Messages::find()
-            .filter(entity::messages::Column::User.eq(user.snowflake))
-            .filter(entity::messages::Column::Channel.eq(channel.snowflake))
-            .filter(entity::messages::Column::Timestamp.gt(last_week))
-            .all(db)
-            .await?;
-
-        let score = last_week_of_messages.iter().map(|m| m.score).sum::<f32>();
-
-        if week_highest_score_channel.is_none() || score > week_highest_score_channel.unwrap().1 {
-            week_highest_score_channel = Some((channel, score));
-        }
-
-        let last_month_of_messages = Messages::find()
-            .filter(entity::messages::Column::User.eq(user.snowflake))
-            .filter(entity::messages::Column::Channel.eq(channel.snowflake))
-            .filter(entity::messages::Column::Timestamp.gt(last_month))
-            .all(db)
-            .await?;
-
-        let score = last_month_of_messages.iter().map(|m| m.score).sum::<f32>();
-
-        if month_highest_score_channel.is_none() || score > month_highest_score_channel.unwrap().1 {
-            month_highest_score_channel = Some((channel, score));
-        }
-
-        let last_year_of_messages = Messages::find()
-            .filter(entity::messages::Column::User.eq(user.snowflake))
-            .filter(entity::messages::Column::Channel.eq(channel.snowflake))
-            .filter(entity::messages::Column::Timestamp.gt(last_year))
-            .all(db)
-            .await?;
-
-        let score = last_year_of_messages.iter().map(|m| m.score).sum::<f32>();
-
-        if year_highest_score_channel.is_none() || score > year_highest_score_channel.unwrap().1 {
-            year_highest_score_channel = Some((channel, score));
-        }
-    }
-
-    if let Some((channel, score)) = highest_score_channel {
-        msg.set(
-            "Best Channel",
-            Some(format!(
-                "{} - {}",
-                ctx.http()
-                    .get_channel(ChannelId::new(channel.snowflake as u64))
-                    .await?
-                    .mention(),
-                score
-            )),
-        )
-        .await?;
-    } else {
-        msg.set("Best Channel", Some("None")).await?;
-    }
-
-    if let Some((channel, score)) = week_highest_score_channel {
-        msg.set(
-            "Best Channel - week",
-            Some(format!(
-                "{} - {}",
-                ctx.http()
-                    .get_channel(ChannelId::new(channel.snowflake as u64))
-                    .await?
-                    .mention(),
-                score
-            )),
-        )
-        .await?;
-    } else {
-        msg.set("Best Channel - week", Some("None")).await?;
-    }
-
-    if let Some((channel, score)) = month_highest_score_channel {
-        msg.set(
-            "Best Channel - month",
-            Some(format!(
-                "{} - {}",
-                ctx.http()
-                    .get_channel(ChannelId::new(channel.snowflake as u64))
-                    .await?
-                    .mention(),
-                score
-            )),
-        )
-        .await?;
-    } else {
-        msg.set("Best Channel - month", Some("None")).await?;
-    }
-
-    if let Some((channel, score)) = year_highest_score_channel {
-        msg.set(
-            "Best Channel - year",
-            Some(format!(
-                "{} - {}",
-                ctx.http()
-                    .get_channel(ChannelId::new(channel.snowflake as u64))
+    let now = chrono::Utc::now().naive_utc();
+    let best_channel = |since: Option<NaiveDateTime>| best_channel(repo.as_ref(), &user, since);
+
+    msg.compute(vec![
+        (
+            "Voice time".to_string(),
+            async {
+                Ok(
+                    match repo
+                        .voice_totals(guild_id.get(), Some(user.snowflake), 0, 1)
+                        .await?
+                        .first()
+                    {
+                        Some((_, seconds, score)) => {
+                            format!("{} - {:.0} points", format_voice_time(*seconds), score)
+                        }
+                        None => "None".to_string(),
+                    },
+                )
+            }
+            .boxed(),
+        ),
+        (
+            "Rank".to_string(),
+            async { Ok((repo.users_above(guild_id.get(), user.score).await? + 1).to_string()) }
+                .boxed(),
+        ),
+        ("Best Channel".to_string(), best_channel(None).boxed()),
+        (
+            "Best Channel - week".to_string(),
+            best_channel(Some(now - chrono::Duration::try_weeks(1).unwrap())).boxed(),
+        ),
+        (
+            "Best Channel - month".to_string(),
+            best_channel(Some(now - chrono::Duration::try_days(30).unwrap())).boxed(),
+        ),
+        (
+            "Best Channel - year".to_string(),
+            best_channel(Some(now - chrono::Duration::try_days(365).unwrap())).boxed(),
+        ),
+        (
+            "Average score for messages - rank".to_string(),
+            async {
+                // ranked from the daily rollups, users with only voice time or imported levels
+                // have no messages to average and are left out
+                let mut ranking = repo
+                    .user_totals_since(guild_id.get(), NaiveDate::MIN)
                     .await?
-                    .mention(),
-                score
-            )),
-        )
-        .await?;
-    } else {
-        msg.set("Best Channel - year", Some("None")).await?;
-    }
-
-    // average score for messages
-
-    let messages = Messages::find()
-        .filter(entity::messages::Column::User.eq(user.snowflake))
-        .all(db)
-        .await?;
-
-    let score = messages.iter().map(|m| m.score).sum::<f32>() / messages.len() as f32;
-
-    msg.set("Average score for messages", Some(format!("{:.2}", score)))
-        .await?;
-
-    let mut users = Users::find()
-        .order_by_desc(entity::users::Column::Score)
-        .all(db)
-        .await?;
-
-    let mut ranking = HashMap::new();
-
-    for user in users.iter() {
-        let messages = Messages::find()
-            .filter(entity::messages::Column::User.eq(user.snowflake))
-            .all(db)
-            .await?;
-
-        let score = messages.iter().map(|m| m.score).sum::<f32>() / messages.len() as f32;
-
-        ranking.insert(user.snowflake, score);
-    }
-
-    users.sort_by(|a, b| {
-        ranking
-            .get(&b.snowflake)
-            .unwrap()
-            .partial_cmp(ranking.get(&a.snowflake).unwrap())
-            .unwrap()
-    });
-
-    msg.set(
-        "Average score for messages - rank",
-        Some(
-            users
-                .iter()
-                .position(|u| u.snowflake == user.snowflake)
-                .unwrap()
-                + 1,
+                    .into_values()
+                    .filter(|total| total.messages > 0)
+                    .map(|total| (total.user, total.score / total.messages as f32))
+                    .collect::<Vec<_>>();
+                ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+                Ok(
+                    match ranking.iter().position(|(u, _)| *u == user.snowflake) {
+                        Some(rank) => (rank + 1).to_string(),
+                        None => "None".to_string(),
+                    },
+                )
+            }
+            .boxed(),
         ),
-    )
+        (
+            "Favorite emoji".to_string(),
+            async {
+                Ok(match repo.favorite_emoji(user.snowflake).await? {
+                    Some((emoji, uses)) => format!("{} - {} times", emoji, uses),
+                    None => "None".to_string(),
+                })
+            }
+            .boxed(),
+        ),
+        (
+            "Reactions given".to_string(),
+            async { Ok(repo.reactions_given(user.snowflake).await?.to_string()) }.boxed(),
+        ),
+        (
+            "Reactions received".to_string(),
+            async { Ok(repo.reactions_received(user.snowflake).await?.to_string()) }.boxed(),
+        ),
+        (
+            "3 most common uncommon words".to_string(),
+            uncommon_words(ctx, guild_id.get(), user.snowflake).boxed(),
+        ),
+    ])
     .await?;
 
-    // 'best' message
-
-    let best_message = messages
-        .iter()
-        .max_by(|a, b| a.score.partial_cmp(&b.score).unwrap())
-        .unwrap();
+    msg.finish().await?;
 
-    msg.set(
-        "'Best' message",
-        Some(format!(
-            "https://discord.com/channels/{}/{}/{} - {}",
-            guild_id.get(),
-            best_message.channel,
-            best_message.snowflake,
-            best_message.score
-        )),
-    )
-    .await?;
+    Ok(())
+}
 
-    let average_post_length =
-        messages.iter().map(|m| m.content.len()).sum::<usize>() / messages.len();
+/// The channel a user scored the most in, optionally only counting messages after `since`
+async fn best_channel(
+    repo: &dyn Repository,
+    user: &rank_core::model::User,
+    since: Option<NaiveDateTime>,
+) -> Result<String, Error> {
+    let mut channels = HashMap::new();
+    for message in repo.user_messages(user.snowflake, None, since).await? {
+        *channels.entry(message.channel).or_insert(0.0) += message.score;
+    }
 
-    msg.set(
-        "Average post length",
-        Some(format!("{}", average_post_length)),
+    Ok(
+        match channels
+            .into_iter()
+            .max_by(|a: &(i64, f32), b| a.1.total_cmp(&b.1))
+        {
+            Some((channel, score)) => {
+                format!("{} - {}", ChannelId::new(channel as u64).mention(), score)
+            }
+            None => "None".to_string(),
+        },
     )
-    .await?;
+}
 
+/// The three longest uncommon words a user uses most
+async fn uncommon_words(ctx: Context<'_>, guild: u64, user: i64) -> Result<String, Error> {
+    // token counts are stored at ingest so this works after the content has been pruned
+    let tokens = ctx.data().repo.user_tokens(user).await?;
+
+    let common_words = guild_common_words(ctx.data(), guild).await?;
     let mut words = HashMap::new();
 
-    for message in messages.iter() {
-        for word in message.content.split(' ') {
-            if word.len() > 8
-                && !word.starts_with("<@")
-                && !word.ends_with('>')
-                && !ctx.data().common_words.contains(word)
-            {
-                let word = word.to_lowercase();
-                *words.entry(word).or_insert(0) += 1;
-            }
+    for (word, count) in tokens.iter() {
+        if word.len() > 8 && !common_words.contains(word) {
+            *words.entry(word).or_insert(0) += *count as usize;
         }
     }
 
-    let mut words = words.into_iter().collect::<Vec<(String, usize)>>();
+    let mut words = words.into_iter().collect::<Vec<(&String, usize)>>();
 
-    words.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    words.sort_by_key(|w| std::cmp::Reverse(w.1));
 
-    msg.set(
-        "3 most common uncommon words",
-        Some(format!(
-            "{} - {} times\n{} - {} times\n{} - {} times",
-            words[0].0, words[0].1, words[1].0, words[1].1, words[2].0, words[2].1,
-        )),
-    )
-    .await?;
-
-    Ok(())
+    Ok(if words.is_empty() {
+        "Not enough words yet".to_string()
+    } else {
+        words
+            .iter()
+            .take(3)
+            .map(|(word, count)| format!("{} - {} times", word, count))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
 }
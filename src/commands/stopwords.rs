@@ -0,0 +1,208 @@
+use crate::common_words::{guild_languages, invalidate, Language};
+use crate::{guild_settings, Context, Error};
+use poise::{ChoiceParameter, CreateReply};
+use rank_core::tokenizer::normalize_word;
+use serenity::builder::CreateEmbed;
+
+/// Configure the common words that are left out of word stats
+#[poise::command(
+    slash_command,
+    guild_only,
+    check = "crate::permissions::bot_manager",
+    subcommands("add", "remove", "reset", "language", "list")
+)]
+pub async fn stopwords(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+async fn reply(ctx: Context<'_>, title: &str, description: String, ok: bool) -> Result<(), Error> {
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title(title)
+                .description(description)
+                .colour(if ok { 0x00ff00 } else { 0xff0000 }),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn set_stopword(ctx: Context<'_>, word: &str, removed: bool) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let word = match normalize_word(word) {
+        Some(word) => word,
+        None => {
+            return reply(
+                ctx,
+                "Invalid word",
+                format!("`{}` is not a word", word),
+                false,
+            )
+            .await
+        }
+    };
+
+    ctx.data()
+        .repo
+        .set_stopword(guild_id, &word, removed)
+        .await?;
+
+    invalidate(ctx.data(), guild_id).await;
+
+    reply(
+        ctx,
+        "Stopwords updated",
+        if removed {
+            format!("`{}` now counts in word stats", word)
+        } else {
+            format!("`{}` is now left out of word stats", word)
+        },
+        true,
+    )
+    .await
+}
+
+/// Leave a word out of word stats
+#[poise::command(slash_command, guild_only)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Word to leave out"] word: String,
+) -> Result<(), Error> {
+    set_stopword(ctx, &word, false).await
+}
+
+/// Count a word from the built in lists in word stats
+#[poise::command(slash_command, guild_only)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Word to count"] word: String,
+) -> Result<(), Error> {
+    set_stopword(ctx, &word, true).await
+}
+
+/// Undo an addition or removal
+#[poise::command(slash_command, guild_only)]
+pub async fn reset(
+    ctx: Context<'_>,
+    #[description = "Word to reset"] word: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+    let word = normalize_word(&word).unwrap_or(word);
+
+    let deleted = ctx.data().repo.delete_stopword(guild_id, &word).await?;
+
+    invalidate(ctx.data(), guild_id).await;
+
+    if !deleted {
+        reply(
+            ctx,
+            "Word not found",
+            format!("`{}` was not added or removed", word),
+            false,
+        )
+        .await
+    } else {
+        reply(
+            ctx,
+            "Stopwords updated",
+            format!("`{}` follows the built in lists again", word),
+            true,
+        )
+        .await
+    }
+}
+
+/// Use or stop using a language's list of common words
+#[poise::command(slash_command, guild_only)]
+pub async fn language(
+    ctx: Context<'_>,
+    #[description = "Language"] language: Language,
+    #[description = "Use this language's list (default on)"] enabled: Option<bool>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let mut settings = guild_settings::get(ctx.data(), guild_id).await?;
+    let mut languages = guild_languages(&settings);
+
+    languages.retain(|l| *l != language);
+    if enabled.unwrap_or(true) {
+        languages.push(language);
+    }
+
+    settings.stopword_languages = Some(
+        languages
+            .iter()
+            .map(|l| l.code())
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+    guild_settings::save(ctx.data(), settings).await?;
+    invalidate(ctx.data(), guild_id).await;
+
+    reply(
+        ctx,
+        "Stopwords updated",
+        format!(
+            "Using the common words of: {}",
+            if languages.is_empty() {
+                "no languages".to_string()
+            } else {
+                languages
+                    .iter()
+                    .map(|l| l.name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        ),
+        true,
+    )
+    .await
+}
+
+/// Show this server's languages and stopword changes
+#[poise::command(slash_command, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let settings = guild_settings::get(ctx.data(), guild_id).await?;
+    let stopwords = ctx.data().repo.stopwords(guild_id).await?;
+
+    let words = |removed: bool| {
+        let words = stopwords
+            .iter()
+            .filter(|s| s.removed == removed)
+            .map(|s| format!("`{}`", s.word))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if words.is_empty() {
+            "None".to_string()
+        } else {
+            // embed fields are limited to 1024 characters
+            words.chars().take(1000).collect()
+        }
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Stopwords")
+                .field(
+                    "Languages",
+                    guild_languages(&settings)
+                        .iter()
+                        .map(|l| format!("{} ({} words)", l.name(), l.word_count()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    false,
+                )
+                .field("Added", words(false), false)
+                .field("Removed", words(true), false)
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
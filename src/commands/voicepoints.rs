@@ -0,0 +1,36 @@
+use crate::{guild_settings, Context, Error};
+use poise::CreateReply;
+use serenity::builder::CreateEmbed;
+
+/// Configure how many points members earn per active minute in voice
+#[poise::command(slash_command, guild_only, check = "crate::permissions::bot_manager")]
+pub async fn voicepoints(
+    ctx: Context<'_>,
+    #[description = "Points per active minute (0 = voice doesn't score)"]
+    #[min = 0]
+    points: Option<f32>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap().get();
+
+    let mut settings = guild_settings::get(ctx.data(), guild_id).await?;
+
+    if let Some(points) = points {
+        settings.voice_points_per_minute = points.max(0.0);
+        guild_settings::save(ctx.data(), settings.clone()).await?;
+    }
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .title("Voice points")
+                .description(format!(
+                    "Members earn {} points per minute in voice while unmuted, undeafened, outside the AFK channel and not alone",
+                    settings.voice_points_per_minute
+                ))
+                .colour(0x00ff00),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
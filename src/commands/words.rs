@@ -0,0 +1,180 @@
+use crate::common_words::guild_common_words;
+use crate::word_stats::{distinctive_words, top_bigrams, top_words, MIN_USES};
+use crate::{Context, Error};
+use chrono::{Duration, Utc};
+use poise::CreateReply;
+use rank_core::tokenizer::normalize_word;
+use rank_core::words::WordScope;
+use serenity::builder::CreateEmbed;
+use serenity::model::channel::GuildChannel;
+use serenity::model::prelude::User;
+
+fn list(entries: Vec<String>, empty: &str) -> String {
+    if entries.is_empty() {
+        empty.to_string()
+    } else {
+        entries.join("\n")
+    }
+}
+
+/// Show the most used words and phrases
+#[poise::command(slash_command, guild_only)]
+pub async fn words(
+    ctx: Context<'_>,
+    #[description = "User (default: the whole server)"] user: Option<User>,
+    #[description = "Channel (default: all channels)"] channel: Option<GuildChannel>,
+    #[description = "Only count the last this many days (default: all time)"]
+    #[max = 36500]
+    days: Option<u32>,
+    #[description = "Number of words to show (default 10, max 25)"] count: Option<usize>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let repo = ctx.data().repo.as_ref();
+    let guild_id = ctx.guild_id().unwrap();
+    let count = count.unwrap_or(10).clamp(1, 25);
+
+    let scope = WordScope {
+        user: user.as_ref().map(|u| u.id.get() as i64),
+        channel: channel.as_ref().map(|c| c.id.get() as i64),
+        since: days
+            .and_then(|days| Utc::now().checked_sub_signed(Duration::days(days as i64)))
+            .map(|since| since.naive_utc()),
+        ..WordScope::guild(guild_id.get())
+    };
+
+    let common_words = guild_common_words(ctx.data(), guild_id.get()).await?;
+
+    let words = top_words(repo, &scope, &common_words, count).await?;
+    let bigrams = top_bigrams(repo, &scope, &common_words, count).await?;
+
+    let mut title = match user {
+        Some(ref user) => format!("Words of {}", user.name),
+        None => "Server words".to_string(),
+    };
+    if let Some(ref channel) = channel {
+        title.push_str(&format!(" in #{}", channel.name));
+    }
+    if let Some(days) = days {
+        title.push_str(&format!(" over the last {} days", days));
+    }
+
+    let mut embed = CreateEmbed::default()
+        .title(title)
+        .field(
+            "Top words",
+            list(
+                words
+                    .iter()
+                    .map(|(word, uses)| format!("{} - {} times", word, uses))
+                    .collect(),
+                "Not enough words yet",
+            ),
+            true,
+        )
+        .field(
+            "Top phrases",
+            list(
+                bigrams
+                    .iter()
+                    .map(|(bigram, uses)| format!("{} - {} times", bigram, uses))
+                    .collect(),
+                "Not enough phrases yet",
+            ),
+            true,
+        );
+
+    if scope.user.is_some() {
+        let distinctive = distinctive_words(repo, &scope, &common_words, count).await?;
+        embed = embed.field(
+            "Used more than the server average",
+            list(
+                distinctive
+                    .iter()
+                    .map(|(word, score)| format!("{} - {:.1}", word, score))
+                    .collect(),
+                &format!(
+                    "Not enough words yet, words count once used {} times",
+                    MIN_USES
+                ),
+            ),
+            false,
+        );
+    }
+
+    ctx.send(CreateReply::default().embed(embed.colour(0x00ff00)))
+        .await?;
+
+    Ok(())
+}
+
+/// Show how often a word is used and by whom
+#[poise::command(slash_command, guild_only)]
+pub async fn wordcount(
+    ctx: Context<'_>,
+    #[description = "Word to count"] word: String,
+    #[description = "User (default: the whole server)"] user: Option<User>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+    let guild_id = ctx.guild_id().unwrap();
+
+    let normalized = match normalize_word(word.trim()) {
+        Some(normalized) if !normalized.contains(char::is_whitespace) => normalized,
+        _ => {
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .title("Invalid word")
+                        .description(format!("`{}` is not a single word", word))
+                        .colour(0xff0000),
+                ),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let scope = WordScope {
+        user: user.as_ref().map(|u| u.id.get() as i64),
+        ..WordScope::guild(guild_id.get())
+    };
+
+    let counts = ctx.data().repo.word_count(&scope, &normalized).await?;
+
+    let title = match user {
+        Some(ref user) => format!("\"{}\" said by {}", normalized, user.name),
+        None => format!("\"{}\"", normalized),
+    };
+
+    let mut embed = CreateEmbed::default()
+        .title(title)
+        .field("Times used", counts.uses.to_string(), true)
+        .field("Messages", counts.messages.to_string(), true)
+        .field(
+            "First used",
+            counts
+                .first_used
+                .map(|t| format!("<t:{}:D>", t.and_utc().timestamp()))
+                .unwrap_or("Never".to_string()),
+            true,
+        );
+
+    if user.is_none() {
+        embed = embed.field(
+            "Used most by",
+            list(
+                counts
+                    .top_users
+                    .iter()
+                    .map(|(user, uses)| format!("<@{}> - {} times", user, uses))
+                    .collect(),
+                "Nobody yet",
+            ),
+            false,
+        );
+    }
+
+    ctx.send(CreateReply::default().embed(embed.colour(0x00ff00)))
+        .await?;
+
+    Ok(())
+}
@@ -1,7 +1,44 @@
+use crate::{guild_settings, Data, Error};
 use std::collections::HashSet;
+use std::sync::Arc;
 
 const WORDS: &str = include_str!("../common_words.txt");
 
+/// Languages with a built in list of common words. Lists are added once there is a frequency
+/// list of comparable size, a short one would leave most everyday words in the word stats.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum Language {
+    English,
+}
+
+impl Language {
+    pub const ALL: [Language; 1] = [Language::English];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL.into_iter().find(|l| l.code() == code.trim())
+    }
+
+    fn words(&self) -> &'static str {
+        match self {
+            Language::English => WORDS,
+        }
+    }
+
+    /// How many words the list has
+    pub fn word_count(&self) -> usize {
+        self.words()
+            .lines()
+            .filter(|w| !w.trim().is_empty())
+            .count()
+    }
+}
+
 pub fn get_common_words() -> HashSet<String> {
     WORDS
         .lines()
@@ -9,3 +46,149 @@ pub fn get_common_words() -> HashSet<String> {
         .collect::<HashSet<String>>()
 }
 
+/// The languages whose common words a guild uses, English unless it picked others
+pub fn guild_languages(settings: &rank_core::model::GuildSettings) -> Vec<Language> {
+    match settings.stopword_languages {
+        Some(ref codes) => codes.split(',').filter_map(Language::from_code).collect(),
+        None => vec![Language::English],
+    }
+}
+
+/// The common words of a guild: the lists of its languages with its own additions and
+/// removals applied. Cached in `data` until the guild changes its stopwords.
+pub async fn guild_common_words(data: &Data, guild: u64) -> Result<Arc<HashSet<String>>, Error> {
+    if let Some(words) = data.guild_common_words.read().await.get(&guild) {
+        return Ok(words.clone());
+    }
+
+    let settings = guild_settings::get(data, guild).await?;
+    let languages = guild_languages(&settings);
+
+    let mut words = if languages == [Language::English] {
+        (*data.common_words).clone()
+    } else {
+        languages
+            .iter()
+            .flat_map(|l| l.words().lines())
+            .map(|s| s.to_string())
+            .collect::<HashSet<String>>()
+    };
+
+    for stopword in data.repo.stopwords(guild).await? {
+        if stopword.removed {
+            words.remove(&stopword.word);
+        } else {
+            words.insert(stopword.word);
+        }
+    }
+
+    let words = Arc::new(words);
+    data.guild_common_words
+        .write()
+        .await
+        .insert(guild, words.clone());
+
+    Ok(words)
+}
+
+/// Forget the cached common words of a guild after its stopwords changed
+pub async fn invalidate(data: &Data, guild: u64) {
+    data.guild_common_words.write().await.remove(&guild);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rank_core::model::GuildSettings;
+    use rank_core::repository::MemoryRepository;
+
+    const GUILD: u64 = 1;
+
+    async fn empty_data() -> Data {
+        Data::load(Arc::new(MemoryRepository::new()), None)
+            .await
+            .unwrap()
+    }
+
+    fn settings(stopword_languages: Option<&str>) -> GuildSettings {
+        GuildSettings {
+            guild: GUILD as i64,
+            retention_days: None,
+            retention_truncate: None,
+            voice_points_per_minute: 5.0,
+            scoring: None,
+            stopword_languages: stopword_languages.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn english_unless_the_guild_picked_otherwise() {
+        assert_eq!(guild_languages(&settings(None)), [Language::English]);
+        assert!(guild_languages(&settings(Some(""))).is_empty());
+        // languages that are no longer offered are skipped
+        assert_eq!(
+            guild_languages(&settings(Some("de, en"))),
+            [Language::English]
+        );
+        assert!(Language::English.word_count() >= 10_000);
+    }
+
+    #[tokio::test]
+    async fn applies_the_guilds_additions_and_removals() {
+        let data = empty_data().await;
+        data.repo
+            .set_stopword(GUILD, "rustacean", false)
+            .await
+            .unwrap();
+        data.repo.set_stopword(GUILD, "the", true).await.unwrap();
+
+        let words = guild_common_words(&data, GUILD).await.unwrap();
+        assert!(words.contains("rustacean"));
+        assert!(!words.contains("the"));
+        assert!(words.contains("and"));
+        // other guilds keep the built in list
+        assert!(guild_common_words(&data, GUILD + 1)
+            .await
+            .unwrap()
+            .contains("the"));
+    }
+
+    #[tokio::test]
+    async fn without_languages_only_additions_are_common() {
+        let data = empty_data().await;
+        guild_settings::save(&data, settings(Some("")))
+            .await
+            .unwrap();
+        data.repo
+            .set_stopword(GUILD, "rustacean", false)
+            .await
+            .unwrap();
+
+        let words = guild_common_words(&data, GUILD).await.unwrap();
+        assert_eq!(*words, HashSet::from(["rustacean".to_string()]));
+    }
+
+    #[tokio::test]
+    async fn cached_until_invalidated() {
+        let data = empty_data().await;
+        assert!(!guild_common_words(&data, GUILD)
+            .await
+            .unwrap()
+            .contains("rustacean"));
+
+        data.repo
+            .set_stopword(GUILD, "rustacean", false)
+            .await
+            .unwrap();
+        assert!(!guild_common_words(&data, GUILD)
+            .await
+            .unwrap()
+            .contains("rustacean"));
+
+        invalidate(&data, GUILD).await;
+        assert!(guild_common_words(&data, GUILD)
+            .await
+            .unwrap()
+            .contains("rustacean"));
+    }
+}
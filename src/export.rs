@@ -0,0 +1,364 @@
+use crate::Error;
+use chrono::{NaiveDate, NaiveDateTime};
+use rank_core::repository::Repository;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// How many messages are read from the database at a time while exporting
+const EXPORT_PAGE_SIZE: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter, clap::ValueEnum)]
+pub enum ExportFormat {
+    #[name = "CSV"]
+    Csv,
+    #[name = "JSON lines"]
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "ndjson",
+        }
+    }
+}
+
+/// What to export for a guild
+pub struct ExportOptions {
+    pub guild: u64,
+    pub format: ExportFormat,
+    /// first day (inclusive) of messages to export
+    pub from: Option<NaiveDate>,
+    /// last day (inclusive) of messages to export
+    pub to: Option<NaiveDate>,
+    pub include_content: bool,
+}
+
+/// The files written by an export and how many rows each holds
+pub struct ExportSummary {
+    pub files: Vec<PathBuf>,
+    pub users: usize,
+    pub channels: usize,
+    pub messages: usize,
+}
+
+#[derive(Serialize)]
+struct UserRow {
+    id: i64,
+    name: String,
+    score: f32,
+    message_count: i32,
+}
+
+#[derive(Serialize)]
+struct ChannelRow {
+    id: i64,
+    name: String,
+    score: f32,
+    message_count: i32,
+}
+
+#[derive(Serialize)]
+struct MessageRow {
+    id: i64,
+    channel: i64,
+    user: i64,
+    replys_to: Option<i64>,
+    timestamp: NaiveDateTime,
+    score: f32,
+    length: i32,
+    word_count: i32,
+    content: Option<String>,
+}
+
+/// Writes rows as CSV or as newline delimited JSON
+enum RowWriter<W: Write> {
+    Csv(Box<csv::Writer<W>>),
+    Json(W),
+}
+
+impl<W: Write> RowWriter<W> {
+    fn new(format: ExportFormat, writer: W) -> Self {
+        match format {
+            ExportFormat::Csv => RowWriter::Csv(Box::new(csv::Writer::from_writer(writer))),
+            ExportFormat::Json => RowWriter::Json(writer),
+        }
+    }
+
+    fn write<T: Serialize>(&mut self, row: &T) -> Result<(), Error> {
+        match self {
+            RowWriter::Csv(writer) => writer.serialize(row)?,
+            RowWriter::Json(writer) => {
+                serde_json::to_writer(&mut *writer, row)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        match self {
+            RowWriter::Csv(mut writer) => writer.flush()?,
+            RowWriter::Json(mut writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
+
+fn create_writer(
+    dir: &Path,
+    name: &str,
+    format: ExportFormat,
+) -> Result<(PathBuf, RowWriter<BufWriter<File>>), Error> {
+    let path = dir.join(format!("{}.{}", name, format.extension()));
+    let file = BufWriter::new(File::create(&path)?);
+    Ok((path, RowWriter::new(format, file)))
+}
+
+/// Export the users, channels and messages of a guild into `dir`, one file each.
+/// Messages are streamed from the database page by page so large guilds do not have to fit in memory.
+pub async fn export_guild(
+    repo: &dyn Repository,
+    options: &ExportOptions,
+    dir: &Path,
+) -> Result<ExportSummary, Error> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut summary = ExportSummary {
+        files: Vec::new(),
+        users: 0,
+        channels: 0,
+        messages: 0,
+    };
+
+    let mut users = repo.users_by_score(options.guild, 0, None).await?;
+    users.sort_by_key(|user| user.snowflake);
+
+    let (path, mut writer) = create_writer(dir, "users", options.format)?;
+    for user in users {
+        writer.write(&UserRow {
+            id: user.snowflake,
+            name: user.name,
+            score: user.score,
+            message_count: user.message_count,
+        })?;
+        summary.users += 1;
+    }
+    writer.finish()?;
+    summary.files.push(path);
+
+    let mut channels = repo.guild_channels(options.guild).await?;
+    channels.sort_by_key(|channel| channel.snowflake);
+
+    let (path, mut writer) = create_writer(dir, "channels", options.format)?;
+    for channel in channels {
+        writer.write(&ChannelRow {
+            id: channel.snowflake,
+            name: channel.name,
+            score: channel.score,
+            message_count: channel.message_count,
+        })?;
+        summary.channels += 1;
+    }
+    writer.finish()?;
+    summary.files.push(path);
+
+    let from = options.from.map(|from| from.and_hms_opt(0, 0, 0).unwrap());
+    let until = options
+        .to
+        .map(|to| to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).unwrap());
+
+    let (path, mut writer) = create_writer(dir, "messages", options.format)?;
+    let mut after = None;
+    loop {
+        let messages = repo
+            .guild_messages(options.guild, from, until, after, EXPORT_PAGE_SIZE)
+            .await?;
+        after = match messages.last() {
+            Some(last) => Some(last.snowflake),
+            None => break,
+        };
+        for message in messages {
+            writer.write(&MessageRow {
+                id: message.snowflake,
+                channel: message.channel,
+                user: message.user,
+                replys_to: message.replys_to,
+                timestamp: message.timestamp,
+                score: message.score,
+                length: message.length,
+                word_count: message.word_count,
+                content: if options.include_content {
+                    message.content
+                } else {
+                    None
+                },
+            })?;
+            summary.messages += 1;
+        }
+    }
+    writer.finish()?;
+    summary.files.push(path);
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rank_core::message::Message;
+    use rank_core::repository::MemoryRepository;
+
+    const GUILD: u64 = 1;
+
+    async fn guild_repo() -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, GUILD, "general".to_string())
+            .await
+            .unwrap();
+        repo.insert_user(100, GUILD, "alice".to_string())
+            .await
+            .unwrap();
+        repo
+    }
+
+    async fn store(repo: &MemoryRepository, id: u64, sent: NaiveDateTime, replys_to: Option<i64>) {
+        let message = Message {
+            id,
+            author: 100,
+            channel: 10,
+            content: format!("message {}", id),
+            timestamp: sent,
+            attachments: vec![],
+            mention_count: 0,
+        };
+        repo.store_message(
+            &message,
+            GUILD,
+            Some(message.content.clone()),
+            1.0,
+            replys_to,
+        )
+        .await
+        .unwrap();
+    }
+
+    fn options(format: ExportFormat, include_content: bool) -> ExportOptions {
+        ExportOptions {
+            guild: GUILD,
+            format,
+            from: None,
+            to: None,
+            include_content,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rank_export_{}_{}", name, std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn json_lines_hold_one_message_each() {
+        let repo = guild_repo().await;
+        let sent = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        store(&repo, 1000, sent, None).await;
+        store(&repo, 1001, sent, Some(1000)).await;
+        let dir = temp_dir("json");
+
+        let summary = export_guild(&repo, &options(ExportFormat::Json, true), &dir)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.files[2], dir.join("messages.ndjson"));
+        let messages = std::fs::read_to_string(dir.join("messages.ndjson")).unwrap();
+        let rows: Vec<serde_json::Value> = messages
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["replys_to"], 1000);
+        assert_eq!(rows[1]["content"], "message 1001");
+        assert_eq!(rows[1]["timestamp"], "2024-03-01T12:00:00");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn the_date_range_includes_its_last_day() {
+        let repo = guild_repo().await;
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        store(
+            &repo,
+            1000,
+            day.and_hms_opt(0, 0, 0).unwrap() - chrono::Duration::seconds(1),
+            None,
+        )
+        .await;
+        store(&repo, 1001, day.and_hms_opt(0, 0, 0).unwrap(), None).await;
+        store(&repo, 1002, day.and_hms_opt(23, 59, 59).unwrap(), None).await;
+        store(
+            &repo,
+            1003,
+            day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap(),
+            None,
+        )
+        .await;
+        let dir = temp_dir("range");
+
+        let summary = export_guild(
+            &repo,
+            &ExportOptions {
+                from: Some(day),
+                to: Some(day),
+                ..options(ExportFormat::Csv, false)
+            },
+            &dir,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(summary.messages, 2);
+        let mut reader = csv::Reader::from_path(dir.join("messages.csv")).unwrap();
+        let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+        assert_eq!(&rows[0][0], "1001");
+        assert_eq!(&rows[1][0], "1002");
+        // without content the column stays, empty
+        assert_eq!(reader.headers().unwrap().get(8), Some("content"));
+        assert!(rows.iter().all(|row| row[8].is_empty()));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn messages_are_read_page_by_page() {
+        let repo = guild_repo().await;
+        let sent = chrono::Utc::now().naive_utc();
+        let count = EXPORT_PAGE_SIZE + 5;
+        for id in 0..count {
+            store(&repo, 1000 + id, sent, None).await;
+        }
+        let dir = temp_dir("pages");
+
+        let summary = export_guild(&repo, &options(ExportFormat::Json, false), &dir)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.messages as u64, count);
+        let messages = std::fs::read_to_string(dir.join("messages.ndjson")).unwrap();
+        let ids: Vec<u64> = messages
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()["id"]
+                    .as_u64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ids, (1000..1000 + count).collect::<Vec<_>>());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Data, Error};
+use rank_core::model::GuildSettings;
+
+/// Points for each active minute in voice, unless the guild sets its own
+pub const DEFAULT_VOICE_POINTS_PER_MINUTE: f32 = 5.0;
+
+/// Settings for a guild that has never configured anything
+fn default_settings(guild: u64) -> GuildSettings {
+    GuildSettings {
+        guild: guild as i64,
+        retention_days: None,
+        retention_truncate: None,
+        voice_points_per_minute: DEFAULT_VOICE_POINTS_PER_MINUTE,
+        scoring: None,
+        stopword_languages: None,
+    }
+}
+
+/// Get the settings of a guild, reading through the cache in `data`
+pub async fn get(data: &Data, guild: u64) -> Result<GuildSettings, Error> {
+    if let Some(settings) = data.guild_settings.read().await.get(&guild) {
+        return Ok(settings.clone());
+    }
+
+    let settings = data
+        .repo
+        .guild_settings(guild)
+        .await?
+        .unwrap_or_else(|| default_settings(guild));
+
+    data.guild_settings
+        .write()
+        .await
+        .insert(guild, settings.clone());
+
+    Ok(settings)
+}
+
+/// Store the settings of a guild and update the cache
+pub async fn save(data: &Data, settings: GuildSettings) -> Result<(), Error> {
+    data.repo.save_guild_settings(settings.clone()).await?;
+
+    data.guild_settings
+        .write()
+        .await
+        .insert(settings.guild as u64, settings);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rank_core::repository::MemoryRepository;
+    use std::sync::Arc;
+
+    const GUILD: u64 = 1;
+
+    #[tokio::test]
+    async fn defaults_until_saved() {
+        let data = Data::load(Arc::new(MemoryRepository::new()), None)
+            .await
+            .unwrap();
+        assert_eq!(get(&data, GUILD).await.unwrap(), default_settings(GUILD));
+
+        let settings = GuildSettings {
+            retention_days: Some(30),
+            voice_points_per_minute: 1.0,
+            ..default_settings(GUILD)
+        };
+        save(&data, settings.clone()).await.unwrap();
+
+        assert_eq!(get(&data, GUILD).await.unwrap(), settings);
+        assert_eq!(
+            data.repo.guild_settings(GUILD).await.unwrap(),
+            Some(settings.clone())
+        );
+
+        // later reads come from the cache, not the repository
+        data.repo
+            .save_guild_settings(default_settings(GUILD))
+            .await
+            .unwrap();
+        assert_eq!(get(&data, GUILD).await.unwrap(), settings);
+    }
+}
@@ -1,34 +1,29 @@
-use crate::message_analyzer::score_message;
+use crate::adapters::core_message;
+use crate::guild_settings;
+use crate::retention::content_to_store;
 use crate::serenity::model::prelude::Message;
+use crate::stats_cache::{invalidate_author, invalidate_user};
 use crate::{Data, Error};
 use async_recursion::async_recursion;
-use entity::guilds;
 
 use std::collections::HashSet;
 
-use entity::channels::{ActiveModel as ChannelActiveModel, Entity as ChannelEntity};
-use entity::messages;
-use entity::messages::{ActiveModel as MessageActiveModel, Entity as MessageEntity};
-use entity::users::{ActiveModel as UserActiveModel, Entity as UserEntity};
-
 use log::{error, trace, warn};
 
 use crate::serenity::cache::Cache;
 use crate::serenity::model::id::GuildId;
 use poise::serenity_prelude as serenity;
-use sea_orm::{
-    prelude::*, DatabaseConnection, QueryOrder, QuerySelect, SelectColumns, Set, TryIntoModel,
-};
 
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Instant;
 
-use entity::guilds::Model;
-use entity::prelude::Guilds;
-use migration::sea_orm::{ActiveModelTrait, EntityTrait};
+use rank_core::model::Guild;
+use rank_core::scoring::{score_message, ScoringConfig};
 use tokio::sync::RwLock;
 
+/// Store a message and the users, channel and guild it needs. Returns whether it was stored,
+/// messages already stored and messages outside of guilds are skipped.
 #[allow(clippy::too_many_arguments)]
 pub async fn handle_message(
     score: f32,
@@ -41,16 +36,12 @@ pub async fn handle_message(
     guild_in_db: &RwLock<HashSet<u64>>,
     channel_in_db: &RwLock<HashSet<u64>>,
     user_in_db: &RwLock<HashSet<u64>>,
-) -> Result<(), Error> {
+) -> Result<bool, Error> {
     let _timer = Instant::now();
     trace!("Message ({}): {}", msg.id, msg.content);
 
-    if MessageEntity::find_by_id(msg.id.get() as i64)
-        .one(&data.db)
-        .await?
-        .is_some()
-    {
-        return Ok(());
+    if data.repo.message(msg.id.get()).await?.is_some() {
+        return Ok(false);
     }
 
     let guild_id = match guild_id {
@@ -59,7 +50,7 @@ pub async fn handle_message(
             Some(guild_id) => guild_id,
             None => {
                 warn!("Message is not in a guild, ignoring");
-                return Ok(());
+                return Ok(false);
             }
         },
     }
@@ -71,22 +62,15 @@ pub async fn handle_message(
         if msg.guild(cache).is_some() {
             name = msg.guild(cache).map(|x| x.name.clone());
         }
-        match Guilds::find_by_id(guild_id as i64).one(&data.db).await? {
+        match data.repo.guild(guild_id).await? {
             Some(g) => {
                 guild_in_db.write().await.insert(guild_id);
                 Some(g)
             }
             None => {
                 let d_guild = http.get_guild(GuildId::new(guild_id)).await?;
-                let guild = guilds::ActiveModel {
-                    snowflake: Set(guild_id as i64),
-                    name: Set(d_guild.name),
-                    score: Set(0.),
-                    message_count: Set(0),
-                    user_count: Set(0),
-                };
                 guild_in_db.write().await.insert(guild_id);
-                Some(guild.clone().insert(&data.db).await?)
+                Some(data.repo.insert_guild(guild_id, d_guild.name).await?)
             }
         }
     } else {
@@ -99,10 +83,7 @@ pub async fn handle_message(
     // }
 
     let channel_name = if !channel_in_db.read().await.contains(&msg.channel_id.get()) {
-        match ChannelEntity::find_by_id(msg.channel_id.get() as i64)
-            .one(&data.db)
-            .await?
-        {
+        match data.repo.channel(msg.channel_id.get()).await? {
             Some(c) => {
                 channel_in_db.write().await.insert(msg.channel_id.get());
                 c.name
@@ -113,14 +94,9 @@ pub async fn handle_message(
                     serenity::Channel::Guild(c) => c.name,
                     _ => "DM".to_string(),
                 };
-                let channel = ChannelActiveModel {
-                    snowflake: Set(msg.channel_id.get() as i64),
-                    name: Set(channel_name.clone()),
-                    score: Set(0.),
-                    message_count: Set(0),
-                    guild: Set(guild_id as i64),
-                };
-                channel.insert(&data.db).await?.try_into_model()?;
+                data.repo
+                    .insert_channel(msg.channel_id.get(), guild_id, channel_name.clone())
+                    .await?;
                 channel_in_db.write().await.insert(msg.channel_id.get());
                 channel_name
             }
@@ -143,38 +119,51 @@ pub async fn handle_message(
     }
 
     if !user_in_db.read().await.contains(&msg.author.id.get()) {
-        match UserEntity::find_by_id(msg.author.id.get() as i64)
-            .one(&data.db)
-            .await?
-        {
+        match data.repo.user(msg.author.id.get()).await? {
             Some(_) => {
                 user_in_db.write().await.insert(msg.author.id.get());
             }
             None => {
-                let user = UserActiveModel {
-                    snowflake: Set(msg.author.id.get() as i64),
-                    name: Set(msg.author.name.clone()),
-                    score: Set(0.),
-                    message_count: Set(0),
-                    guild: Set(guild_id as i64),
-                };
                 user_in_db.write().await.insert(msg.author.id.get());
-                user.clone().insert(&data.db).await?;
+                data.repo
+                    .insert_user(msg.author.id.get(), guild_id, msg.author.name.clone())
+                    .await?;
             }
         };
     }
 
-    let message = MessageActiveModel {
-        snowflake: Set(msg.id.get() as i64),
-        content: Set(msg.content.clone()),
-        score: Set(score),
-        user: Set(msg.author.id.get() as i64),
-        channel: Set(msg.channel_id.get() as i64),
-        replys_to: { Set(find_reply_to(&data.db, msg, &data).await?) },
-        timestamp: Set(msg.timestamp.naive_utc()),
-    };
+    let replys_to = find_reply_to(msg, data, guild_id).await?;
+    insert_message(data, msg, guild_id, score, replys_to).await?;
 
-    message.insert(&data.db).await?;
+    if let Some(replys_to) = replys_to {
+        if data.repo.refresh_engagement(replys_to).await? != 0.0 {
+            invalidate_author(data, replys_to).await?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Store a message along with its precomputed features, applying the guild's retention policy
+async fn insert_message(
+    data: &Data,
+    msg: &Message,
+    guild_id: u64,
+    score: f32,
+    replys_to: Option<i64>,
+) -> Result<(), Error> {
+    let settings = guild_settings::get(data, guild_id).await?;
+
+    data.repo
+        .store_message(
+            &core_message(msg),
+            guild_id,
+            content_to_store(&settings, &msg.content),
+            score,
+            replys_to,
+        )
+        .await?;
+    invalidate_user(data, guild_id, msg.author.id.get() as i64).await;
 
     Ok(())
 }
@@ -185,61 +174,40 @@ async fn guild_by_id(
     guild_in_db: &RwLock<HashSet<u64>>,
     guild_id: u64,
     guild_name: String,
-) -> Result<Model, Error> {
-    Ok(
-        match Guilds::find_by_id(guild_id as i64).one(&data.db).await? {
-            Some(guild) => {
-                guild_in_db.write().await.insert(guild_id);
-                guild
-            }
-            None => {
-                let guild = guilds::ActiveModel {
-                    snowflake: Set(guild_id as i64),
-                    name: Set(guild_name),
-                    score: Set(0.),
-                    message_count: Set(0),
-                    user_count: Set(0),
-                };
-                guild_in_db.write().await.insert(guild_id);
-                guild.clone().insert(&data.db).await?
-            }
-        },
-    )
+) -> Result<Guild, Error> {
+    Ok(match data.repo.guild(guild_id).await? {
+        Some(guild) => {
+            guild_in_db.write().await.insert(guild_id);
+            guild
+        }
+        None => {
+            guild_in_db.write().await.insert(guild_id);
+            data.repo.insert_guild(guild_id, guild_name).await?
+        }
+    })
 }
 
 #[async_recursion]
-async fn find_reply_to(
-    db: &DatabaseConnection,
-    msg: &Message,
-    data: &Data,
-) -> Result<Option<i64>, Error> {
+async fn find_reply_to(msg: &Message, data: &Data, guild_id: u64) -> Result<Option<i64>, Error> {
     let _timer = Instant::now();
     let reply_to = match &msg.referenced_message {
         Some(ref_msg) => {
-            let reply_to = messages::Entity::find_by_id(ref_msg.id.get() as i64)
-                .one(db)
-                .await?;
+            let reply_to = data.repo.message(ref_msg.id.get()).await?;
             match reply_to {
                 Some(reply_to) => Some(reply_to.snowflake),
                 None => {
                     let mut last_five = data.last_five_map.write().await;
 
                     let last_five = last_five.entry(ref_msg.author.id.clone()).or_insert(
-                        entity::prelude::Messages::find()
-                            .filter(entity::messages::Column::User.eq(ref_msg.author.id.get()))
-                            .order_by_desc(entity::messages::Column::Snowflake)
-                            .select_only()
-                            .select_column(entity::messages::Column::Content)
-                            .limit(5)
-                            .all(&data.db)
+                        data.repo
+                            .recent_contents(ref_msg.author.id.get(), 5)
                             .await
-                            .expect("Error fetching recent messages")
-                            .into_iter()
-                            .map(|m| m.content)
-                            .collect(),
+                            .expect("Error fetching recent messages"),
                     );
 
-                    let score = score_message(ref_msg, last_five).await;
+                    let config =
+                        ScoringConfig::from_settings(&guild_settings::get(data, guild_id).await?);
+                    let score = score_message(&core_message(ref_msg), last_five, &config);
 
                     last_five.push(ref_msg.content.clone());
 
@@ -248,16 +216,8 @@ async fn find_reply_to(
                     }
                     debug_assert!(last_five.len() < 6);
 
-                    let reply_to = MessageActiveModel {
-                        snowflake: Set(ref_msg.id.get() as i64),
-                        content: Set(ref_msg.content.clone()),
-                        score: Set(score),
-                        user: Set(ref_msg.author.id.get() as i64),
-                        channel: Set(ref_msg.channel_id.get() as i64),
-                        replys_to: Set(find_reply_to(db, ref_msg, data).await?),
-                        timestamp: Set(ref_msg.timestamp.naive_utc()),
-                    };
-                    reply_to.insert(db).await?;
+                    let replys_to = find_reply_to(ref_msg, data, guild_id).await?;
+                    insert_message(data, ref_msg, guild_id, score, replys_to).await?;
                     Some(ref_msg.id.get() as i64)
                 }
             }
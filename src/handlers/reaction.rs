@@ -0,0 +1,309 @@
+use crate::serenity::model::prelude::{Message, MessageId, Reaction, ReactionType};
+use crate::stats_cache::invalidate_author;
+use crate::{Data, Error};
+use log::trace;
+use poise::serenity_prelude as serenity;
+
+/// Most users Discord returns per page of a reaction
+const REACTION_PAGE_SIZE: u8 = 100;
+
+/// Pause between the requests for who reacted, a backfill makes one per emoji of every message
+/// and would otherwise run into Discord's rate limit
+const REACTION_REQUEST_DELAY: std::time::Duration = std::time::Duration::from_millis(250);
+
+async fn is_stored(data: &Data, message: MessageId) -> Result<bool, Error> {
+    Ok(data.repo.message(message.get()).await?.is_some())
+}
+
+/// Store a new reaction and update the message's engagement bonus
+pub async fn handle_reaction_add(data: &Data, reaction: &Reaction) -> Result<(), Error> {
+    let user = match reaction.user_id {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+
+    if !is_stored(data, reaction.message_id).await? {
+        return Ok(());
+    }
+
+    let message = reaction.message_id.get() as i64;
+    data.repo
+        .insert_reactions(
+            message,
+            &reaction.emoji.to_string(),
+            &[user.get() as i64],
+            chrono::Utc::now().naive_utc(),
+        )
+        .await?;
+
+    let change = data.repo.refresh_engagement(message).await?;
+    if change != 0.0 {
+        invalidate_author(data, reaction.message_id.get() as i64).await?;
+    }
+    trace!(
+        "Reaction on {} changed engagement by {}",
+        reaction.message_id,
+        change
+    );
+
+    Ok(())
+}
+
+/// Forget a removed reaction and update the message's engagement bonus
+pub async fn handle_reaction_remove(data: &Data, reaction: &Reaction) -> Result<(), Error> {
+    let user = match reaction.user_id {
+        Some(user) => user,
+        None => return Ok(()),
+    };
+
+    let message = reaction.message_id.get() as i64;
+    data.repo
+        .delete_reactions(
+            message,
+            Some(user.get() as i64),
+            Some(&reaction.emoji.to_string()),
+        )
+        .await?;
+
+    if data.repo.refresh_engagement(message).await? != 0.0 {
+        invalidate_author(data, message).await?;
+    }
+
+    Ok(())
+}
+
+/// Forget every reaction of one emoji (a moderator cleared it) or of every emoji on a message
+pub async fn handle_reaction_clear(
+    data: &Data,
+    message: MessageId,
+    emoji: Option<&ReactionType>,
+) -> Result<(), Error> {
+    let message = message.get() as i64;
+    let emoji = emoji.map(|emoji| emoji.to_string());
+    data.repo
+        .delete_reactions(message, None, emoji.as_deref())
+        .await?;
+
+    if data.repo.refresh_engagement(message).await? != 0.0 {
+        invalidate_author(data, message).await?;
+    }
+
+    Ok(())
+}
+
+/// Fetch who reacted to a message and store it, used when loading old messages.
+/// Discord doesn't say when a reaction was added so the message's own time is used.
+/// Every emoji costs at least one request, so these are spaced out.
+pub async fn backfill_reactions(
+    data: &Data,
+    http: &serenity::Http,
+    message: &Message,
+) -> Result<(), Error> {
+    if message.reactions.is_empty() || !is_stored(data, message.id).await? {
+        return Ok(());
+    }
+
+    for reaction in message.reactions.iter().filter(|r| r.count > 0) {
+        let mut after = None;
+        loop {
+            tokio::time::sleep(REACTION_REQUEST_DELAY).await;
+            let users = message
+                .reaction_users(
+                    http,
+                    reaction.reaction_type.clone(),
+                    Some(REACTION_PAGE_SIZE),
+                    after,
+                )
+                .await?;
+
+            after = users.last().map(|user| user.id);
+            let page_len = users.len();
+
+            data.repo
+                .insert_reactions(
+                    message.id.get() as i64,
+                    &reaction.reaction_type.to_string(),
+                    &users
+                        .iter()
+                        .map(|user| user.id.get() as i64)
+                        .collect::<Vec<_>>(),
+                    message.timestamp.naive_utc(),
+                )
+                .await?;
+
+            if page_len < REACTION_PAGE_SIZE as usize {
+                break;
+            }
+        }
+    }
+
+    if data
+        .repo
+        .refresh_engagement(message.id.get() as i64)
+        .await?
+        != 0.0
+    {
+        invalidate_author(data, message.id.get() as i64).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats_cache::{window_stats, Window};
+    use rank_core::engagement::REACTION_BONUS;
+    use rank_core::message::Message as NewMessage;
+    use rank_core::repository::{MemoryRepository, Repository};
+    use std::sync::Arc;
+
+    const GUILD: u64 = 1;
+    const CHANNEL: u64 = 10;
+    const ALICE: u64 = 100;
+    const BOB: u64 = 101;
+    const CAROL: u64 = 102;
+
+    /// A guild where alice posted message 1000
+    async fn data_with_message() -> Data {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_channel(CHANNEL, GUILD, "general".to_string())
+            .await
+            .unwrap();
+        for (user, name) in [(ALICE, "alice"), (BOB, "bob"), (CAROL, "carol")] {
+            repo.insert_user(user, GUILD, name.to_string())
+                .await
+                .unwrap();
+        }
+        let message = NewMessage {
+            id: 1000,
+            author: ALICE,
+            channel: CHANNEL,
+            content: "a message worth reacting to".to_string(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            attachments: vec![],
+            mention_count: 0,
+        };
+        repo.store_message(&message, GUILD, None, 5.0, None)
+            .await
+            .unwrap();
+        Data::load(Arc::new(repo), None).await.unwrap()
+    }
+
+    fn reaction(message: u64, user: u64, emoji: serde_json::Value) -> Reaction {
+        serde_json::from_value(serde_json::json!({
+            "user_id": user.to_string(),
+            "channel_id": CHANNEL.to_string(),
+            "message_id": message.to_string(),
+            "guild_id": GUILD.to_string(),
+            "emoji": emoji,
+            "burst": false,
+            "type": 0,
+        }))
+        .unwrap()
+    }
+
+    fn unicode(name: &str) -> serde_json::Value {
+        serde_json::json!({ "id": null, "name": name })
+    }
+
+    fn custom() -> serde_json::Value {
+        serde_json::json!({ "id": "1234", "name": "ferris", "animated": false })
+    }
+
+    #[tokio::test]
+    async fn tracks_given_and_received_reactions() {
+        let data = data_with_message().await;
+        for (user, emoji) in [
+            (BOB, custom()),
+            (BOB, unicode("👍")),
+            (CAROL, custom()),
+            // repeated events are stored once
+            (CAROL, custom()),
+            (ALICE, custom()),
+        ] {
+            handle_reaction_add(&data, &reaction(1000, user, emoji))
+                .await
+                .unwrap();
+        }
+
+        let repo = data.repo.as_ref();
+        assert_eq!(repo.reactions_given(BOB as i64).await.unwrap(), 2);
+        assert_eq!(repo.reactions_given(CAROL as i64).await.unwrap(), 1);
+        // alice's own reaction is not received
+        assert_eq!(repo.reactions_received(ALICE as i64).await.unwrap(), 3);
+        assert_eq!(
+            repo.favorite_emoji(CAROL as i64).await.unwrap(),
+            Some(("<:ferris:1234>".to_string(), 1))
+        );
+        assert_eq!(repo.message(1000).await.unwrap().unwrap().reaction_count, 3);
+    }
+
+    #[tokio::test]
+    async fn removing_and_clearing_reactions() {
+        let data = data_with_message().await;
+        for (user, emoji) in [
+            (BOB, unicode("👍")),
+            (BOB, unicode("🎉")),
+            (CAROL, unicode("👍")),
+            (CAROL, unicode("🎉")),
+        ] {
+            handle_reaction_add(&data, &reaction(1000, user, emoji))
+                .await
+                .unwrap();
+        }
+        let repo = data.repo.as_ref();
+
+        handle_reaction_remove(&data, &reaction(1000, BOB, unicode("👍")))
+            .await
+            .unwrap();
+        assert_eq!(repo.reactions_given(BOB as i64).await.unwrap(), 1);
+        assert_eq!(repo.reactions_given(CAROL as i64).await.unwrap(), 2);
+
+        // a moderator removing every 🎉
+        let party = ReactionType::Unicode("🎉".to_string());
+        handle_reaction_clear(&data, MessageId::new(1000), Some(&party))
+            .await
+            .unwrap();
+        assert_eq!(repo.reactions_given(BOB as i64).await.unwrap(), 0);
+        assert_eq!(
+            repo.favorite_emoji(CAROL as i64).await.unwrap(),
+            Some(("👍".to_string(), 1))
+        );
+
+        handle_reaction_clear(&data, MessageId::new(1000), None)
+            .await
+            .unwrap();
+        assert_eq!(repo.reactions_received(ALICE as i64).await.unwrap(), 0);
+        assert_eq!(repo.message(1000).await.unwrap().unwrap().reaction_count, 0);
+    }
+
+    #[tokio::test]
+    async fn reactions_on_messages_not_stored_are_ignored() {
+        let data = data_with_message().await;
+
+        handle_reaction_add(&data, &reaction(2000, BOB, unicode("👍")))
+            .await
+            .unwrap();
+
+        assert_eq!(data.repo.reactions_given(BOB as i64).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn reactions_refresh_the_authors_cached_window() {
+        let data = data_with_message().await;
+        let before = window_stats(&data, GUILD, ALICE as i64, Window::Week)
+            .await
+            .unwrap();
+
+        handle_reaction_add(&data, &reaction(1000, BOB, unicode("👍")))
+            .await
+            .unwrap();
+
+        let after = window_stats(&data, GUILD, ALICE as i64, Window::Week)
+            .await
+            .unwrap();
+        assert_eq!(after.score, before.score + REACTION_BONUS);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::Error;
+use rank_core::adjustments::{ImportedUser, NewImport};
+use rank_core::levels::{level_from_xp, UserScore};
+use rank_core::repository::Repository;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter, clap::ValueEnum)]
+pub enum ImportFormat {
+    #[name = "CSV"]
+    Csv,
+    #[name = "JSON"]
+    Json,
+}
+
+impl ImportFormat {
+    /// Guess the format of an export from its file name
+    pub fn from_file_name(name: &str) -> ImportFormat {
+        if name.to_lowercase().ends_with(".csv") {
+            ImportFormat::Csv
+        } else {
+            ImportFormat::Json
+        }
+    }
+}
+
+/// One user from another bot's export
+#[derive(Debug, Deserialize)]
+pub struct ImportRecord {
+    #[serde(alias = "user_id", alias = "id", deserialize_with = "deserialize_id")]
+    pub user: u64,
+    #[serde(default, alias = "username")]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub xp: Option<f64>,
+    #[serde(default)]
+    pub level: Option<f32>,
+    #[serde(default, alias = "messages")]
+    pub message_count: Option<i32>,
+}
+
+/// Other bots' exports often store snowflakes as strings
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        String(String),
+    }
+
+    match Id::deserialize(deserializer)? {
+        Id::Number(id) => Ok(id),
+        Id::String(id) => id.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// The JSON shapes accepted: a plain list of users, or a leaderboard dump with a `players` list
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonExport {
+    List(Vec<ImportRecord>),
+    Leaderboard { players: Vec<ImportRecord> },
+}
+
+pub fn parse_records(format: ImportFormat, bytes: &[u8]) -> Result<Vec<ImportRecord>, Error> {
+    let records = match format {
+        ImportFormat::Csv => csv::Reader::from_reader(bytes)
+            .deserialize()
+            .collect::<Result<Vec<ImportRecord>, _>>()?,
+        ImportFormat::Json => match serde_json::from_slice(bytes)? {
+            JsonExport::List(records) => records,
+            JsonExport::Leaderboard { players } => players,
+        },
+    };
+
+    for record in records.iter() {
+        if record.xp.is_some_and(|xp| !xp.is_finite()) {
+            return Err(format!("user {} has an invalid xp value", record.user).into());
+        }
+        if record.level.is_some_and(|level| !level.is_finite()) {
+            return Err(format!("user {} has an invalid level", record.user).into());
+        }
+        if record.message_count.is_some_and(|count| count < 0) {
+            return Err(format!("user {} has a negative message count", record.user).into());
+        }
+        if !score_for_record(record).is_finite() {
+            return Err(format!("user {} has a level too high to import", record.user).into());
+        }
+    }
+
+    Ok(records)
+}
+
+/// Map a user from another bot onto our levelling curve, so they keep the level they had
+pub fn score_for_record(record: &ImportRecord) -> f32 {
+    let level = match (record.level, record.xp) {
+        (Some(level), _) => level,
+        (None, Some(xp)) => level_from_xp(xp),
+        (None, None) => 0.0,
+    };
+    UserScore::score_for_level(level)
+}
+
+pub struct ImportSummary {
+    pub batch: String,
+    pub users: usize,
+    pub created_users: usize,
+    pub score: f32,
+}
+
+/// Seed users' scores from another bot's export.
+/// Every user gets a synthetic score adjustment tagged with the import batch, so the import
+/// can be reverted and the message derived numbers (channels, windows) are left alone.
+pub async fn import_records(
+    repo: &dyn Repository,
+    guild: u64,
+    moderator: Option<u64>,
+    records: &[ImportRecord],
+) -> Result<ImportSummary, Error> {
+    if repo.guild(guild).await?.is_none() {
+        return Err(format!(
+            "guild {} is not in the database yet, load its messages first",
+            guild
+        )
+        .into());
+    }
+
+    let users = records
+        .iter()
+        .map(|record| ImportedUser {
+            user: record.user,
+            name: record.name.clone(),
+            delta: score_for_record(record),
+            message_delta: record.message_count.unwrap_or(0),
+        })
+        .collect::<Vec<_>>();
+    let score = users.iter().map(|user| user.delta).sum();
+
+    let imported = repo
+        .import_adjustments(NewImport {
+            guild,
+            moderator,
+            batch: format!("import-{}", chrono::Utc::now().format("%Y%m%d%H%M%S")),
+            reason: Some("imported from another bot".to_string()),
+            users,
+        })
+        .await?;
+
+    Ok(ImportSummary {
+        batch: imported.batch,
+        users: records.len(),
+        created_users: imported.created_users,
+        score,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::leaderboard::{leaderboard_page, Ranking};
+    use crate::stats_cache::Window;
+    use rank_core::repository::{AdminRepository, MemoryRepository};
+
+    const GUILD: u64 = 1;
+
+    async fn guild_repo() -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_user(100, GUILD, "alice".to_string())
+            .await
+            .unwrap();
+        repo
+    }
+
+    #[test]
+    fn parses_other_bots_exports() {
+        let csv = "user_id,username,xp,messages\n\"100\",alice,1500,12\n101,bob,,\n";
+        let records = parse_records(ImportFormat::Csv, csv.as_bytes()).unwrap();
+        assert_eq!(records[0].user, 100);
+        assert_eq!(records[0].name.as_deref(), Some("alice"));
+        assert_eq!(records[0].message_count, Some(12));
+        assert_eq!((records[1].xp, records[1].message_count), (None, None));
+
+        let json = r#"{"players": [{"id": "100", "level": 3, "messages": 40}]}"#;
+        let records = parse_records(ImportFormat::Json, json.as_bytes()).unwrap();
+        assert_eq!((records[0].user, records[0].level), (100, Some(3.0)));
+    }
+
+    #[test]
+    fn rejects_impossible_values() {
+        let negative = r#"[{"id": 100, "level": 3, "messages": -5}]"#;
+        assert!(parse_records(ImportFormat::Json, negative.as_bytes()).is_err());
+        let too_high = r#"[{"id": 100, "level": 1e30}]"#;
+        assert!(parse_records(ImportFormat::Json, too_high.as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn import_and_revert() {
+        let repo = guild_repo().await;
+        let records = parse_records(
+            ImportFormat::Json,
+            br#"[{"id": 100, "level": 5, "messages": 10}, {"id": 999, "name": "carol", "level": 2}]"#,
+        )
+        .unwrap();
+
+        let summary = import_records(&repo, GUILD, None, &records).await.unwrap();
+
+        assert_eq!((summary.users, summary.created_users), (2, 1));
+        let alice = repo.user(100).await.unwrap().unwrap();
+        assert_eq!(alice.score, UserScore::score_for_level(5.0));
+        assert_eq!(alice.message_count, 10);
+        assert_eq!(repo.user(999).await.unwrap().unwrap().name, "carol");
+        assert_eq!(
+            repo.import_batches(GUILD)
+                .await
+                .unwrap()
+                .get(&summary.batch),
+            Some(&2)
+        );
+
+        assert_eq!(repo.revert_import(GUILD, &summary.batch).await.unwrap(), 2);
+        let alice = repo.user(100).await.unwrap().unwrap();
+        assert_eq!((alice.score, alice.message_count), (0.0, 0));
+        assert!(repo.import_batches(GUILD).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn imports_are_not_recent_score() {
+        let repo = guild_repo().await;
+        let records = [ImportRecord {
+            user: 100,
+            name: None,
+            xp: None,
+            level: Some(5.0),
+            message_count: None,
+        }];
+        import_records(&repo, GUILD, None, &records).await.unwrap();
+
+        let all_time = leaderboard_page(&repo, GUILD, Ranking::Score, None, 1)
+            .await
+            .unwrap();
+        assert_eq!(all_time.rows.len(), 1);
+        let week = leaderboard_page(&repo, GUILD, Ranking::Score, Some(Window::Week), 1)
+            .await
+            .unwrap();
+        assert!(week.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn import_needs_a_stored_guild() {
+        let repo = guild_repo().await;
+        let records = parse_records(ImportFormat::Json, br#"[{"id": 100, "level": 5}]"#).unwrap();
+
+        assert!(import_records(&repo, GUILD + 1, None, &records)
+            .await
+            .is_err());
+        assert_eq!(repo.user(100).await.unwrap().unwrap().score, 0.0);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::stats_cache::Window;
+use crate::voice::format_voice_time;
+use crate::Error;
+use poise::ChoiceParameter;
+use rank_core::levels::UserScore;
+use rank_core::repository::Repository;
+use std::collections::HashMap;
+
+/// Users shown per page
+pub const PAGE_SIZE: u64 = 10;
+
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter, clap::ValueEnum)]
+pub enum Ranking {
+    #[name = "Score"]
+    Score,
+    #[name = "Voice time"]
+    Voice,
+}
+
+/// One page of a leaderboard, the name and value of every user on it, best first
+pub struct LeaderboardPage {
+    pub title: String,
+    pub rows: Vec<(String, String)>,
+}
+
+/// Page `page` (starting at 1) of the users of a guild ranked by voice time, score, or score
+/// in a recent window
+pub async fn leaderboard_page(
+    repo: &dyn Repository,
+    guild: u64,
+    ranking: Ranking,
+    window: Option<Window>,
+    page: u64,
+) -> Result<LeaderboardPage, Error> {
+    let page = page.max(1);
+
+    if let Ranking::Voice = ranking {
+        let totals = repo
+            .voice_totals(guild, None, (page - 1) * PAGE_SIZE, PAGE_SIZE)
+            .await?;
+        let names = repo
+            .user_names(&totals.iter().map(|t| t.0).collect::<Vec<_>>())
+            .await?;
+
+        return Ok(LeaderboardPage {
+            title: format!("Voice leaderboard page: {}", page),
+            rows: totals
+                .iter()
+                .map(|(user, seconds, score)| {
+                    (
+                        names.get(user).cloned().unwrap_or(user.to_string()),
+                        format!("{} - {:.0} points", format_voice_time(*seconds), score),
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    if let Some(window) = window {
+        let since = window.since(chrono::Utc::now().date_naive());
+
+        let mut totals = repo
+            .user_totals_since(guild, since)
+            .await?
+            .into_values()
+            .map(|total| (total.user, total.score))
+            .collect::<HashMap<i64, f32>>();
+        for (user, delta) in repo
+            .adjustments_since(guild, since.and_hms_opt(0, 0, 0).unwrap())
+            .await?
+        {
+            *totals.entry(user).or_insert(0.0) += delta;
+        }
+
+        let mut totals = totals.into_iter().collect::<Vec<(i64, f32)>>();
+        totals.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        let totals = totals
+            .into_iter()
+            .skip(((page - 1) * PAGE_SIZE) as usize)
+            .take(PAGE_SIZE as usize)
+            .collect::<Vec<_>>();
+        let names = repo
+            .user_names(&totals.iter().map(|t| t.0).collect::<Vec<_>>())
+            .await?;
+
+        return Ok(LeaderboardPage {
+            title: format!("Leaderboard ({}) page: {}", window.name(), page),
+            rows: totals
+                .iter()
+                .map(|(user, score)| {
+                    (
+                        names.get(user).cloned().unwrap_or(user.to_string()),
+                        format!("{:.2}", score),
+                    )
+                })
+                .collect(),
+        });
+    }
+
+    // users in the guild ordered by score
+    let users = repo
+        .users_by_score(guild, (page - 1) * PAGE_SIZE, Some(PAGE_SIZE))
+        .await?;
+
+    Ok(LeaderboardPage {
+        title: format!("Leaderboard page: {}", page),
+        rows: users
+            .iter()
+            .map(|user| {
+                (
+                    user.name.clone(),
+                    UserScore::new(user.score).display_score(),
+                )
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDateTime, Utc};
+    use rank_core::adjustments::{NewAdjustment, MANUAL_SOURCE};
+    use rank_core::message::Message;
+    use rank_core::repository::{AdminRepository, MemoryRepository};
+
+    const GUILD: u64 = 1;
+
+    async fn guild_repo(users: u64) -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, GUILD, "general".to_string())
+            .await
+            .unwrap();
+        for user in 0..users {
+            repo.insert_user(100 + user, GUILD, format!("user{}", user))
+                .await
+                .unwrap();
+        }
+        repo
+    }
+
+    /// Store a message like the message handler does, into the rollups and the running totals
+    async fn post(repo: &MemoryRepository, id: u64, author: u64, score: f32, sent: NaiveDateTime) {
+        let message = Message {
+            id,
+            author,
+            channel: 10,
+            content: "a message".to_string(),
+            timestamp: sent,
+            attachments: vec![],
+            mention_count: 0,
+        };
+        repo.store_message(&message, GUILD, None, score, None)
+            .await
+            .unwrap();
+        repo.add_to_user(author as i64, score, 1).await.unwrap();
+    }
+
+    fn names(page: &LeaderboardPage) -> Vec<&str> {
+        page.rows.iter().map(|row| row.0.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn windows_sum_the_days_and_adjustments_inside_them() {
+        let repo = guild_repo(2).await;
+        let now = Utc::now().naive_utc();
+        post(&repo, 1000, 100, 5.0, now).await;
+        post(&repo, 1001, 100, 50.0, now - Duration::days(20)).await;
+        post(&repo, 1002, 101, 8.0, now).await;
+        repo.apply_adjustment(NewAdjustment {
+            guild: GUILD,
+            user: 101,
+            moderator: Some(1),
+            delta: 10.0,
+            message_delta: 0,
+            source: MANUAL_SOURCE,
+            batch: None,
+            reason: None,
+        })
+        .await
+        .unwrap();
+
+        let week = leaderboard_page(&repo, GUILD, Ranking::Score, Some(Window::Week), 1)
+            .await
+            .unwrap();
+        assert_eq!(
+            week.rows,
+            [
+                ("user1".to_string(), "18.00".to_string()),
+                ("user0".to_string(), "5.00".to_string())
+            ]
+        );
+        let month = leaderboard_page(&repo, GUILD, Ranking::Score, Some(Window::Month), 1)
+            .await
+            .unwrap();
+        assert_eq!(names(&month), ["user0", "user1"]);
+    }
+
+    #[tokio::test]
+    async fn pages_continue_where_the_last_one_ended() {
+        let repo = guild_repo(PAGE_SIZE + 2).await;
+        let now = Utc::now().naive_utc();
+        for user in 0..PAGE_SIZE + 2 {
+            post(&repo, 1000 + user, 100 + user, user as f32 + 1.0, now).await;
+        }
+
+        for window in [None, Some(Window::Week)] {
+            let first = leaderboard_page(&repo, GUILD, Ranking::Score, window, 1)
+                .await
+                .unwrap();
+            assert_eq!(first.rows.len(), PAGE_SIZE as usize);
+            assert_eq!(names(&first)[0], format!("user{}", PAGE_SIZE + 1));
+            let second = leaderboard_page(&repo, GUILD, Ranking::Score, window, 2)
+                .await
+                .unwrap();
+            assert_eq!(names(&second), ["user1", "user0"]);
+        }
+    }
+}
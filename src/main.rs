@@ -2,10 +2,6 @@
 
 extern crate core;
 
-use entity::channels::Column::Snowflake as ChannelSnowflake;
-use entity::guilds::Column::Snowflake as GuildSnowflake;
-use entity::prelude::Messages;
-use entity::users::Column::Snowflake as UserSnowflake;
 use env_file_reader::read_file;
 use log::{debug, info, LevelFilter};
 use poise::serenity_prelude as serenity;
@@ -13,36 +9,92 @@ use serenity::all::UserId;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectOptions, Database, DatabaseConnection, EntityTrait,
-    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, SelectColumns, Set,
-};
+use sea_orm::{ConnectOptions, Database};
 
 use crate::handlers::message::handle_message;
+use crate::handlers::reaction::{
+    handle_reaction_add, handle_reaction_clear, handle_reaction_remove,
+};
 use commands::messages;
+use rank_core::repository::{PostgresRepository, Repository};
 
-use crate::commands::{leaderboard, stats};
-use crate::message_analyzer::score_message;
-use entity::prelude::Guilds;
+use crate::adapters::core_message;
+use crate::commands::{
+    activity, botmanagers, channelstats, compare, connections, export as export_command,
+    import as import_command, leaderboard as leaderboard_command, retention as retention_command,
+    score as score_command, scoring, serverstats, stats, stopwords, voicepoints, words,
+};
+use crate::progressive_embed::LoadingIndicator;
+use crate::stats_cache::StatsCache;
+use crate::voice::handle_voice_state;
+use clap::Parser;
+use rank_core::scoring::{score_message, ScoringConfig};
 use std::time::Duration;
 use tokio::sync::RwLock;
 
+mod adapters;
+mod backfill;
+mod charts;
+mod cli;
 mod commands;
 mod common_words;
-mod db;
+mod export;
+mod guild_settings;
 mod handlers;
+mod import;
+mod leaderboard;
 mod logging;
-mod message_analyzer;
-mod scores;
+mod maintenance;
+mod permissions;
+mod progressive_embed;
+mod reply_graph;
+mod retention;
+mod schema;
+mod server_stats;
+mod stats_cache;
+mod voice;
+mod word_stats;
 
 #[derive(Clone)]
 pub struct Data {
-    db: DatabaseConnection,
+    /// Reads and writes of guilds, channels, users and messages
+    repo: Arc<dyn Repository>,
     last_five_map: Arc<RwLock<HashMap<UserId, Vec<String>>>>,
     guild_in_db: Arc<RwLock<HashSet<u64>>>,
     channel_in_db: Arc<RwLock<HashSet<u64>>>,
     user_in_db: Arc<RwLock<HashSet<u64>>>,
     common_words: Arc<HashSet<String>>,
+    guild_common_words: Arc<RwLock<HashMap<u64, Arc<HashSet<String>>>>>,
+    guild_settings: Arc<RwLock<HashMap<u64, rank_core::model::GuildSettings>>>,
+    /// Name and id of the animated emoji shown while stats load
+    loading_emoji: Option<(String, u64)>,
+    stats_cache: Arc<RwLock<StatsCache>>,
+}
+
+impl Data {
+    /// State shared by commands, handlers and the CLI, starting with the guilds, channels and
+    /// users already in the database
+    pub async fn load(
+        repo: Arc<dyn Repository>,
+        loading_emoji: Option<(String, u64)>,
+    ) -> Result<Data, Error> {
+        let guild_in_db = repo.guild_ids().await?;
+        let channel_in_db = repo.channel_ids().await?;
+        let user_in_db = repo.user_ids().await?;
+
+        Ok(Data {
+            repo,
+            last_five_map: Arc::new(RwLock::new(HashMap::new())),
+            guild_in_db: Arc::new(RwLock::new(guild_in_db)),
+            channel_in_db: Arc::new(RwLock::new(channel_in_db)),
+            user_in_db: Arc::new(RwLock::new(user_in_db)),
+            common_words: Arc::new(common_words::get_common_words()),
+            guild_common_words: Arc::new(RwLock::new(HashMap::new())),
+            guild_settings: Arc::new(RwLock::new(HashMap::new())),
+            loading_emoji,
+            stats_cache: Arc::new(RwLock::new(stats_cache::new_cache())),
+        })
+    }
 }
 
 unsafe impl Send for Data {}
@@ -96,22 +148,23 @@ async fn event_event_handler(
                         .expect("Failed to reply to message");
                 }
 
+                let config = match msg.guild_id {
+                    Some(guild_id) => ScoringConfig::from_settings(
+                        &guild_settings::get(data, guild_id.get()).await?,
+                    ),
+                    None => ScoringConfig::default(),
+                };
+
                 let mut last_five = data.last_five_map.write().await;
 
                 let last_five = last_five.entry(msg.author.id.clone()).or_insert(
-                    Messages::find()
-                        .filter(entity::messages::Column::User.eq(msg.author.id.get()))
-                        .order_by_desc(entity::messages::Column::Snowflake)
-                        .limit(5)
-                        .all(&data.db)
+                    data.repo
+                        .recent_contents(msg.author.id.get(), 5)
                         .await
-                        .expect("Error fetching recent messages")
-                        .into_iter()
-                        .map(|m| m.content)
-                        .collect(),
+                        .expect("Error fetching recent messages"),
                 );
 
-                let score = score_message(msg, last_five).await;
+                let score = score_message(&core_message(msg), last_five, &config);
 
                 last_five.push(msg.content.clone());
 
@@ -120,7 +173,7 @@ async fn event_event_handler(
                 }
                 debug_assert!(last_five.len() < 6);
 
-                handle_message(
+                let stored = handle_message(
                     score,
                     &_ctx.http.clone(),
                     data,
@@ -134,41 +187,43 @@ async fn event_event_handler(
                 )
                 .await
                 .expect("Failed to handle message");
-
-                let mut a_guild = Guilds::find_by_id(msg.guild_id.unwrap().get() as i64)
-                    .one(&data.db)
-                    .await?
-                    .unwrap()
-                    .into_active_model();
-                a_guild.score = Set(score + a_guild.score.unwrap());
-                a_guild.message_count = Set(a_guild.message_count.unwrap() + 1);
-
-                a_guild.update(&data.db).await?;
-
-                let mut a_user = entity::users::Entity::find_by_id(msg.author.id.get() as i64)
-                    .one(&data.db)
-                    .await?
-                    .unwrap()
-                    .into_active_model();
-
-                a_user.score = Set(score + a_user.score.unwrap());
-                a_user.message_count = Set(a_user.message_count.unwrap() + 1);
-
-                a_user.update(&data.db).await?;
-
-                let mut a_channel =
-                    entity::channels::Entity::find_by_id(msg.channel_id.get() as i64)
-                        .one(&data.db)
-                        .await?
-                        .unwrap()
-                        .into_active_model();
-
-                a_channel.score = Set(score + a_channel.score.unwrap());
-                a_channel.message_count = Set(a_channel.message_count.unwrap() + 1);
-
-                a_channel.update(&data.db).await?;
+                // a message seen before is already in the totals
+                if stored {
+                    data.repo
+                        .add_to_guild(msg.guild_id.unwrap().get() as i64, score, 1)
+                        .await?;
+                    data.repo
+                        .add_to_user(msg.author.id.get() as i64, score, 1)
+                        .await?;
+                    data.repo
+                        .add_to_channel(msg.channel_id.get() as i64, score, 1)
+                        .await?;
+                }
             }
         }
+        serenity::FullEvent::ReactionAdd { add_reaction } => {
+            handle_reaction_add(data, add_reaction).await?;
+        }
+        serenity::FullEvent::ReactionRemove { removed_reaction } => {
+            handle_reaction_remove(data, removed_reaction).await?;
+        }
+        serenity::FullEvent::ReactionRemoveAll {
+            removed_from_message_id,
+            ..
+        } => {
+            handle_reaction_clear(data, *removed_from_message_id, None).await?;
+        }
+        serenity::FullEvent::ReactionRemoveEmoji { removed_reactions } => {
+            handle_reaction_clear(
+                data,
+                removed_reactions.message_id,
+                Some(&removed_reactions.emoji),
+            )
+            .await?;
+        }
+        serenity::FullEvent::VoiceStateUpdate { new, .. } => {
+            handle_voice_state(data, &_ctx.cache, new).await?;
+        }
         _ => {}
     }
     Ok(())
@@ -176,6 +231,8 @@ async fn event_event_handler(
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    let cli = cli::Cli::parse();
+
     logging::setup_logging()?;
 
     debug!("Starting up");
@@ -184,14 +241,30 @@ async fn main() -> Result<(), Error> {
 
     let env_variables = read_file("./auth.env").expect("Failed to read .env file, does it exist?");
 
-    let token = env_variables
-        .get("TOKEN")
-        .expect("Failed to get TOKEN from .env file, did you set it?");
+    let command = cli.command.unwrap_or(cli::Command::Run);
+
+    // only the bot itself and the commands that talk to Discord need the token
+    let token = command.needs_token().then(|| {
+        env_variables
+            .get("TOKEN")
+            .expect("Failed to get TOKEN from .env file, did you set it?")
+            .as_str()
+    });
 
     let db_url = env_variables
         .get("DATABASE_URL")
         .expect("Failed to get DATABASE_URL from .env file, did you set it?");
 
+    // migrations are applied on boot unless turned off, then `migrate up` has to be run by hand
+    let auto_migrate = env_variables
+        .get("AUTO_MIGRATE")
+        .map(|value| !matches!(value.trim(), "false" | "0" | "no"))
+        .unwrap_or(true);
+
+    let loading_emoji = env_variables
+        .get("LOADING_EMOJI")
+        .and_then(|emoji| LoadingIndicator::parse_emoji(emoji));
+
     // let db_url = "sqlite://./db.db"; // you have to provide a database BEFORE running the bot
 
     let mut opt = ConnectOptions::new(db_url.to_owned());
@@ -208,31 +281,17 @@ async fn main() -> Result<(), Error> {
         .await
         .expect("Failed to connect to database");
 
-    // Migrator::fresh(&db).await?; // (this is for when you want to reset the database)
-
-    let guild_in_db = entity::guilds::Entity::find()
-        .select_column(GuildSnowflake)
-        .all(&db)
-        .await?
-        .into_iter()
-        .map(|g| g.snowflake as u64)
-        .collect::<HashSet<_>>();
-
-    let channel_in_db = entity::channels::Entity::find()
-        .select_column(ChannelSnowflake)
-        .all(&db)
-        .await?
-        .into_iter()
-        .map(|c| c.snowflake as u64)
-        .collect::<HashSet<_>>();
-
-    let user_in_db = entity::users::Entity::find()
-        .select_column(UserSnowflake)
-        .all(&db)
-        .await?
-        .into_iter()
-        .map(|u| u.snowflake as u64)
-        .collect::<HashSet<_>>();
+    if command.manages_schema() {
+        return cli::manage_schema(command, &db).await;
+    }
+    schema::migrate_and_check(&db, auto_migrate).await?;
+
+    let data = Data::load(Arc::new(PostgresRepository::new(db)), loading_emoji).await?;
+
+    if !matches!(command, cli::Command::Run) {
+        return cli::run(command, &data, token).await;
+    }
+    let token = token.expect("the bot always needs the token");
 
     info!("Done ====================");
 
@@ -240,15 +299,30 @@ async fn main() -> Result<(), Error> {
         .options(poise::FrameworkOptions {
             commands: vec![
                 messages::load_messages(),
-                leaderboard::leaderboard(),
+                leaderboard_command::leaderboard(),
                 stats::stats(),
+                channelstats::channelstats(),
+                serverstats::serverstats(),
+                compare::compare(),
+                retention_command::retention(),
+                export_command::export(),
+                import_command::import(),
+                score_command::score(),
+                botmanagers::botmanagers(),
+                activity::activity(),
+                connections::connections(),
+                words::words(),
+                words::wordcount(),
+                voicepoints::voicepoints(),
+                scoring::scoring(),
+                stopwords::stopwords(),
             ],
             event_handler: |ctx, event, framework, user_data| {
                 Box::pin(event_event_handler(ctx, event, framework, user_data))
             },
             ..Default::default()
         })
-        .setup(|ctx, _ready, framework| {
+        .setup(move |ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
                 // poise::builtins::register_in_guild(
@@ -257,14 +331,9 @@ async fn main() -> Result<(), Error> {
                 //     GuildId(729277347399991336),
                 // )
                 //     .await?;
-                Ok(Data {
-                    db,
-                    last_five_map: Arc::new(RwLock::new(HashMap::new())),
-                    guild_in_db: Arc::new(RwLock::new(guild_in_db)),
-                    channel_in_db: Arc::new(RwLock::new(channel_in_db)),
-                    user_in_db: Arc::new(RwLock::new(user_in_db)),
-                    common_words: Arc::new(common_words::get_common_words()),
-                })
+                retention::spawn_retention_task(data.repo.clone());
+                voice::spawn_voice_task(ctx.clone(), data.clone());
+                Ok(data)
             })
         })
         .build();
@@ -0,0 +1,253 @@
+use crate::stats_cache::invalidate_guild;
+use crate::{guild_settings, Data, Error};
+use rank_core::repository::Repository;
+use rank_core::scoring::{score_features, MessageFeatures, ScoringConfig};
+use std::collections::HashMap;
+
+// Offline repairs run from the CLI, the recomputing of totals is in `rank_core::maintenance`
+
+/// How many messages are rescored in one transaction
+const RESCORE_PAGE_SIZE: u64 = 1000;
+
+pub struct RescoreSummary {
+    pub messages: u64,
+    pub changed: u64,
+    /// Messages whose content was pruned or truncated keep their score
+    pub skipped: u64,
+}
+
+/// The features `score_features` needs, as stored with the message
+fn stored_features(message: &rank_core::model::StoredMessage) -> MessageFeatures {
+    MessageFeatures {
+        length: message.length,
+        word_count: message.word_count,
+        tokens: HashMap::new(),
+        bigrams: HashMap::new(),
+        attachment_count: message.attachment_count,
+        attachment_types: message.attachment_types.clone(),
+        link_count: message.link_count,
+        code_lines: message.code_lines,
+        emoji_only: message.emoji_only,
+        mention_count: message.mention_count,
+    }
+}
+
+/// Score every stored message of a guild again with the guild's current scoring settings,
+/// keeping their engagement bonus, then reconcile the totals and rebuild the rollups.
+pub async fn rescore_guild(data: &Data, guild: u64) -> Result<RescoreSummary, Error> {
+    let repo = data.repo.as_ref();
+    let config = ScoringConfig::from_settings(&guild_settings::get(data, guild).await?);
+    let mut summary = RescoreSummary {
+        messages: 0,
+        changed: 0,
+        skipped: 0,
+    };
+
+    // the last five messages of every user, repeating one of them scores nothing
+    let mut recent: HashMap<i64, Vec<String>> = HashMap::new();
+
+    let mut after = None;
+    loop {
+        let messages = repo
+            .guild_messages(guild, None, None, after, RESCORE_PAGE_SIZE)
+            .await?;
+        let last = match messages.last() {
+            Some(last) => last.snowflake,
+            None => break,
+        };
+
+        let mut scores = Vec::new();
+        for message in messages {
+            summary.messages += 1;
+            // content that was truncated would score as a shorter message than was sent
+            let content = match &message.content {
+                Some(content) if content.chars().count() == message.length as usize => content,
+                _ => {
+                    summary.skipped += 1;
+                    continue;
+                }
+            };
+
+            let last_five = recent.entry(message.user).or_default();
+            let base = if !content.is_empty() && last_five.contains(content) {
+                0.0
+            } else {
+                score_features(content, &stored_features(&message), &config)
+            };
+            last_five.push(content.clone());
+            if last_five.len() > 5 {
+                last_five.remove(0);
+            }
+
+            let score = base + message.engagement;
+            if (score - message.score).abs() > f32::EPSILON {
+                scores.push((message.snowflake, score));
+            }
+        }
+        summary.changed += scores.len() as u64;
+        repo.set_message_scores(&scores).await?;
+        after = Some(last);
+    }
+
+    repo.reconcile_aggregates(Some(guild)).await?;
+    repo.rebuild_rollups(Some(guild)).await?;
+    invalidate_guild(data, Some(guild)).await;
+
+    Ok(summary)
+}
+
+pub struct PruneSummary {
+    pub messages: u64,
+    pub reactions: u64,
+}
+
+/// Delete a user and everything stored about them in a guild: their messages, reactions,
+/// score adjustments, voice sessions and rollups. Replies to their messages are kept, and the
+/// engagement of messages they replied or reacted to is taken back off.
+pub async fn prune_user(
+    repo: &dyn Repository,
+    guild: u64,
+    user: u64,
+) -> Result<PruneSummary, Error> {
+    match repo.user(user).await? {
+        Some(stored) if stored.guild == guild as i64 => {}
+        _ => return Err(format!("user {} is not stored in guild {}", user, guild).into()),
+    }
+
+    let deleted = repo.delete_user(user as i64).await?;
+
+    for message in deleted.engaged {
+        repo.refresh_engagement(message).await?;
+    }
+    repo.reconcile_aggregates(Some(guild)).await?;
+    repo.rebuild_rollups(Some(guild)).await?;
+
+    Ok(PruneSummary {
+        messages: deleted.messages,
+        reactions: deleted.reactions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rank_core::message::Message;
+    use rank_core::repository::MemoryRepository;
+    use std::sync::Arc;
+
+    const GUILD: u64 = 1;
+    const ALICE: u64 = 100;
+    const BOB: u64 = 101;
+
+    async fn guild_data() -> Data {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, GUILD, "general".to_string())
+            .await
+            .unwrap();
+        for (user, name) in [(ALICE, "alice"), (BOB, "bob")] {
+            repo.insert_user(user, GUILD, name.to_string())
+                .await
+                .unwrap();
+        }
+        Data::load(Arc::new(repo), None).await.unwrap()
+    }
+
+    /// Store a message like the message handler does, keeping `stored` of its content
+    async fn post(
+        data: &Data,
+        id: u64,
+        author: u64,
+        content: &str,
+        stored: Option<&str>,
+        score: f32,
+        replys_to: Option<i64>,
+    ) {
+        let message = Message {
+            id,
+            author,
+            channel: 10,
+            content: content.to_string(),
+            timestamp: chrono::Utc::now().naive_utc(),
+            attachments: vec![],
+            mention_count: 0,
+        };
+        let repo = data.repo.as_ref();
+        repo.store_message(
+            &message,
+            GUILD,
+            stored.map(str::to_string),
+            score,
+            replys_to,
+        )
+        .await
+        .unwrap();
+        repo.add_to_guild(GUILD as i64, score, 1).await.unwrap();
+        repo.add_to_channel(10, score, 1).await.unwrap();
+        repo.add_to_user(author as i64, score, 1).await.unwrap();
+    }
+
+    async fn score(data: &Data, user: u64) -> f32 {
+        data.repo.user(user).await.unwrap().unwrap().score
+    }
+
+    #[tokio::test]
+    async fn rescore_skips_messages_without_their_full_content() {
+        let data = guild_data().await;
+        let text = "a long and thoughtful message about rust";
+        post(&data, 1000, ALICE, text, Some(text), 0.0, None).await;
+        // repeating the last message still scores nothing
+        post(&data, 1001, ALICE, text, Some(text), 0.0, None).await;
+        post(&data, 1002, BOB, text, None, 7.0, None).await;
+        post(&data, 1003, BOB, text, Some("a long"), 9.0, None).await;
+        // a running total that drifted
+        data.repo.add_to_user(BOB as i64, 50.0, 0).await.unwrap();
+
+        let summary = rescore_guild(&data, GUILD).await.unwrap();
+
+        assert_eq!(
+            (summary.messages, summary.changed, summary.skipped),
+            (4, 1, 2)
+        );
+        let rescored = data.repo.message(1000).await.unwrap().unwrap().score;
+        assert!(rescored > 0.0);
+        assert_eq!(score(&data, ALICE).await, rescored);
+        assert_eq!(data.repo.message(1003).await.unwrap().unwrap().score, 9.0);
+        assert_eq!(score(&data, BOB).await, 16.0);
+    }
+
+    #[tokio::test]
+    async fn prune_takes_back_the_engagement_of_replies() {
+        let data = guild_data().await;
+        post(&data, 1000, ALICE, "what do you think", None, 5.0, None).await;
+        post(
+            &data,
+            1001,
+            BOB,
+            "i think it is great",
+            None,
+            3.0,
+            Some(1000),
+        )
+        .await;
+        data.repo.refresh_engagement(1000).await.unwrap();
+        assert!(score(&data, ALICE).await > 5.0);
+
+        let summary = prune_user(data.repo.as_ref(), GUILD, BOB).await.unwrap();
+
+        assert_eq!(summary.messages, 1);
+        assert!(data.repo.user(BOB).await.unwrap().is_none());
+        assert_eq!(score(&data, ALICE).await, 5.0);
+        let guild = data.repo.guild(GUILD).await.unwrap().unwrap();
+        assert_eq!((guild.score, guild.message_count), (5.0, 1));
+    }
+
+    #[tokio::test]
+    async fn prune_refuses_users_of_other_guilds() {
+        let data = guild_data().await;
+        assert!(prune_user(data.repo.as_ref(), GUILD + 1, ALICE)
+            .await
+            .is_err());
+        assert!(data.repo.user(ALICE).await.unwrap().is_some());
+    }
+}
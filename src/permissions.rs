@@ -0,0 +1,81 @@
+use crate::{Context, Error};
+use poise::CreateReply;
+use rank_core::repository::Repository;
+use serenity::all::{Permissions, RoleId};
+use serenity::builder::CreateEmbed;
+
+/// The roles of a guild that may use the bot's admin commands
+pub async fn bot_manager_roles(repo: &dyn Repository, guild: u64) -> Result<Vec<RoleId>, Error> {
+    Ok(repo
+        .bot_manager_roles(guild)
+        .await?
+        .into_iter()
+        .map(RoleId::new)
+        .collect())
+}
+
+/// Command check for commands that change or delete data.
+/// Passes for members with Administrator or Manage Server, and for members with one of the
+/// guild's bot manager roles; everyone else gets told why they were denied.
+pub async fn bot_manager(ctx: Context<'_>) -> Result<bool, Error> {
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Ok(false),
+    };
+
+    let member = match ctx.author_member().await {
+        Some(member) => member,
+        None => return Ok(false),
+    };
+
+    // interactions carry the member's resolved permissions, fall back to the cache otherwise
+    let permissions = match member.permissions {
+        Some(permissions) => permissions,
+        None => ctx
+            .guild()
+            .map(|guild| guild.member_permissions(&member))
+            .unwrap_or_default(),
+    };
+
+    if permissions.contains(Permissions::ADMINISTRATOR)
+        || permissions.contains(Permissions::MANAGE_GUILD)
+    {
+        return Ok(true);
+    }
+
+    let roles = bot_manager_roles(ctx.data().repo.as_ref(), guild_id.get()).await?;
+    if member.roles.iter().any(|role| roles.contains(role)) {
+        return Ok(true);
+    }
+
+    let allowed = if roles.is_empty() {
+        "Administrator or Manage Server".to_string()
+    } else {
+        format!(
+            "Administrator, Manage Server or one of these roles: {}",
+            roles
+                .iter()
+                .map(|role| format!("<@&{}>", role))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .title("Permission denied")
+                    .description(format!(
+                        "`/{}` changes or exposes server data, you need {} to use it",
+                        ctx.command().qualified_name,
+                        allowed
+                    ))
+                    .colour(0xff0000),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+
+    Ok(false)
+}
@@ -0,0 +1,371 @@
+use crate::{Context, Error};
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use log::warn;
+use poise::CreateReply;
+use serenity::all::{ChannelId, EmojiId, MessageId};
+use serenity::builder::{CreateEmbed, CreateEmbedFooter, EditMessage};
+use serenity::cache::Cache;
+use serenity::http::Http;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Animated loading emoji used unless `LOADING_EMOJI` is set in auth.env
+const LOADING_EMOJI: (&str, u64) = ("recall_loading", 1163546685994188934);
+
+/// Animated emoji shown in the footer once everything is loaded
+const FINISHED_EMOJI: u64 = 1163591120840831046;
+
+/// Minimum time between two edits of the message, Discord rate limits edits per channel
+const DEBOUNCE: Duration = Duration::from_millis(1500);
+
+fn emoji_url(id: u64) -> String {
+    format!("https://cdn.discordapp.com/emojis/{}.gif?v=1", id)
+}
+
+/// What a field shows while it loads and the footer icon while loading
+#[derive(Debug, Clone)]
+pub struct LoadingIndicator {
+    pub loading: String,
+    pub loading_icon: Option<String>,
+}
+
+impl LoadingIndicator {
+    /// Plain text, works everywhere
+    pub fn text(loading: impl ToString) -> LoadingIndicator {
+        LoadingIndicator {
+            loading: loading.to_string(),
+            loading_icon: None,
+        }
+    }
+
+    /// An animated custom emoji if the bot can use it, plain text otherwise.
+    /// `emoji` is a name and id, the built in loading emoji when `None`.
+    pub fn emoji_or_text(cache: &Cache, emoji: Option<&(String, u64)>) -> LoadingIndicator {
+        let (name, id) = emoji
+            .map(|(name, id)| (name.as_str(), *id))
+            .unwrap_or(LOADING_EMOJI);
+        let available = cache.guilds().into_iter().any(|guild| {
+            cache
+                .guild(guild)
+                .map(|g| g.emojis.contains_key(&EmojiId::new(id)))
+                .unwrap_or(false)
+        });
+
+        if available {
+            LoadingIndicator {
+                loading: format!("<a:{}:{}>", name, id),
+                loading_icon: Some(emoji_url(id)),
+            }
+        } else {
+            LoadingIndicator::text("⏳")
+        }
+    }
+
+    /// Parse a `name:id` emoji from config
+    pub fn parse_emoji(emoji: &str) -> Option<(String, u64)> {
+        let (name, id) = emoji.trim().split_once(':')?;
+        Some((name.to_string(), id.parse().ok()?))
+    }
+}
+
+/// How a progressive embed looks
+#[derive(Debug, Clone)]
+pub struct EmbedStyle {
+    pub colour: u32,
+    /// Animated emoji shown in the footer once everything is loaded, only when the loading
+    /// emoji is shown too
+    pub finished_emoji: Option<u64>,
+    /// Footer while loading, followed by how much has loaded
+    pub loading_footer: String,
+    pub finished_footer: String,
+}
+
+impl Default for EmbedStyle {
+    fn default() -> Self {
+        EmbedStyle {
+            colour: 0x00ff00,
+            finished_emoji: Some(FINISHED_EMOJI),
+            loading_footer: "Loading stats...".to_string(),
+            finished_footer: "Finished loading stats".to_string(),
+        }
+    }
+}
+
+/// The message an embed was sent as, shared with the flush scheduled by a held back edit
+struct SentMessage {
+    http: Arc<Http>,
+    channel: ChannelId,
+    message: MessageId,
+    /// The latest embed held back by the debounce. Every edit holds the lock while it is sent,
+    /// so a flush can't overwrite a newer edit.
+    pending: Mutex<Option<CreateEmbed>>,
+}
+
+impl SentMessage {
+    async fn edit(&self, embed: CreateEmbed) -> Result<(), Error> {
+        self.channel
+            .edit_message(&*self.http, self.message, EditMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FieldState {
+    Loading,
+    Done(String),
+    Failed,
+}
+
+/// An embed that is sent with every field loading and filled in as values arrive.
+/// Edits are debounced so filling many fields doesn't run into rate limits, an edit held back
+/// is made once the debounce time passed and the last field always edits straight away.
+pub struct ProgressiveEmbed {
+    title: String,
+    style: EmbedStyle,
+    indicator: Option<LoadingIndicator>,
+    fields: Vec<(String, FieldState)>,
+    sent: Option<Arc<SentMessage>>,
+    last_edit: Instant,
+    dirty: bool,
+}
+
+impl ProgressiveEmbed {
+    pub fn new(title: impl ToString, fields: Vec<impl ToString>) -> ProgressiveEmbed {
+        ProgressiveEmbed::with_style(title, fields, EmbedStyle::default())
+    }
+
+    pub fn with_style(
+        title: impl ToString,
+        fields: Vec<impl ToString>,
+        style: EmbedStyle,
+    ) -> ProgressiveEmbed {
+        ProgressiveEmbed {
+            title: title.to_string(),
+            style,
+            indicator: None,
+            fields: fields
+                .iter()
+                .map(|field| (field.to_string(), FieldState::Loading))
+                .collect(),
+            sent: None,
+            last_edit: Instant::now(),
+            dirty: false,
+        }
+    }
+
+    /// Send the embed with every field loading
+    pub async fn send(mut self, ctx: Context<'_>) -> Result<ProgressiveEmbed, Error> {
+        self.indicator = Some(LoadingIndicator::emoji_or_text(
+            ctx.cache(),
+            ctx.data().loading_emoji.as_ref(),
+        ));
+        let reply = ctx.send(CreateReply::default().embed(self.embed())).await?;
+        let message = reply.message().await?;
+        self.sent = Some(Arc::new(SentMessage {
+            http: ctx.serenity_context().http.clone(),
+            channel: message.channel_id,
+            message: message.id,
+            pending: Mutex::new(None),
+        }));
+        self.last_edit = Instant::now();
+        Ok(self)
+    }
+
+    fn progress(&self) -> f32 {
+        let finished = self
+            .fields
+            .iter()
+            .filter(|(_, state)| !matches!(state, FieldState::Loading))
+            .count();
+        finished as f32 / self.fields.len().max(1) as f32
+    }
+
+    fn embed(&self) -> CreateEmbed {
+        let indicator = self
+            .indicator
+            .clone()
+            .unwrap_or_else(|| LoadingIndicator::text("⏳"));
+        let progress = self.progress();
+
+        let mut footer = if progress == 1.0 {
+            CreateEmbedFooter::new(&self.style.finished_footer)
+        } else {
+            CreateEmbedFooter::new(format!(
+                "{} {:.2}%",
+                self.style.loading_footer,
+                progress * 100.0
+            ))
+        };
+        let icon = match (progress == 1.0, indicator.loading_icon) {
+            (true, Some(_)) => self.style.finished_emoji.map(emoji_url),
+            (true, None) => None,
+            (false, icon) => icon,
+        };
+        if let Some(icon) = icon {
+            footer = footer.icon_url(icon);
+        }
+
+        CreateEmbed::default()
+            .title(&self.title)
+            .fields(self.fields.iter().map(|(name, state)| {
+                let value = match state {
+                    FieldState::Loading => indicator.loading.clone(),
+                    FieldState::Done(value) => value.clone(),
+                    FieldState::Failed => "⚠️ Failed to load".to_string(),
+                };
+                (name.clone(), value, true)
+            }))
+            .colour(self.style.colour)
+            .footer(footer)
+    }
+
+    fn update(&mut self, name: impl ToString, state: FieldState) {
+        let name = name.to_string();
+        match self.fields.iter_mut().find(|(field, _)| *field == name) {
+            Some((_, field_state)) => *field_state = state,
+            None => self.fields.push((name, state)),
+        }
+        self.dirty = true;
+    }
+
+    /// Edit the message if it changed. Within the debounce time of the last edit the change is
+    /// only shown once that passed, unless `force`.
+    async fn edit(&mut self, force: bool) -> Result<(), Error> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let sent = match &self.sent {
+            Some(sent) => sent.clone(),
+            None => return Ok(()),
+        };
+
+        if !force && self.last_edit.elapsed() < DEBOUNCE {
+            let mut pending = sent.pending.lock().await;
+            let scheduled = pending.is_some();
+            *pending = Some(self.embed());
+            if !scheduled {
+                let due = tokio::time::Instant::from_std(self.last_edit + DEBOUNCE);
+                let sent = sent.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep_until(due).await;
+                    let mut pending = sent.pending.lock().await;
+                    if let Some(embed) = pending.take() {
+                        if let Err(e) = sent.edit(embed).await {
+                            warn!("Failed to show a held back update: {:?}", e);
+                        }
+                    }
+                });
+            }
+            return Ok(());
+        }
+
+        let mut pending = sent.pending.lock().await;
+        *pending = None;
+        sent.edit(self.embed()).await?;
+        self.last_edit = Instant::now();
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Fill in a field
+    pub async fn set(&mut self, name: impl ToString, value: impl ToString) -> Result<(), Error> {
+        self.update(name, FieldState::Done(value.to_string()));
+        let finished = self.progress() == 1.0;
+        self.edit(finished).await
+    }
+
+    /// Show a field as failed, the error is logged rather than shown
+    pub async fn fail(&mut self, name: impl ToString, error: Error) -> Result<(), Error> {
+        let name = name.to_string();
+        warn!(
+            "Failed to load {} for \"{}\": {:?}",
+            name, self.title, error
+        );
+        self.update(name, FieldState::Failed);
+        let finished = self.progress() == 1.0;
+        self.edit(finished).await
+    }
+
+    /// Compute fields concurrently, filling each in as soon as it is ready.
+    /// A field whose future fails is shown as failed, the others still load.
+    pub async fn compute(
+        &mut self,
+        fields: Vec<(String, BoxFuture<'_, Result<String, Error>>)>,
+    ) -> Result<(), Error> {
+        let mut pending = fields
+            .into_iter()
+            .map(|(name, future)| async move { (name, future.await) })
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some((name, result)) = pending.next().await {
+            match result {
+                Ok(value) => self.set(name, value).await?,
+                Err(e) => self.fail(name, e).await?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark fields that never got a value as failed and show the final state
+    pub async fn finish(&mut self) -> Result<(), Error> {
+        for (_, state) in self.fields.iter_mut() {
+            if matches!(state, FieldState::Loading) {
+                *state = FieldState::Failed;
+                self.dirty = true;
+            }
+        }
+        self.edit(true).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(embed: &ProgressiveEmbed) -> serde_json::Value {
+        serde_json::to_value(embed.embed()).unwrap()
+    }
+
+    #[test]
+    fn uses_the_style_while_and_after_loading() {
+        let mut embed = ProgressiveEmbed::with_style(
+            "Comparison",
+            vec!["a", "b"],
+            EmbedStyle {
+                colour: 0x123456,
+                finished_emoji: Some(42),
+                loading_footer: "Comparing...".to_string(),
+                finished_footer: "Compared".to_string(),
+            },
+        );
+        embed.indicator = Some(LoadingIndicator {
+            loading: "<a:loading:1>".to_string(),
+            loading_icon: Some(emoji_url(1)),
+        });
+
+        embed.update("a", FieldState::Done("1".to_string()));
+        let loading = rendered(&embed);
+        assert_eq!(loading["color"], 0x123456);
+        assert_eq!(loading["footer"]["text"], "Comparing... 50.00%");
+        assert_eq!(loading["footer"]["icon_url"], emoji_url(1));
+        assert_eq!(loading["fields"][1]["value"], "<a:loading:1>");
+
+        embed.update("b", FieldState::Failed);
+        let finished = rendered(&embed);
+        assert_eq!(finished["footer"]["text"], "Compared");
+        assert_eq!(finished["footer"]["icon_url"], emoji_url(42));
+    }
+
+    #[test]
+    fn no_finished_icon_without_the_loading_emoji() {
+        let mut embed = ProgressiveEmbed::new("Stats", vec!["a"]);
+        embed.update("a", FieldState::Done("1".to_string()));
+
+        let finished = rendered(&embed);
+        assert_eq!(finished["footer"]["text"], "Finished loading stats");
+        assert!(finished["footer"].get("icon_url").is_none());
+    }
+}
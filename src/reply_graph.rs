@@ -0,0 +1,130 @@
+use crate::Error;
+use rank_core::replies::Thread;
+use rank_core::repository::Repository;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Who replies to whom in a guild, built from `messages.replys_to`
+pub struct ReplyGraph {
+    /// (from, to) -> number of replies
+    pub edges: HashMap<(i64, i64), i64>,
+    pub threads: Vec<Thread>,
+}
+
+impl ReplyGraph {
+    pub async fn load(repo: &dyn Repository, guild: u64) -> Result<ReplyGraph, Error> {
+        Ok(ReplyGraph {
+            edges: repo.reply_counts(guild).await?,
+            threads: repo.threads(guild).await?,
+        })
+    }
+
+    pub fn replies(&self, from: i64, to: i64) -> i64 {
+        self.edges.get(&(from, to)).copied().unwrap_or(0)
+    }
+
+    /// The `n` pairs with the most replies from one to the other
+    pub fn top_pairs(&self, n: usize) -> Vec<((i64, i64), i64)> {
+        let mut pairs = self
+            .edges
+            .iter()
+            .map(|(pair, replies)| (*pair, *replies))
+            .collect::<Vec<_>>();
+        pairs.sort_by_key(|p| Reverse(p.1));
+        pairs.truncate(n);
+        pairs
+    }
+
+    /// The `n` pairs that reply to each other the most, ranked by the smaller direction
+    pub fn mutual_pairs(&self, n: usize) -> Vec<((i64, i64), i64)> {
+        let mut pairs = self
+            .edges
+            .keys()
+            .filter(|(from, to)| from < to)
+            .map(|(from, to)| {
+                (
+                    (*from, *to),
+                    self.replies(*from, *to).min(self.replies(*to, *from)),
+                )
+            })
+            .filter(|(_, replies)| *replies > 0)
+            .collect::<Vec<_>>();
+        pairs.sort_by_key(|p| Reverse(p.1));
+        pairs.truncate(n);
+        pairs
+    }
+
+    /// Share of reply relationships that go both ways.
+    /// With a user, only the people that user replies to are counted.
+    pub fn reciprocity(&self, user: Option<i64>) -> Option<f32> {
+        let edges = self
+            .edges
+            .keys()
+            .filter(|(from, _)| user.map(|user| *from == user).unwrap_or(true))
+            .collect::<Vec<_>>();
+
+        if edges.is_empty() {
+            return None;
+        }
+
+        let mutual = edges
+            .iter()
+            .filter(|(from, to)| self.edges.contains_key(&(*to, *from)))
+            .count();
+
+        Some(mutual as f32 / edges.len() as f32)
+    }
+
+    /// The users `user` replies to most
+    pub fn replies_to(&self, user: i64, n: usize) -> Vec<(i64, i64)> {
+        self.neighbours(n, |(from, to)| (*from == user).then_some(*to))
+    }
+
+    /// The users that reply to `user` most
+    pub fn replied_by(&self, user: i64, n: usize) -> Vec<(i64, i64)> {
+        self.neighbours(n, |(from, to)| (*to == user).then_some(*from))
+    }
+
+    fn neighbours(&self, n: usize, f: impl Fn(&(i64, i64)) -> Option<i64>) -> Vec<(i64, i64)> {
+        let mut neighbours = self
+            .edges
+            .iter()
+            .filter_map(|(pair, replies)| f(pair).map(|other| (other, *replies)))
+            .collect::<Vec<_>>();
+        neighbours.sort_by_key(|p| Reverse(p.1));
+        neighbours.truncate(n);
+        neighbours
+    }
+
+    /// Replies sent per user
+    pub fn responders(&self) -> HashMap<i64, i64> {
+        let mut responders = HashMap::new();
+        for ((from, _), replies) in self.edges.iter() {
+            *responders.entry(*from).or_insert(0) += replies;
+        }
+        responders
+    }
+
+    /// Conversations that got at least one reply, per user that started them
+    pub fn starters(&self) -> HashMap<i64, i64> {
+        let mut starters = HashMap::new();
+        for thread in self.threads.iter() {
+            *starters.entry(thread.starter).or_insert(0) += 1;
+        }
+        starters
+    }
+
+    /// Average and deepest reply chain of the conversations, optionally only those started by `user`
+    pub fn thread_depth(&self, user: Option<i64>) -> Option<(f32, &Thread)> {
+        let threads = self
+            .threads
+            .iter()
+            .filter(|t| user.map(|user| t.starter == user).unwrap_or(true))
+            .collect::<Vec<_>>();
+
+        let deepest = threads.iter().max_by_key(|t| t.depth).copied()?;
+        let average = threads.iter().map(|t| t.depth as f32).sum::<f32>() / threads.len() as f32;
+
+        Some((average, deepest))
+    }
+}
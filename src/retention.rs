@@ -0,0 +1,211 @@
+use crate::Error;
+use log::{info, warn};
+use rank_core::model::GuildSettings;
+use rank_core::repository::Repository;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the retention policy of every guild is applied
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The content to store for a new message under a guild's retention policy.
+/// A retention of 0 days means content is never stored (content-less storage mode).
+pub fn content_to_store(settings: &GuildSettings, content: &str) -> Option<String> {
+    match (settings.retention_days, settings.retention_truncate) {
+        (Some(0), Some(chars)) => Some(content.chars().take(chars.max(0) as usize).collect()),
+        (Some(0), None) => None,
+        _ => Some(content.to_string()),
+    }
+}
+
+/// Null or truncate the content of a guild's messages that are older than its retention period.
+/// Score, timestamps, reply links and the precomputed features are left untouched.
+pub async fn apply_guild_retention(
+    repo: &dyn Repository,
+    settings: &GuildSettings,
+) -> Result<u64, Error> {
+    let days = match settings.retention_days {
+        Some(days) => days,
+        None => return Ok(0),
+    };
+
+    // a period reaching back past the earliest representable date has nothing old enough yet
+    let cutoff = match chrono::Duration::try_days(days as i64)
+        .and_then(|period| chrono::Utc::now().naive_utc().checked_sub_signed(period))
+    {
+        Some(cutoff) => cutoff,
+        None => return Ok(0),
+    };
+
+    repo.prune_content(settings.guild as u64, cutoff, settings.retention_truncate)
+        .await
+}
+
+/// Apply the retention policy of every guild that has one
+pub async fn apply_retention(repo: &dyn Repository) -> Result<(), Error> {
+    for settings in repo.retention_policies().await?.iter() {
+        let pruned = apply_guild_retention(repo, settings).await?;
+        if pruned > 0 {
+            info!(
+                "retention: pruned content of {} messages in guild {}",
+                pruned, settings.guild
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawn the background task that periodically applies retention policies
+pub fn spawn_retention_task(repo: Arc<dyn Repository>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = apply_retention(repo.as_ref()).await {
+                warn!("failed to apply retention policies: {:?}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDateTime, Utc};
+    use rank_core::message::Message;
+    use rank_core::model::StoredMessage;
+    use rank_core::repository::MemoryRepository;
+
+    fn settings(retention_days: Option<i32>, retention_truncate: Option<i32>) -> GuildSettings {
+        GuildSettings {
+            guild: 1,
+            retention_days,
+            retention_truncate,
+            voice_points_per_minute: 5.0,
+            scoring: None,
+            stopword_languages: None,
+        }
+    }
+
+    async fn store(repo: &MemoryRepository, id: u64, content: &str, sent: NaiveDateTime) {
+        let message = Message {
+            id,
+            author: 100,
+            channel: 10,
+            content: content.to_string(),
+            timestamp: sent,
+            attachments: vec![],
+            mention_count: 0,
+        };
+        let replys_to = (id > 1000).then_some(id as i64 - 1);
+        repo.store_message(&message, 1, Some(content.to_string()), 7.5, replys_to)
+            .await
+            .unwrap();
+    }
+
+    async fn guild_repo() -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(1, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, 1, "general".to_string())
+            .await
+            .unwrap();
+        repo.insert_user(100, 1, "alice".to_string()).await.unwrap();
+        repo
+    }
+
+    #[test]
+    fn content_less_mode_stores_nothing_or_a_prefix() {
+        let content = "héllo wörld";
+        assert_eq!(
+            content_to_store(&settings(None, None), content).as_deref(),
+            Some(content)
+        );
+        assert_eq!(
+            content_to_store(&settings(Some(30), Some(3)), content).as_deref(),
+            Some(content)
+        );
+        assert_eq!(content_to_store(&settings(Some(0), None), content), None);
+        // truncation counts characters, not bytes
+        assert_eq!(
+            content_to_store(&settings(Some(0), Some(4)), content).as_deref(),
+            Some("héll")
+        );
+        assert_eq!(
+            content_to_store(&settings(Some(0), Some(-1)), content).as_deref(),
+            Some("")
+        );
+    }
+
+    #[tokio::test]
+    async fn old_content_goes_but_score_and_replies_stay() {
+        let repo = guild_repo().await;
+        let old = Utc::now().naive_utc() - chrono::Duration::days(40);
+        store(&repo, 1000, "an old question", old).await;
+        store(&repo, 1001, "an old answer", old).await;
+        store(&repo, 1002, "ok", old).await;
+        store(&repo, 1003, "a fresh message", Utc::now().naive_utc()).await;
+        let before = repo.message(1001).await.unwrap().unwrap();
+
+        // "ok" is already short enough
+        assert_eq!(
+            apply_guild_retention(&repo, &settings(Some(30), Some(6)))
+                .await
+                .unwrap(),
+            2
+        );
+        let truncated = repo.message(1001).await.unwrap().unwrap();
+        assert_eq!(truncated.content.as_deref(), Some("an old"));
+        assert_eq!(
+            truncated,
+            StoredMessage {
+                content: Some("an old".to_string()),
+                ..before.clone()
+            }
+        );
+
+        assert_eq!(
+            apply_guild_retention(&repo, &settings(Some(30), None))
+                .await
+                .unwrap(),
+            3
+        );
+        let pruned = repo.message(1001).await.unwrap().unwrap();
+        assert_eq!(
+            pruned,
+            StoredMessage {
+                content: None,
+                ..before
+            }
+        );
+        assert_eq!(pruned.replys_to, Some(1000));
+        assert_eq!(
+            repo.message(1003)
+                .await
+                .unwrap()
+                .unwrap()
+                .content
+                .as_deref(),
+            Some("a fresh message")
+        );
+    }
+
+    #[tokio::test]
+    async fn periods_before_the_earliest_date_prune_nothing() {
+        let repo = guild_repo().await;
+        store(&repo, 1000, "a message", NaiveDateTime::MIN).await;
+
+        assert_eq!(
+            apply_guild_retention(&repo, &settings(Some(i32::MAX), None))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            apply_guild_retention(&repo, &settings(None, None))
+                .await
+                .unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use crate::Error;
+use entity::prelude::*;
+use log::{error, info};
+use migration::{Migrator, MigratorTrait};
+use sea_orm::sea_query::{ForeignKeyAction, TableRef};
+use sea_orm::{
+    ColumnTrait, ColumnType, DatabaseConnection, DbBackend, EntityTrait, FromQueryResult,
+    IdenStatic, Identity, Iterable, RelationTrait, Statement,
+};
+use std::collections::{HashMap, HashSet};
+
+const COLUMNS_SQL: &str = r#"
+SELECT table_name::text AS "table", column_name::text AS "column", data_type::text AS "data_type",
+    is_nullable = 'YES' AS "nullable"
+FROM information_schema.columns WHERE table_schema = current_schema()
+"#;
+
+const FOREIGN_KEYS_SQL: &str = r#"
+SELECT c.conrelid::regclass::text AS "table", a.attname::text AS "column",
+    c.confrelid::regclass::text AS "to_table", af.attname::text AS "to_column",
+    c.confdeltype::text AS "on_delete", c.confupdtype::text AS "on_update"
+FROM pg_constraint c
+JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+JOIN pg_attribute af ON af.attrelid = c.confrelid AND af.attnum = c.confkey[1]
+WHERE c.contype = 'f'
+"#;
+
+#[derive(Debug, FromQueryResult)]
+struct LiveColumn {
+    table: String,
+    column: String,
+    data_type: String,
+    nullable: bool,
+}
+
+#[derive(Debug, FromQueryResult, PartialEq, Eq, Hash)]
+struct LiveForeignKey {
+    table: String,
+    column: String,
+    to_table: String,
+    to_column: String,
+    on_delete: String,
+    on_update: String,
+}
+
+/// The schema of the database the bot is connected to
+struct LiveSchema {
+    columns: HashMap<(String, String), LiveColumn>,
+    foreign_keys: HashSet<LiveForeignKey>,
+}
+
+impl LiveSchema {
+    async fn load(db: &DatabaseConnection) -> Result<LiveSchema, Error> {
+        let columns =
+            LiveColumn::find_by_statement(Statement::from_string(DbBackend::Postgres, COLUMNS_SQL))
+                .all(db)
+                .await?;
+        let foreign_keys = LiveForeignKey::find_by_statement(Statement::from_string(
+            DbBackend::Postgres,
+            FOREIGN_KEYS_SQL,
+        ))
+        .all(db)
+        .await?;
+
+        Ok(LiveSchema {
+            columns: columns
+                .into_iter()
+                .map(|c| ((c.table.clone(), c.column.clone()), c))
+                .collect(),
+            foreign_keys: foreign_keys.into_iter().collect(),
+        })
+    }
+}
+
+/// The `data_type`s Postgres reports for a column type, `None` for types the bot doesn't use
+fn postgres_types(column_type: &ColumnType) -> Option<&'static [&'static str]> {
+    Some(match column_type {
+        ColumnType::BigInteger | ColumnType::BigUnsigned => &["bigint"],
+        ColumnType::Integer | ColumnType::Unsigned => &["integer"],
+        ColumnType::SmallInteger | ColumnType::SmallUnsigned => &["smallint"],
+        ColumnType::Float => &["real"],
+        ColumnType::Double => &["double precision"],
+        ColumnType::Boolean => &["boolean"],
+        ColumnType::Text => &["text"],
+        ColumnType::String(_) => &["character varying", "text"],
+        ColumnType::Date => &["date"],
+        ColumnType::DateTime | ColumnType::Timestamp => &["timestamp without time zone"],
+        ColumnType::TimestampWithTimeZone => &["timestamp with time zone"],
+        ColumnType::Json => &["json"],
+        ColumnType::JsonBinary => &["jsonb"],
+        _ => return None,
+    })
+}
+
+/// The `pg_constraint` code of a foreign key action, no action when unset
+fn action_code(action: Option<ForeignKeyAction>) -> &'static str {
+    match action {
+        Some(ForeignKeyAction::Cascade) => "c",
+        Some(ForeignKeyAction::SetNull) => "n",
+        Some(ForeignKeyAction::SetDefault) => "d",
+        Some(ForeignKeyAction::Restrict) => "r",
+        Some(ForeignKeyAction::NoAction) | None => "a",
+    }
+}
+
+fn action_name(code: &str) -> &'static str {
+    match code {
+        "c" => "cascade",
+        "n" => "set null",
+        "d" => "set default",
+        "r" => "restrict",
+        _ => "no action",
+    }
+}
+
+fn table_name(table: &TableRef) -> String {
+    match table {
+        TableRef::Table(table) | TableRef::SchemaTable(_, table) => table.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn column_name(column: &Identity) -> String {
+    match column {
+        Identity::Unary(column) => column.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Differences between an entity and its table
+fn check_entity<E: EntityTrait>(live: &LiveSchema, problems: &mut Vec<String>) {
+    let table = E::default().table_name().to_string();
+
+    for column in E::Column::iter() {
+        let name = column.as_str().to_string();
+        let def = column.def();
+        let live_column = match live.columns.get(&(table.clone(), name.clone())) {
+            Some(live_column) => live_column,
+            None => {
+                problems.push(format!("{}.{} is missing", table, name));
+                continue;
+            }
+        };
+
+        if let Some(types) = postgres_types(def.get_column_type()) {
+            if !types.contains(&live_column.data_type.as_str()) {
+                problems.push(format!(
+                    "{}.{} is {} but the entity expects {}",
+                    table,
+                    name,
+                    live_column.data_type,
+                    types.join(" or ")
+                ));
+            }
+        }
+        if def.is_null() != live_column.nullable {
+            problems.push(format!(
+                "{}.{} is {} but the entity expects it {}",
+                table,
+                name,
+                if live_column.nullable {
+                    "nullable"
+                } else {
+                    "not null"
+                },
+                if def.is_null() {
+                    "nullable"
+                } else {
+                    "not null"
+                }
+            ));
+        }
+    }
+
+    // belongs_to relations are the foreign keys of this table
+    for relation in E::Relation::iter().map(|r| r.def()).filter(|r| !r.is_owner) {
+        let expected = LiveForeignKey {
+            table: table_name(&relation.from_tbl),
+            column: column_name(&relation.from_col),
+            to_table: table_name(&relation.to_tbl),
+            to_column: column_name(&relation.to_col),
+            on_delete: action_code(relation.on_delete).to_string(),
+            on_update: action_code(relation.on_update).to_string(),
+        };
+        if !live.foreign_keys.contains(&expected) {
+            problems.push(format!(
+                "foreign key {}.{} -> {}.{} (on delete {}, on update {}) is missing",
+                expected.table,
+                expected.column,
+                expected.to_table,
+                expected.to_column,
+                action_name(&expected.on_delete),
+                action_name(&expected.on_update)
+            ));
+        }
+    }
+}
+
+/// Differences between every entity and the database
+async fn schema_problems(db: &DatabaseConnection) -> Result<Vec<String>, Error> {
+    let live = LiveSchema::load(db).await?;
+    let mut problems = vec![];
+
+    check_entity::<BotManagerRoles>(&live, &mut problems);
+    check_entity::<ChannelDaily>(&live, &mut problems);
+    check_entity::<Channels>(&live, &mut problems);
+    check_entity::<GuildSettings>(&live, &mut problems);
+    check_entity::<GuildStopwords>(&live, &mut problems);
+    check_entity::<Guilds>(&live, &mut problems);
+    check_entity::<MessageBigrams>(&live, &mut problems);
+    check_entity::<MessageTokens>(&live, &mut problems);
+    check_entity::<Messages>(&live, &mut problems);
+    check_entity::<Reactions>(&live, &mut problems);
+    check_entity::<ScoreAdjustments>(&live, &mut problems);
+    check_entity::<UserDaily>(&live, &mut problems);
+    check_entity::<Users>(&live, &mut problems);
+    check_entity::<VoiceSessions>(&live, &mut problems);
+
+    Ok(problems)
+}
+
+/// Apply pending migrations when `migrate` is set and make sure the tables match the entities,
+/// so a drifted or unmigrated database is caught at startup instead of by the first query that
+/// touches it
+pub async fn migrate_and_check(db: &DatabaseConnection, migrate: bool) -> Result<(), Error> {
+    if migrate {
+        Migrator::up(db, None).await?;
+    }
+
+    let problems = schema_problems(db).await?;
+    if problems.is_empty() {
+        info!("database schema matches the entities");
+        return Ok(());
+    }
+
+    for problem in problems.iter() {
+        error!("schema: {}", problem);
+    }
+    Err(format!(
+        "the database schema doesn't match the entities ({} differences)",
+        problems.len()
+    )
+    .into())
+}
@@ -0,0 +1,76 @@
+use crate::Error;
+use chrono::NaiveDate;
+use rank_core::repository::Repository;
+use rank_core::stats::StatsScope;
+
+/// Messages per UTC day from the day of `scope.since` to `today`, oldest first
+pub async fn daily_messages(
+    repo: &dyn Repository,
+    scope: &StatsScope,
+    today: NaiveDate,
+) -> Result<Vec<f32>, Error> {
+    let first_day = scope.since.date();
+    let mut days = vec![0.0; ((today - first_day).num_days() + 1).max(0) as usize];
+
+    for (day, messages) in repo.message_days(scope).await? {
+        let day = (day - first_day).num_days();
+        if let Some(slot) = days.get_mut(day as usize) {
+            *slot += messages as f32;
+        }
+    }
+
+    Ok(days)
+}
+
+/// Messages per UTC hour of the day
+pub async fn hourly_messages(
+    repo: &dyn Repository,
+    scope: &StatsScope,
+) -> Result<[f32; 24], Error> {
+    let mut hours = [0.0; 24];
+    for (_, hour, messages) in repo.message_hours(scope, "UTC").await? {
+        hours[hour as usize % 24] += messages as f32;
+    }
+    Ok(hours)
+}
+
+/// Messages per weekday (Monday first) and hour in the timezone `tz`, an IANA name
+pub async fn weekday_hour_messages(
+    repo: &dyn Repository,
+    scope: &StatsScope,
+    tz: &str,
+) -> Result<[[f32; 24]; 7], Error> {
+    let mut hours = [[0.0; 24]; 7];
+    for (weekday, hour, messages) in repo.message_hours(scope, tz).await? {
+        hours[weekday as usize % 7][hour as usize % 24] += messages as f32;
+    }
+    Ok(hours)
+}
+
+/// Change from `before` to `now` as a signed percentage
+pub fn growth(before: f32, now: f32) -> String {
+    if before == 0.0 {
+        if now == 0.0 {
+            "No change".to_string()
+        } else {
+            "New".to_string()
+        }
+    } else {
+        format!("{:+.0}%", (now - before) / before * 100.0)
+    }
+}
+
+/// The `n` hours with the most messages, busiest first
+pub fn busiest_hours(hours: &[f32; 24], n: usize) -> String {
+    let mut ranked = (0..24).filter(|h| hours[*h] > 0.0).collect::<Vec<usize>>();
+    ranked.sort_by(|a, b| hours[*b].partial_cmp(&hours[*a]).unwrap());
+    if ranked.is_empty() {
+        return "No messages yet".to_string();
+    }
+    ranked
+        .iter()
+        .take(n)
+        .map(|h| format!("{:02}:00 UTC - {} messages", h, hours[*h]))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
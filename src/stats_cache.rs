@@ -0,0 +1,270 @@
+use crate::{Data, Error};
+use chrono::{Duration, NaiveDate, Utc};
+use lru::LruCache;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::time::Instant;
+
+/// Most windowed stats kept in memory
+pub const CACHE_SIZE: usize = 10_000;
+
+/// Cached ranks go stale as other users post, so entries expire even without invalidation
+const MAX_AGE: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// A span of recent days that scores and ranks are computed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, poise::ChoiceParameter, clap::ValueEnum)]
+pub enum Window {
+    #[name = "Last 7 days"]
+    Week,
+    #[name = "Last 30 days"]
+    Month,
+    #[name = "Last 365 days"]
+    Year,
+}
+
+impl Window {
+    pub const ALL: [Window; 3] = [Window::Week, Window::Month, Window::Year];
+
+    pub fn days(&self) -> i64 {
+        match self {
+            Window::Week => 7,
+            Window::Month => 30,
+            Window::Year => 365,
+        }
+    }
+
+    /// First day in the window, today being the last
+    pub fn since(&self, today: NaiveDate) -> NaiveDate {
+        today - Duration::days(self.days() - 1)
+    }
+}
+
+/// A user's score, rank and message count in a window
+#[derive(Debug, Clone, Copy)]
+pub struct WindowStats {
+    pub score: f32,
+    pub rank: usize,
+    pub messages: i64,
+}
+
+pub type StatsCache = LruCache<(u64, i64, Window), (Instant, WindowStats)>;
+
+pub fn new_cache() -> StatsCache {
+    LruCache::new(NonZeroUsize::new(CACHE_SIZE).unwrap())
+}
+
+/// Score, rank and message count of a user in a window, from the cache in `data` or computed
+/// from the daily rollups. Computing a window caches it for every user in the guild.
+pub async fn window_stats(
+    data: &Data,
+    guild: u64,
+    user: i64,
+    window: Window,
+) -> Result<WindowStats, Error> {
+    if let Some((computed, stats)) = data.stats_cache.write().await.get(&(guild, user, window)) {
+        if computed.elapsed() < MAX_AGE {
+            return Ok(*stats);
+        }
+    }
+
+    let since = window.since(Utc::now().date_naive());
+    let mut totals = data
+        .repo
+        .user_totals_since(guild, since)
+        .await?
+        .into_iter()
+        .map(|(user, total)| (user, (total.score, total.messages)))
+        .collect::<HashMap<_, _>>();
+
+    for (user, delta) in data
+        .repo
+        .adjustments_since(guild, since.and_hms_opt(0, 0, 0).unwrap())
+        .await?
+    {
+        totals.entry(user).or_insert((0.0, 0)).0 += delta;
+    }
+
+    let mut scores = totals
+        .values()
+        .map(|(score, _)| *score)
+        .collect::<Vec<f32>>();
+    scores.sort_by(|a, b| b.partial_cmp(a).unwrap());
+    let stats = |score: f32, messages: i64| WindowStats {
+        score,
+        rank: scores.partition_point(|other| *other > score) + 1,
+        messages,
+    };
+
+    let (score, messages) = totals.get(&user).copied().unwrap_or((0.0, 0));
+
+    let now = Instant::now();
+    let mut cache = data.stats_cache.write().await;
+    for (other, (score, messages)) in totals.iter() {
+        cache.put((guild, *other, window), (now, stats(*score, *messages)));
+    }
+    let user_stats = stats(score, messages);
+    cache.put((guild, user, window), (now, user_stats));
+
+    Ok(user_stats)
+}
+
+/// Forget a user's cached windows, their numbers changed.
+/// The ranks cached for everyone else catch up once they expire.
+pub async fn invalidate_user(data: &Data, guild: u64, user: i64) {
+    let mut cache = data.stats_cache.write().await;
+    for window in Window::ALL {
+        cache.pop(&(guild, user, window));
+    }
+}
+
+/// Forget the cached windows of the author of a message whose score changed
+pub async fn invalidate_author(data: &Data, message: i64) -> Result<(), Error> {
+    if let Some(message) = data.repo.message(message as u64).await? {
+        if let Some(user) = data.repo.user(message.user as u64).await? {
+            invalidate_user(data, user.guild as u64, user.snowflake).await;
+        }
+    }
+    Ok(())
+}
+
+/// Forget every cached window of a guild, or of every guild, after changing many users at once.
+/// Only the cache of this process is cleared, a running bot sees changes made from the CLI once
+/// its entries expire.
+pub async fn invalidate_guild(data: &Data, guild: Option<u64>) {
+    let mut cache = data.stats_cache.write().await;
+    match guild {
+        Some(guild) => {
+            let stale = cache
+                .iter()
+                .map(|(key, _)| *key)
+                .filter(|key| key.0 == guild)
+                .collect::<Vec<_>>();
+            for key in stale {
+                cache.pop(&key);
+            }
+        }
+        None => cache.clear(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+    use rank_core::message::Message;
+    use rank_core::repository::{MemoryRepository, Repository};
+    use std::sync::Arc;
+
+    const GUILD: u64 = 1;
+    const ALICE: i64 = 100;
+    const BOB: i64 = 101;
+
+    async fn empty_data() -> Data {
+        let repo = MemoryRepository::new();
+        repo.insert_guild(GUILD, "guild".to_string()).await.unwrap();
+        repo.insert_channel(10, GUILD, "general".to_string())
+            .await
+            .unwrap();
+        for (user, name) in [(ALICE, "alice"), (BOB, "bob")] {
+            repo.insert_user(user as u64, GUILD, name.to_string())
+                .await
+                .unwrap();
+        }
+        Data::load(Arc::new(repo), None).await.unwrap()
+    }
+
+    async fn post(data: &Data, id: u64, author: i64, score: f32, sent: NaiveDateTime) {
+        let message = Message {
+            id,
+            author: author as u64,
+            channel: 10,
+            content: "a message".to_string(),
+            timestamp: sent,
+            attachments: vec![],
+            mention_count: 0,
+        };
+        data.repo
+            .store_message(&message, GUILD, None, score, None)
+            .await
+            .unwrap();
+    }
+
+    fn days_ago(days: i64) -> NaiveDateTime {
+        Utc::now().naive_utc() - Duration::days(days)
+    }
+
+    #[tokio::test]
+    async fn windows_rank_the_days_inside_them() {
+        let data = empty_data().await;
+        post(&data, 1000, ALICE, 5.0, days_ago(0)).await;
+        post(&data, 1001, ALICE, 5.0, days_ago(6)).await;
+        post(&data, 1002, BOB, 8.0, days_ago(1)).await;
+        post(&data, 1003, BOB, 50.0, days_ago(20)).await;
+
+        let week = window_stats(&data, GUILD, ALICE, Window::Week)
+            .await
+            .unwrap();
+        assert_eq!((week.score, week.rank, week.messages), (10.0, 1, 2));
+        let month = window_stats(&data, GUILD, ALICE, Window::Month)
+            .await
+            .unwrap();
+        assert_eq!((month.score, month.rank), (10.0, 2));
+
+        // computing alice's window cached bob's too
+        let mut cache = data.stats_cache.write().await;
+        let (_, bob) = cache.get(&(GUILD, BOB, Window::Week)).unwrap();
+        assert_eq!((bob.score, bob.rank, bob.messages), (8.0, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn cached_until_invalidated() {
+        let data = empty_data().await;
+        post(&data, 1000, ALICE, 5.0, days_ago(0)).await;
+        post(&data, 1001, BOB, 1.0, days_ago(0)).await;
+        window_stats(&data, GUILD, ALICE, Window::Week)
+            .await
+            .unwrap();
+
+        post(&data, 1002, ALICE, 5.0, days_ago(0)).await;
+        post(&data, 1003, BOB, 20.0, days_ago(0)).await;
+        let cached = window_stats(&data, GUILD, ALICE, Window::Week)
+            .await
+            .unwrap();
+        assert_eq!((cached.score, cached.messages), (5.0, 1));
+
+        invalidate_user(&data, GUILD, ALICE).await;
+        let fresh = window_stats(&data, GUILD, ALICE, Window::Week)
+            .await
+            .unwrap();
+        assert_eq!((fresh.score, fresh.rank, fresh.messages), (10.0, 2, 2));
+
+        // recomputing alice's window refreshed everyone in the guild
+        let bob = window_stats(&data, GUILD, BOB, Window::Week).await.unwrap();
+        assert_eq!(bob.score, 21.0);
+    }
+
+    #[tokio::test]
+    async fn invalidating_a_guild_leaves_the_others() {
+        let data = empty_data().await;
+        let stats = WindowStats {
+            score: 1.0,
+            rank: 1,
+            messages: 1,
+        };
+        {
+            let mut cache = data.stats_cache.write().await;
+            for guild in [GUILD, GUILD + 1] {
+                cache.put((guild, ALICE, Window::Week), (Instant::now(), stats));
+            }
+        }
+
+        invalidate_guild(&data, Some(GUILD)).await;
+        let mut cache = data.stats_cache.write().await;
+        assert!(cache.get(&(GUILD, ALICE, Window::Week)).is_none());
+        assert!(cache.get(&(GUILD + 1, ALICE, Window::Week)).is_some());
+        drop(cache);
+
+        invalidate_guild(&data, None).await;
+        assert!(data.stats_cache.read().await.is_empty());
+    }
+}
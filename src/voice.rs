@@ -0,0 +1,373 @@
+use crate::stats_cache::invalidate_user;
+use crate::{guild_settings, Data, Error};
+use log::{info, warn};
+use poise::serenity_prelude as serenity;
+use rank_core::repository::Repository;
+use serenity::all::{ChannelId, GuildId, UserId, VoiceState};
+use serenity::cache::Cache;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How often members in voice are awarded points
+const VOICE_TICK: Duration = Duration::from_secs(60);
+
+/// A member in a voice channel at the time of a tick
+struct VoiceMember {
+    user: UserId,
+    name: String,
+    channel: ChannelId,
+    muted: bool,
+    deafened: bool,
+    active: bool,
+}
+
+fn muted(state: &VoiceState) -> bool {
+    state.mute || state.self_mute
+}
+
+fn deafened(state: &VoiceState) -> bool {
+    state.deaf || state.self_deaf
+}
+
+/// The members of a guild that are in voice, with whether they earn points right now.
+/// Members that are muted, deafened, in the AFK channel or without another person in
+/// their channel don't.
+fn voice_members(cache: &Cache, guild_id: GuildId) -> (String, Vec<VoiceMember>) {
+    let guild = match cache.guild(guild_id) {
+        Some(guild) => guild,
+        None => return (String::new(), vec![]),
+    };
+
+    let afk_channel = guild.afk_metadata.as_ref().map(|afk| afk.afk_channel_id);
+    let is_bot = |user: &UserId| guild.members.get(user).map(|m| m.user.bot).unwrap_or(false);
+
+    let mut people = HashMap::new();
+    for state in guild.voice_states.values() {
+        if let Some(channel) = state.channel_id {
+            if !is_bot(&state.user_id) {
+                *people.entry(channel).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let members = guild
+        .voice_states
+        .values()
+        .filter(|state| !is_bot(&state.user_id))
+        .filter_map(|state| {
+            let channel = state.channel_id?;
+            Some(VoiceMember {
+                user: state.user_id,
+                name: guild
+                    .members
+                    .get(&state.user_id)
+                    .map(|m| m.user.name.clone())
+                    .unwrap_or_else(|| state.user_id.to_string()),
+                channel,
+                muted: muted(state),
+                deafened: deafened(state),
+                active: !muted(state)
+                    && !deafened(state)
+                    && Some(channel) != afk_channel
+                    && people.get(&channel).copied().unwrap_or(0) > 1,
+            })
+        })
+        .collect();
+
+    (guild.name.clone(), members)
+}
+
+async fn open_session(
+    repo: &dyn Repository,
+    guild: u64,
+    member: &VoiceMember,
+) -> Result<(), Error> {
+    repo.ensure_user(member.user.get(), guild, &member.name)
+        .await?;
+    repo.open_voice_session(
+        guild,
+        member.user.get() as i64,
+        member.channel.get() as i64,
+        member.muted,
+        member.deafened,
+    )
+    .await
+}
+
+/// Record a join, leave, move, mute or deafen by ending the member's current session and
+/// starting a new one if they are still in a channel
+pub async fn handle_voice_state(
+    data: &Data,
+    cache: &Cache,
+    state: &VoiceState,
+) -> Result<(), Error> {
+    let guild_id = match state.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    if state.member.as_ref().map(|m| m.user.bot).unwrap_or(false) {
+        return Ok(());
+    }
+
+    data.repo
+        .close_voice_session(guild_id.get(), state.user_id.get() as i64)
+        .await?;
+
+    if let Some(channel) = state.channel_id {
+        let guild_name = cache
+            .guild(guild_id)
+            .map(|g| g.name.clone())
+            .unwrap_or_default();
+        data.repo.ensure_guild(guild_id.get(), &guild_name).await?;
+
+        let member = VoiceMember {
+            user: state.user_id,
+            name: state
+                .member
+                .as_ref()
+                .map(|m| m.user.name.clone())
+                .unwrap_or_else(|| state.user_id.to_string()),
+            channel,
+            muted: muted(state),
+            deafened: deafened(state),
+            active: false,
+        };
+        open_session(data.repo.as_ref(), guild_id.get(), &member).await?;
+    }
+
+    Ok(())
+}
+
+/// Award one tick of voice activity to every active member of a guild
+async fn award_guild(data: &Data, cache: &Cache, guild_id: GuildId) -> Result<(), Error> {
+    let (guild_name, members) = voice_members(cache, guild_id);
+    if members.is_empty() {
+        return Ok(());
+    }
+
+    let guild = guild_id.get();
+    data.repo.ensure_guild(guild, &guild_name).await?;
+    let settings = guild_settings::get(data, guild).await?;
+    let points = settings.voice_points_per_minute * VOICE_TICK.as_secs_f32() / 60.0;
+
+    for member in members.iter() {
+        let user = member.user.get() as i64;
+
+        // members that were in voice before the bot started have no session yet
+        if !data.repo.has_open_voice_session(guild, user).await? {
+            open_session(data.repo.as_ref(), guild, member).await?;
+        }
+
+        if !member.active {
+            continue;
+        }
+
+        data.repo
+            .add_voice_activity(guild, user, VOICE_TICK.as_secs() as i32, points)
+            .await?;
+
+        if points != 0.0 {
+            data.repo.add_to_user(user, points, 0).await?;
+            data.repo.add_to_guild(guild as i64, points, 0).await?;
+            invalidate_user(data, guild, user).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Award voice points every minute for as long as the bot runs
+pub fn spawn_voice_task(ctx: serenity::Context, data: Data) {
+    tokio::spawn(async move {
+        // sessions left open by a previous run end at the last time they earned points
+        match data.repo.close_stale_voice_sessions().await {
+            Ok(closed) => info!("Closed {} stale voice sessions", closed),
+            Err(e) => warn!("Failed to close stale voice sessions: {:?}", e),
+        }
+
+        let mut interval = tokio::time::interval(VOICE_TICK);
+        // the first tick completes immediately, skip it so nobody is paid for a minute they
+        // haven't spent yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            for guild_id in ctx.cache.guilds() {
+                if let Err(e) = award_guild(&data, &ctx.cache, guild_id).await {
+                    warn!("Failed to award voice points in {}: {:?}", guild_id, e);
+                }
+            }
+        }
+    });
+}
+
+/// Format a number of seconds as hours and minutes
+pub fn format_voice_time(seconds: i64) -> String {
+    format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guild_settings::{get, save};
+    use crate::serenity::{CacheUpdate, GuildCreateEvent};
+    use rank_core::repository::MemoryRepository;
+    use std::sync::Arc;
+
+    const GUILD: u64 = 1;
+    const AFK: u64 = 30;
+    const BOT: u64 = 99;
+
+    async fn empty_data() -> Data {
+        Data::load(Arc::new(MemoryRepository::new()), None)
+            .await
+            .unwrap()
+    }
+
+    fn voice_state(user: u64, channel: Option<u64>, self_mute: bool) -> serde_json::Value {
+        serde_json::json!({
+            "channel_id": channel.map(|c| c.to_string()),
+            "deaf": false,
+            "guild_id": GUILD.to_string(),
+            "mute": false,
+            "self_deaf": false,
+            "self_mute": self_mute,
+            "self_video": false,
+            "session_id": "session",
+            "suppress": false,
+            "user_id": user.to_string(),
+            "request_to_speak_timestamp": null,
+        })
+    }
+
+    fn member(user: u64) -> serde_json::Value {
+        serde_json::json!({
+            "user": {
+                "id": user.to_string(),
+                "username": format!("user{}", user),
+                "discriminator": "0",
+                "avatar": null,
+                "bot": user == BOT,
+            },
+            "roles": [],
+            "joined_at": "2024-01-01T00:00:00Z",
+            "deaf": false,
+            "mute": false,
+            "flags": 0,
+        })
+    }
+
+    /// A cache holding the guild with these members in voice, as `(user, channel, self muted)`
+    fn cache_with_voice(states: &[(u64, u64, bool)]) -> Cache {
+        let mut event: GuildCreateEvent = serde_json::from_value(serde_json::json!({
+            "id": GUILD.to_string(),
+            "name": "guild",
+            "owner_id": "1",
+            "afk_channel_id": AFK.to_string(),
+            "afk_timeout": 300,
+            "roles": [],
+            "emojis": [],
+            "features": [],
+            "channels": [],
+            "members": states.iter().map(|(user, _, _)| member(*user)).collect::<Vec<_>>(),
+            "voice_states": states
+                .iter()
+                .map(|(user, channel, muted)| voice_state(*user, Some(*channel), *muted))
+                .collect::<Vec<_>>(),
+            "verification_level": 0,
+            "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "mfa_level": 0,
+            "system_channel_flags": 0,
+            "premium_tier": 0,
+            "preferred_locale": "en-US",
+            "nsfw_level": 0,
+            "premium_progress_bar_enabled": false,
+            "stickers": [],
+            "joined_at": "2024-01-01T00:00:00Z",
+            "large": false,
+            "member_count": states.len(),
+            "threads": [],
+            "presences": [],
+            "stage_instances": [],
+            "guild_scheduled_events": [],
+        }))
+        .unwrap();
+        let cache = Cache::new();
+        event.update(&cache);
+        cache
+    }
+
+    #[tokio::test]
+    async fn only_active_members_earn_points() {
+        let data = empty_data().await;
+        let mut settings = get(&data, GUILD).await.unwrap();
+        settings.voice_points_per_minute = 3.0;
+        save(&data, settings).await.unwrap();
+        let cache = cache_with_voice(&[
+            // talking to each other
+            (100, 20, false),
+            (101, 20, false),
+            // muted in the same channel
+            (102, 20, true),
+            // alone with a bot
+            (103, 21, false),
+            (BOT, 21, false),
+            // in the AFK channel, together
+            (104, AFK, false),
+            (105, AFK, false),
+        ]);
+
+        award_guild(&data, &cache, GuildId::new(GUILD))
+            .await
+            .unwrap();
+
+        let mut totals = data.repo.voice_totals(GUILD, None, 0, 10).await.unwrap();
+        totals.sort_by_key(|total| total.0);
+        assert_eq!(
+            totals,
+            [
+                (100, 60, 3.0),
+                (101, 60, 3.0),
+                (102, 0, 0.0),
+                (103, 0, 0.0),
+                (104, 0, 0.0),
+                (105, 0, 0.0)
+            ]
+        );
+        assert_eq!(data.repo.user(100).await.unwrap().unwrap().score, 3.0);
+        // everyone but the bot has a session now, earning or not
+        for user in [100, 101, 102, 103, 104, 105] {
+            assert!(data.repo.has_open_voice_session(GUILD, user).await.unwrap());
+        }
+        assert!(data.repo.user(BOT).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn joining_and_leaving_opens_and_closes_a_session() {
+        let data = empty_data().await;
+        let cache = Cache::new();
+        let update = |channel| -> VoiceState {
+            serde_json::from_value(voice_state(100, channel, false)).unwrap()
+        };
+
+        handle_voice_state(&data, &cache, &update(Some(20)))
+            .await
+            .unwrap();
+        // unknown guilds and users are stored on joining
+        assert!(data.repo.guild(GUILD).await.unwrap().is_some());
+        assert_eq!(data.repo.user(100).await.unwrap().unwrap().name, "100");
+        assert!(data.repo.has_open_voice_session(GUILD, 100).await.unwrap());
+
+        handle_voice_state(&data, &cache, &update(None))
+            .await
+            .unwrap();
+        assert!(!data.repo.has_open_voice_session(GUILD, 100).await.unwrap());
+    }
+
+    #[test]
+    fn formats_hours_and_minutes() {
+        assert_eq!(format_voice_time(59), "0h 0m");
+        assert_eq!(format_voice_time(3 * 3600 + 25 * 60 + 10), "3h 25m");
+    }
+}
@@ -0,0 +1,88 @@
+use crate::Error;
+use rank_core::repository::Repository;
+use rank_core::words::WordScope;
+use std::collections::{HashMap, HashSet};
+
+/// Most popular words fetched before common words are filtered out
+const CANDIDATES: u64 = 1000;
+
+/// Words used fewer times than this are too rare to say anything about a user
+pub const MIN_USES: i64 = 3;
+
+fn uncommon(word: &str, common_words: &HashSet<String>) -> bool {
+    word.chars().count() > 3 && !common_words.contains(word)
+}
+
+/// The most used words that aren't common words
+pub async fn top_words(
+    repo: &dyn Repository,
+    scope: &WordScope,
+    common_words: &HashSet<String>,
+    n: usize,
+) -> Result<Vec<(String, i64)>, Error> {
+    Ok(repo
+        .word_counts(scope, Some(CANDIDATES))
+        .await?
+        .into_iter()
+        .filter(|(word, _)| uncommon(word, common_words))
+        .take(n)
+        .collect())
+}
+
+/// The most used pairs of words, leaving out pairs made only of common words
+pub async fn top_bigrams(
+    repo: &dyn Repository,
+    scope: &WordScope,
+    common_words: &HashSet<String>,
+    n: usize,
+) -> Result<Vec<(String, i64)>, Error> {
+    Ok(repo
+        .bigram_counts(scope, CANDIDATES)
+        .await?
+        .into_iter()
+        .filter(|(bigram, _)| bigram.split(' ').any(|w| uncommon(w, common_words)))
+        .take(n)
+        .collect())
+}
+
+/// Words a user uses more than the rest of the guild, TF-IDF style: how much more often the
+/// user says a word than the guild does, weighted by how often the user says it.
+/// Words the user used fewer than `MIN_USES` times are left out so small samples don't
+/// produce noise.
+pub async fn distinctive_words(
+    repo: &dyn Repository,
+    scope: &WordScope,
+    common_words: &HashSet<String>,
+    n: usize,
+) -> Result<Vec<(String, f32)>, Error> {
+    let user_counts = repo.word_counts(scope, None).await?;
+    let user_total = user_counts.iter().map(|(_, c)| c).sum::<i64>() as f32;
+
+    let guild_scope = WordScope {
+        user: None,
+        ..*scope
+    };
+    let guild_counts = repo
+        .word_counts(&guild_scope, None)
+        .await?
+        .into_iter()
+        .collect::<HashMap<String, i64>>();
+    let guild_total = guild_counts.values().sum::<i64>() as f32;
+
+    let mut words = user_counts
+        .into_iter()
+        .filter(|(word, uses)| *uses >= MIN_USES && uncommon(word, common_words))
+        .map(|(word, uses)| {
+            let user_share = uses as f32 / user_total;
+            let guild_share =
+                (guild_counts.get(&word).copied().unwrap_or(0) + 1) as f32 / (guild_total + 1.0);
+            let score = user_share / guild_share * (uses as f32).ln();
+            (word, score)
+        })
+        .filter(|(_, score)| *score > 1.0)
+        .collect::<Vec<_>>();
+
+    words.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    words.truncate(n);
+    Ok(words)
+}